@@ -0,0 +1,272 @@
+use std::collections::{BTreeMap, BTreeSet};
+use std::fs::{self, File};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex, MutexGuard};
+use std::time::{Duration, SystemTime};
+
+/// Storage the note store reads from and writes to.
+///
+/// `FsBackend` talks to the real filesystem; `MemoryBackend` keeps
+/// everything in a map so the store can be driven without touching disk.
+pub trait Backend {
+    fn create_dir_all(&mut self, dir: &Path) -> io::Result<()>;
+    /// Files directly inside `dir`, in no particular order.
+    fn list(&self, dir: &Path) -> io::Result<Vec<PathBuf>>;
+    /// Folders directly inside `dir`, in no particular order.
+    fn list_dirs(&self, dir: &Path) -> io::Result<Vec<PathBuf>>;
+    fn read(&self, path: &Path) -> io::Result<String>;
+    fn write(&mut self, path: &Path, contents: &str) -> io::Result<()>;
+    fn rename(&mut self, from: &Path, to: &Path) -> io::Result<()>;
+    fn remove(&mut self, path: &Path) -> io::Result<()>;
+    /// Removes `dir`, which must be empty.
+    fn remove_dir(&mut self, dir: &Path) -> io::Result<()>;
+    fn exists(&self, path: &Path) -> bool;
+    fn modified(&self, path: &Path) -> io::Result<SystemTime>;
+}
+
+#[derive(Default)]
+pub struct FsBackend;
+
+impl Backend for FsBackend {
+    fn create_dir_all(&mut self, dir: &Path) -> io::Result<()> {
+        fs::create_dir_all(dir)
+    }
+
+    fn list(&self, dir: &Path) -> io::Result<Vec<PathBuf>> {
+        let mut files = vec![];
+        for entry in fs::read_dir(dir)? {
+            let path = entry?.path();
+            if path.is_file() {
+                files.push(path);
+            }
+        }
+        Ok(files)
+    }
+
+    fn list_dirs(&self, dir: &Path) -> io::Result<Vec<PathBuf>> {
+        let mut dirs = vec![];
+        for entry in fs::read_dir(dir)? {
+            let path = entry?.path();
+            if path.is_dir() {
+                dirs.push(path);
+            }
+        }
+        Ok(dirs)
+    }
+
+    fn read(&self, path: &Path) -> io::Result<String> {
+        fs::read_to_string(path)
+    }
+
+    fn write(&mut self, path: &Path, contents: &str) -> io::Result<()> {
+        write_atomic(path, contents.as_bytes())
+    }
+
+    fn rename(&mut self, from: &Path, to: &Path) -> io::Result<()> {
+        fs::rename(from, to)
+    }
+
+    fn remove(&mut self, path: &Path) -> io::Result<()> {
+        fs::remove_file(path)
+    }
+
+    fn remove_dir(&mut self, dir: &Path) -> io::Result<()> {
+        fs::remove_dir(dir)
+    }
+
+    fn exists(&self, path: &Path) -> bool {
+        path.exists()
+    }
+
+    fn modified(&self, path: &Path) -> io::Result<SystemTime> {
+        fs::metadata(path)?.modified()
+    }
+}
+
+/// Replaces `path` with `contents` so that a crash or full disk leaves
+/// either the old file or the new one, never a truncated mix: the data goes
+/// to a temporary sibling, is flushed to disk, then renamed over `path`.
+pub fn write_atomic(path: &Path, contents: &[u8]) -> io::Result<()> {
+    let file_name = path
+        .file_name()
+        .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidInput, "path has no file name"))?;
+    let temp = path.with_file_name(format!(
+        ".{}.{}.tmp",
+        file_name.to_string_lossy(),
+        std::process::id()
+    ));
+
+    let result = (|| {
+        let mut file = File::create(&temp)?;
+        file.write_all(contents)?;
+        file.sync_all()?;
+        drop(file);
+        fs::rename(&temp, path)
+    })();
+    if result.is_err() {
+        let _ = fs::remove_file(&temp);
+        return result;
+    }
+
+    // Make the rename itself durable. Directories cannot be opened for
+    // syncing on every platform, so this part is best effort.
+    if let Some(dir) = path.parent().filter(|dir| !dir.as_os_str().is_empty()) {
+        if let Ok(dir) = File::open(dir) {
+            let _ = dir.sync_all();
+        }
+    }
+    Ok(())
+}
+
+/// In-memory files. Clones share the same files, so a test can keep a
+/// handle and change files "externally" while a store owns another.
+#[derive(Clone, Default)]
+pub struct MemoryBackend {
+    state: Arc<Mutex<MemoryState>>,
+}
+
+#[derive(Default)]
+struct MemoryState {
+    files: BTreeMap<PathBuf, (String, SystemTime)>,
+    /// Folders created explicitly; the folders files live in exist implicitly.
+    dirs: BTreeSet<PathBuf>,
+    /// Ticks once per write so modification times always move forward.
+    clock: u64,
+}
+
+impl MemoryState {
+    fn put(&mut self, path: PathBuf, contents: String) {
+        self.clock += 1;
+        let modified = SystemTime::UNIX_EPOCH + Duration::from_secs(self.clock);
+        self.files.insert(path, (contents, modified));
+    }
+
+    fn is_dir(&self, path: &Path) -> bool {
+        self.dirs.contains(path) || self.files.keys().any(|file| file.starts_with(path) && file != path)
+    }
+}
+
+impl MemoryBackend {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Seeds or replaces a file, e.g. to simulate edits made by another program.
+    pub fn insert(&self, path: impl Into<PathBuf>, contents: impl Into<String>) {
+        self.lock().put(path.into(), contents.into());
+    }
+
+    fn lock(&self) -> MutexGuard<'_, MemoryState> {
+        self.state.lock().unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+
+    fn not_found(path: &Path) -> io::Error {
+        io::Error::new(io::ErrorKind::NotFound, format!("{} not found", path.display()))
+    }
+}
+
+impl Backend for MemoryBackend {
+    fn create_dir_all(&mut self, dir: &Path) -> io::Result<()> {
+        let mut state = self.lock();
+        for dir in dir.ancestors().filter(|dir| !dir.as_os_str().is_empty()) {
+            state.dirs.insert(dir.to_path_buf());
+        }
+        Ok(())
+    }
+
+    fn list(&self, dir: &Path) -> io::Result<Vec<PathBuf>> {
+        Ok(self
+            .lock()
+            .files
+            .keys()
+            .filter(|path| path.parent() == Some(dir))
+            .cloned()
+            .collect())
+    }
+
+    fn list_dirs(&self, dir: &Path) -> io::Result<Vec<PathBuf>> {
+        let state = self.lock();
+        let implied = state.files.keys().flat_map(|file| file.ancestors().skip(1));
+        let dirs: BTreeSet<&Path> = implied
+            .chain(state.dirs.iter().map(PathBuf::as_path))
+            .filter(|path| path.parent() == Some(dir))
+            .collect();
+        Ok(dirs.into_iter().map(Path::to_path_buf).collect())
+    }
+
+    fn read(&self, path: &Path) -> io::Result<String> {
+        self.lock()
+            .files
+            .get(path)
+            .map(|(contents, _)| contents.clone())
+            .ok_or_else(|| Self::not_found(path))
+    }
+
+    fn write(&mut self, path: &Path, contents: &str) -> io::Result<()> {
+        self.lock().put(path.to_path_buf(), contents.to_string());
+        Ok(())
+    }
+
+    fn rename(&mut self, from: &Path, to: &Path) -> io::Result<()> {
+        let mut state = self.lock();
+        if let Some(file) = state.files.remove(from) {
+            state.files.insert(to.to_path_buf(), file);
+            return Ok(());
+        }
+        if !state.is_dir(from) {
+            return Err(Self::not_found(from));
+        }
+        // A folder takes everything inside it along
+        let moved = |path: &Path| match path.strip_prefix(from) {
+            Ok(rest) if !rest.as_os_str().is_empty() => to.join(rest),
+            _ => to.to_path_buf(),
+        };
+        let files: Vec<PathBuf> = state.files.keys().filter(|path| path.starts_with(from)).cloned().collect();
+        for path in files {
+            if let Some(file) = state.files.remove(&path) {
+                state.files.insert(moved(&path), file);
+            }
+        }
+        let dirs: Vec<PathBuf> = state.dirs.iter().filter(|path| path.starts_with(from)).cloned().collect();
+        for path in dirs {
+            state.dirs.remove(&path);
+            state.dirs.insert(moved(&path));
+        }
+        Ok(())
+    }
+
+    fn remove(&mut self, path: &Path) -> io::Result<()> {
+        self.lock()
+            .files
+            .remove(path)
+            .map(|_| ())
+            .ok_or_else(|| Self::not_found(path))
+    }
+
+    fn remove_dir(&mut self, dir: &Path) -> io::Result<()> {
+        let mut state = self.lock();
+        if !state.is_dir(dir) {
+            return Err(Self::not_found(dir));
+        }
+        let occupied = state.files.keys().chain(&state.dirs).any(|path| path.starts_with(dir) && path != dir);
+        if occupied {
+            return Err(io::Error::new(io::ErrorKind::DirectoryNotEmpty, format!("{} is not empty", dir.display())));
+        }
+        state.dirs.remove(dir);
+        Ok(())
+    }
+
+    fn exists(&self, path: &Path) -> bool {
+        let state = self.lock();
+        state.files.contains_key(path) || state.is_dir(path)
+    }
+
+    fn modified(&self, path: &Path) -> io::Result<SystemTime> {
+        self.lock()
+            .files
+            .get(path)
+            .map(|(_, modified)| *modified)
+            .ok_or_else(|| Self::not_found(path))
+    }
+}
@@ -0,0 +1,115 @@
+//! Which notes link to which: an index of the wiki links in every note as
+//! last saved, kept up to date note by note, and the plain-text mentions of
+//! a title that could become links.
+
+use crate::links;
+use crate::store::NoteId;
+use std::collections::{BTreeSet, HashMap};
+use std::ops::Range;
+
+/// Longest context shown on each side of a link or mention, in characters.
+const CONTEXT_CHARS: usize = 60;
+
+/// The link targets of each note, and the notes linking to each target.
+/// Targets are kept in lower case.
+#[derive(Default)]
+pub struct LinkIndex {
+    outgoing: HashMap<NoteId, BTreeSet<String>>,
+    incoming: HashMap<String, BTreeSet<NoteId>>,
+}
+
+impl LinkIndex {
+    /// Indexes the links in `content` as those of note `id`, replacing the
+    /// ones indexed before.
+    pub fn update(&mut self, id: NoteId, content: &str) {
+        let targets: BTreeSet<String> = links::find(content).into_iter().map(|link| key(&link.target)).collect();
+        if self.outgoing.get(&id) == Some(&targets) {
+            return;
+        }
+        self.remove(id);
+        for target in &targets {
+            self.incoming.entry(target.clone()).or_default().insert(id);
+        }
+        self.outgoing.insert(id, targets);
+    }
+
+    pub fn remove(&mut self, id: NoteId) {
+        for target in self.outgoing.remove(&id).into_iter().flatten() {
+            if let Some(sources) = self.incoming.get_mut(&target) {
+                sources.remove(&id);
+                if sources.is_empty() {
+                    self.incoming.remove(&target);
+                }
+            }
+        }
+    }
+
+    /// What note `id` links to, in lower case.
+    pub fn targets(&self, id: NoteId) -> impl Iterator<Item = &str> + '_ {
+        self.outgoing.get(&id).into_iter().flatten().map(String::as_str)
+    }
+
+    /// The notes linking to `title`.
+    pub fn linking_to(&self, title: &str) -> impl Iterator<Item = NoteId> + '_ {
+        self.incoming.get(&key(title)).into_iter().flatten().copied()
+    }
+}
+
+/// A note linking to another, with the lines the links are on.
+#[derive(Clone, Debug)]
+pub struct Backlink {
+    pub source: NoteId,
+    pub snippets: Vec<Snippet>,
+}
+
+/// The title of a note written as plain text in another note.
+#[derive(Clone, Debug)]
+pub struct Mention {
+    pub source: NoteId,
+    /// Byte range of the mention in the source note's content.
+    pub range: Range<usize>,
+    pub snippet: Snippet,
+}
+
+/// Text around a link or mention.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Snippet {
+    /// Line number in the note's body, counting from 1.
+    pub line: usize,
+    pub text: String,
+    /// Byte range of the link or mention within `text`.
+    pub highlight: Range<usize>,
+}
+
+impl Snippet {
+    /// The line of `content` holding `range`, shortened around it.
+    pub fn around(content: &str, range: Range<usize>) -> Self {
+        let line_start = content[..range.start].rfind('\n').map_or(0, |i| i + 1);
+        let line_end = content[range.end..].find('\n').map_or(content.len(), |i| range.end + i);
+        let before = &content[line_start..range.start];
+        let after = &content[range.end..line_end];
+
+        let before_chars = before.chars().count();
+        let mut text = String::new();
+        if before_chars > CONTEXT_CHARS {
+            text.push('…');
+            text.extend(before.chars().skip(before_chars - CONTEXT_CHARS));
+        } else {
+            text.push_str(before.trim_start());
+        }
+        let start = text.len();
+        text.push_str(&content[range]);
+        let highlight = start..text.len();
+        if after.chars().count() > CONTEXT_CHARS {
+            text.extend(after.chars().take(CONTEXT_CHARS));
+            text.push('…');
+        } else {
+            text.push_str(after.trim_end());
+        }
+        Self { line: content[..line_start].matches('\n').count() + 1, text, highlight }
+    }
+}
+
+fn key(title: &str) -> String {
+    title.trim().to_lowercase()
+}
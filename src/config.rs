@@ -0,0 +1,30 @@
+//! Where rusty-notes keeps its own files, following each platform's
+//! convention for per-user configuration.
+
+use std::env;
+use std::path::PathBuf;
+
+const APP_DIR: &str = "rusty-notes";
+
+/// The user's home directory, if the environment names one.
+pub fn home_dir() -> Option<PathBuf> {
+    let var = if cfg!(windows) { "USERPROFILE" } else { "HOME" };
+    env::var_os(var).filter(|value| !value.is_empty()).map(PathBuf::from)
+}
+
+/// `rusty-notes` inside the platform configuration directory: `%APPDATA%`
+/// on Windows, `~/Library/Application Support` on macOS and
+/// `$XDG_CONFIG_HOME` (or `~/.config`) elsewhere.
+pub fn config_dir() -> Option<PathBuf> {
+    let base = if cfg!(windows) {
+        env::var_os("APPDATA").filter(|value| !value.is_empty()).map(PathBuf::from)
+    } else if cfg!(target_os = "macos") {
+        home_dir().map(|home| home.join("Library").join("Application Support"))
+    } else {
+        env::var_os("XDG_CONFIG_HOME")
+            .filter(|value| !value.is_empty())
+            .map(PathBuf::from)
+            .or_else(|| home_dir().map(|home| home.join(".config")))
+    };
+    base.map(|dir| dir.join(APP_DIR))
+}
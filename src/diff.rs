@@ -0,0 +1,293 @@
+//! Line-based diffing and three-way merging of note text.
+
+use std::ops::Range;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum DiffOp {
+    /// Line `.0` of the old text equals line `.1` of the new text.
+    Equal(usize, usize),
+    /// Line of the old text that is not in the new text.
+    Delete(usize),
+    /// Line of the new text that is not in the old text.
+    Insert(usize),
+}
+
+/// Splits text into lines, keeping each line's terminator.
+pub fn lines(text: &str) -> Vec<&str> {
+    text.split_inclusive('\n').collect()
+}
+
+/// Shortest edit script turning `old` into `new` (Myers' algorithm).
+pub fn diff_lines(old: &[&str], new: &[&str]) -> Vec<DiffOp> {
+    let prefix = old.iter().zip(new).take_while(|(a, b)| a == b).count();
+    let suffix = old[prefix..]
+        .iter()
+        .rev()
+        .zip(new[prefix..].iter().rev())
+        .take_while(|(a, b)| a == b)
+        .count();
+
+    let mut ops: Vec<DiffOp> = (0..prefix).map(|i| DiffOp::Equal(i, i)).collect();
+    let middle = myers(&old[prefix..old.len() - suffix], &new[prefix..new.len() - suffix]);
+    ops.extend(middle.into_iter().map(|op| match op {
+        DiffOp::Equal(a, b) => DiffOp::Equal(a + prefix, b + prefix),
+        DiffOp::Delete(a) => DiffOp::Delete(a + prefix),
+        DiffOp::Insert(b) => DiffOp::Insert(b + prefix),
+    }));
+    let (old_tail, new_tail) = (old.len() - suffix, new.len() - suffix);
+    ops.extend((0..suffix).map(|i| DiffOp::Equal(old_tail + i, new_tail + i)));
+    ops
+}
+
+fn myers(old: &[&str], new: &[&str]) -> Vec<DiffOp> {
+    let (n, m) = (old.len() as isize, new.len() as isize);
+    let max = n + m;
+    let offset = max + 1;
+    let mut v = vec![0isize; 2 * offset as usize + 1];
+    // Snapshot of v[-d-1..=d+1] before each round, for backtracking
+    let mut trace: Vec<Vec<isize>> = vec![];
+
+    'search: for d in 0..=max {
+        trace.push(v[(offset - d - 1) as usize..=(offset + d + 1) as usize].to_vec());
+        for k in (-d..=d).step_by(2) {
+            let at = |k: isize| (offset + k) as usize;
+            let mut x = if k == -d || (k != d && v[at(k - 1)] < v[at(k + 1)]) {
+                v[at(k + 1)]
+            } else {
+                v[at(k - 1)] + 1
+            };
+            let mut y = x - k;
+            while x < n && y < m && old[x as usize] == new[y as usize] {
+                x += 1;
+                y += 1;
+            }
+            v[at(k)] = x;
+            if x >= n && y >= m {
+                break 'search;
+            }
+        }
+    }
+
+    let mut ops = vec![];
+    let (mut x, mut y) = (n, m);
+    for (d, snapshot) in trace.iter().enumerate().rev() {
+        let d = d as isize;
+        let at = |k: isize| (k + d + 1) as usize;
+        let k = x - y;
+        let prev_k = if k == -d || (k != d && snapshot[at(k - 1)] < snapshot[at(k + 1)]) {
+            k + 1
+        } else {
+            k - 1
+        };
+        let prev_x = snapshot[at(prev_k)];
+        let prev_y = prev_x - prev_k;
+        while x > prev_x && y > prev_y {
+            ops.push(DiffOp::Equal((x - 1) as usize, (y - 1) as usize));
+            x -= 1;
+            y -= 1;
+        }
+        if d > 0 {
+            if x == prev_x {
+                ops.push(DiffOp::Insert((y - 1) as usize));
+            } else {
+                ops.push(DiffOp::Delete((x - 1) as usize));
+            }
+        }
+        x = prev_x;
+        y = prev_y;
+    }
+    ops.reverse();
+    ops
+}
+
+/// A run of changed lines: `old` lines were replaced by `new` lines.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Hunk {
+    pub old: Range<usize>,
+    pub new: Range<usize>,
+}
+
+/// Groups an edit script into hunks of consecutive changes.
+pub fn hunks(ops: &[DiffOp]) -> Vec<Hunk> {
+    let mut hunks = vec![];
+    let mut current: Option<Hunk> = None;
+    let (mut old_pos, mut new_pos) = (0, 0);
+    for op in ops {
+        match *op {
+            DiffOp::Equal(a, b) => {
+                hunks.extend(current.take());
+                old_pos = a + 1;
+                new_pos = b + 1;
+            }
+            DiffOp::Delete(a) => {
+                let hunk = current.get_or_insert(Hunk { old: a..a, new: new_pos..new_pos });
+                hunk.old.end = a + 1;
+                old_pos = a + 1;
+            }
+            DiffOp::Insert(b) => {
+                let hunk = current.get_or_insert(Hunk { old: old_pos..old_pos, new: b..b });
+                hunk.new.end = b + 1;
+                new_pos = b + 1;
+            }
+        }
+    }
+    hunks.extend(current);
+    hunks
+}
+
+pub struct Merge {
+    pub text: String,
+    /// Number of regions both sides changed differently, now marked with
+    /// `<<<<<<<`, `=======` and `>>>>>>>` lines.
+    pub conflicts: usize,
+}
+
+/// Three-way merges `mine` and `theirs`, which both started from `base`.
+pub fn merge3(base: &str, mine: &str, theirs: &str) -> Merge {
+    let (base, mine, theirs) = (lines(base), lines(mine), lines(theirs));
+    let ours = hunks(&diff_lines(&base, &mine));
+    let other = hunks(&diff_lines(&base, &theirs));
+
+    let mut text = String::new();
+    let mut conflicts = 0;
+    let (mut i, mut j) = (0, 0);
+    let (mut our_delta, mut their_delta) = (0isize, 0isize);
+    let mut pos = 0;
+
+    while i < ours.len() || j < other.len() {
+        let start = match (ours.get(i), other.get(j)) {
+            (Some(a), Some(b)) => a.old.start.min(b.old.start),
+            (Some(a), None) => a.old.start,
+            (None, Some(b)) => b.old.start,
+            (None, None) => unreachable!("loop condition"),
+        };
+        let mut end = start;
+        let (first_ours, first_other) = (i, j);
+        // Grow the region until no hunk from either side touches it
+        loop {
+            if let Some(h) = ours.get(i).filter(|h| h.old.start <= end) {
+                end = end.max(h.old.end);
+                i += 1;
+            } else if let Some(h) = other.get(j).filter(|h| h.old.start <= end) {
+                end = end.max(h.old.end);
+                j += 1;
+            } else {
+                break;
+            }
+        }
+
+        text.extend(base[pos..start].iter().copied());
+        let delta = |hunks: &[Hunk]| hunks.iter().map(|h| h.new.len() as isize - h.old.len() as isize).sum::<isize>();
+        let our_end_delta = our_delta + delta(&ours[first_ours..i]);
+        let their_end_delta = their_delta + delta(&other[first_other..j]);
+        let our_side = &mine[(start as isize + our_delta) as usize..(end as isize + our_end_delta) as usize];
+        let their_side = &theirs[(start as isize + their_delta) as usize..(end as isize + their_end_delta) as usize];
+
+        if first_other == j || our_side == their_side {
+            text.extend(our_side.iter().copied());
+        } else if first_ours == i {
+            text.extend(their_side.iter().copied());
+        } else {
+            conflicts += 1;
+            text.push_str("<<<<<<< mine\n");
+            push_lines(&mut text, our_side);
+            text.push_str("=======\n");
+            push_lines(&mut text, their_side);
+            text.push_str(">>>>>>> theirs\n");
+        }
+
+        pos = end;
+        our_delta = our_end_delta;
+        their_delta = their_end_delta;
+    }
+    text.extend(base[pos..].iter().copied());
+
+    Merge { text, conflicts }
+}
+
+/// Appends lines, making sure the last one ends with a newline so a
+/// conflict marker can follow.
+fn push_lines(text: &mut String, lines: &[&str]) {
+    text.extend(lines.iter().copied());
+    if !text.ends_with('\n') {
+        text.push('\n');
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // The new text an edit script describes, rebuilt from both sides
+    fn apply(old: &[&str], new: &[&str], ops: &[DiffOp]) -> String {
+        let mut text = String::new();
+        for op in ops {
+            match *op {
+                DiffOp::Equal(a, b) => {
+                    assert_eq!(old[a], new[b]);
+                    text.push_str(old[a]);
+                }
+                DiffOp::Delete(_) => {}
+                DiffOp::Insert(b) => text.push_str(new[b]),
+            }
+        }
+        text
+    }
+
+    #[test]
+    fn lines_keep_their_terminators() {
+        assert_eq!(lines("a\nb\n"), vec!["a\n", "b\n"]);
+        assert_eq!(lines("a\nb"), vec!["a\n", "b"]);
+        assert!(lines("").is_empty());
+    }
+
+    #[test]
+    fn diff_is_shortest_and_rebuilds_the_new_text() {
+        let cases = [("a\nb\nc\n", "a\nc\n"), ("", "x\n"), ("x\n", ""), ("a\nb\nc\nd\n", "b\nx\nc\ne\n"), ("same\n", "same\n")];
+        for (old, new) in cases {
+            let (old_lines, new_lines) = (lines(old), lines(new));
+            let ops = diff_lines(&old_lines, &new_lines);
+            assert_eq!(apply(&old_lines, &new_lines, &ops), new, "{:?} -> {:?}", old, new);
+        }
+        let ops = diff_lines(&["a", "b", "c"], &["a", "x", "c"]);
+        assert_eq!(ops, vec![DiffOp::Equal(0, 0), DiffOp::Delete(1), DiffOp::Insert(1), DiffOp::Equal(2, 2)]);
+    }
+
+    #[test]
+    fn hunks_group_consecutive_changes() {
+        let ops = diff_lines(&["a", "b", "c", "d", "e"], &["a", "x", "c", "e", "f"]);
+        assert_eq!(
+            hunks(&ops),
+            vec![Hunk { old: 1..2, new: 1..2 }, Hunk { old: 3..4, new: 3..3 }, Hunk { old: 5..5, new: 4..5 }]
+        );
+        assert!(hunks(&diff_lines(&["a"], &["a"])).is_empty());
+    }
+
+    #[test]
+    fn merge_takes_changes_from_both_sides() {
+        let merge = merge3("a\nb\nc\n", "A\nb\nc\n", "a\nb\nC\n");
+        assert_eq!(merge.text, "A\nb\nC\n");
+        assert_eq!(merge.conflicts, 0);
+        // Insertions and deletions shift the lines that follow
+        let merge = merge3("a\nb\nc\nd\n", "new\na\nb\nc\nd\n", "a\nb\nd\n");
+        assert_eq!(merge.text, "new\na\nb\nd\n");
+        assert_eq!(merge.conflicts, 0);
+    }
+
+    #[test]
+    fn merge_of_the_same_change_is_clean() {
+        let merge = merge3("a\nb\n", "a\nx\n", "a\nx\n");
+        assert_eq!(merge.text, "a\nx\n");
+        assert_eq!(merge.conflicts, 0);
+    }
+
+    #[test]
+    fn merge_marks_conflicting_changes() {
+        let merge = merge3("a\nb\nc\n", "a\nmine\nc\n", "a\ntheirs\nc\n");
+        assert_eq!(merge.text, "a\n<<<<<<< mine\nmine\n=======\ntheirs\n>>>>>>> theirs\nc\n");
+        assert_eq!(merge.conflicts, 1);
+        // A last line without a newline still leaves the markers on their own lines
+        let merge = merge3("a", "b", "c");
+        assert_eq!(merge.text, "<<<<<<< mine\nb\n=======\nc\n>>>>>>> theirs\n");
+    }
+}
@@ -0,0 +1,92 @@
+use crate::store::NoteId;
+use std::error::Error;
+use std::fmt;
+use std::io;
+use std::path::PathBuf;
+
+/// Why a note store operation failed.
+#[derive(Debug)]
+pub enum StoreError {
+    CreateDir { dir: PathBuf, source: io::Error },
+    List { dir: PathBuf, source: io::Error },
+    Read { path: PathBuf, source: io::Error },
+    Write { path: PathBuf, source: io::Error },
+    Rename { from: PathBuf, to: PathBuf, source: io::Error },
+    Delete { path: PathBuf, source: io::Error },
+    /// No note with this id is in the store.
+    NoSuchNote(NoteId),
+    /// No file with this name is in the trash.
+    NotInTrash(String),
+    /// A note or folder is already at the place another was to be moved to.
+    AlreadyExists(PathBuf),
+    /// The note's file could not be read when it was loaded, so saving the
+    /// (empty) content in the editor would destroy it.
+    NotLoaded(NoteId),
+}
+
+pub type Result<T, E = StoreError> = std::result::Result<T, E>;
+
+impl fmt::Display for StoreError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            StoreError::CreateDir { dir, source } => write!(f, "cannot create {}: {}", dir.display(), source),
+            StoreError::List { dir, source } => write!(f, "cannot list {}: {}", dir.display(), source),
+            StoreError::Read { path, source } => write!(f, "cannot read {}: {}", path.display(), source),
+            StoreError::Write { path, source } => write!(f, "cannot write {}: {}", path.display(), source),
+            StoreError::Rename { from, to, source } => {
+                write!(f, "cannot rename {} to {}: {}", from.display(), to.display(), source)
+            }
+            StoreError::Delete { path, source } => write!(f, "cannot delete {}: {}", path.display(), source),
+            StoreError::NoSuchNote(id) => write!(f, "note {} does not exist", id),
+            StoreError::NotInTrash(file) => write!(f, "{} is not in the trash", file),
+            StoreError::AlreadyExists(path) => write!(f, "{} already exists", path.display()),
+            StoreError::NotLoaded(id) => {
+                write!(f, "note {} could not be loaded, so it will not be saved over", id)
+            }
+        }
+    }
+}
+
+impl Error for StoreError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            StoreError::CreateDir { source, .. }
+            | StoreError::List { source, .. }
+            | StoreError::Read { source, .. }
+            | StoreError::Write { source, .. }
+            | StoreError::Rename { source, .. }
+            | StoreError::Delete { source, .. } => Some(source),
+            StoreError::NoSuchNote(_)
+            | StoreError::NotInTrash(_)
+            | StoreError::AlreadyExists(_)
+            | StoreError::NotLoaded(_) => None,
+        }
+    }
+}
+
+/// Why the settings file could not be loaded or saved.
+#[derive(Debug)]
+pub enum SettingsError {
+    Read { path: PathBuf, source: io::Error },
+    Parse { path: PathBuf, source: toml::de::Error },
+    Write { path: PathBuf, source: io::Error },
+}
+
+impl fmt::Display for SettingsError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SettingsError::Read { path, source } => write!(f, "cannot read {}: {}", path.display(), source),
+            SettingsError::Parse { path, source } => write!(f, "{} is not valid: {}", path.display(), source.message()),
+            SettingsError::Write { path, source } => write!(f, "cannot write {}: {}", path.display(), source),
+        }
+    }
+}
+
+impl Error for SettingsError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            SettingsError::Read { source, .. } | SettingsError::Write { source, .. } => Some(source),
+            SettingsError::Parse { source, .. } => Some(source),
+        }
+    }
+}
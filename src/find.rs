@@ -0,0 +1,195 @@
+//! Finding text in a note, as typed or as a regular expression, and
+//! working out what replacing it would change.
+
+use crate::backlinks::Snippet;
+use crate::store::NoteId;
+use regex::{Regex, RegexBuilder};
+use std::ops::Range;
+
+/// How a [`Finder`] reads its pattern.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct FindOptions {
+    pub match_case: bool,
+    /// Skip matches that are only part of a longer word.
+    pub whole_word: bool,
+    /// The pattern is a regular expression, and `$1` or `${name}` in a
+    /// replacement stand for what its groups matched.
+    pub regex: bool,
+}
+
+/// A pattern ready to search with.
+#[derive(Clone, Debug)]
+pub struct Finder {
+    regex: Regex,
+    options: FindOptions,
+}
+
+/// One match and what would replace it.
+#[derive(Clone, Debug)]
+pub struct Change {
+    /// Byte range of the match in the text searched.
+    pub range: Range<usize>,
+    pub snippet: Snippet,
+    pub replacement: String,
+}
+
+/// The changes replacing would make to one note's body.
+#[derive(Clone, Debug)]
+pub struct NoteChanges {
+    pub id: NoteId,
+    /// The body the changes were worked out from.
+    pub body: String,
+    pub changes: Vec<Change>,
+}
+
+impl Finder {
+    /// Fails if `options.regex` is set and `pattern` is not a valid
+    /// regular expression.
+    pub fn new(pattern: &str, options: FindOptions) -> Result<Self, regex::Error> {
+        let pattern = if options.regex { pattern.to_string() } else { regex::escape(pattern) };
+        let regex = RegexBuilder::new(&pattern).case_insensitive(!options.match_case).multi_line(true).build()?;
+        Ok(Self { regex, options })
+    }
+
+    /// Byte ranges of every match in `text`, in order. Empty matches, such
+    /// as an empty pattern finds, are left out.
+    pub fn find_all(&self, text: &str) -> Vec<Range<usize>> {
+        self.regex
+            .find_iter(text)
+            .map(|found| found.range())
+            .filter(|range| !range.is_empty() && (!self.options.whole_word || is_whole_word(text, range)))
+            .collect()
+    }
+
+    /// What replaces the match at `range`, a range [`find_all`] returned,
+    /// with the groups of a regular expression filled in.
+    ///
+    /// [`find_all`]: Self::find_all
+    pub fn replacement(&self, text: &str, range: Range<usize>, replacement: &str) -> String {
+        if !self.options.regex {
+            return replacement.to_string();
+        }
+        let mut expanded = String::new();
+        if let Some(captures) = self.regex.captures_at(text, range.start) {
+            captures.expand(replacement, &mut expanded);
+        }
+        expanded
+    }
+
+    /// Every match in `text` with what would replace it.
+    pub fn changes(&self, text: &str, replacement: &str) -> Vec<Change> {
+        self.find_all(text)
+            .into_iter()
+            .map(|range| Change {
+                snippet: Snippet::around(text, range.clone()),
+                replacement: self.replacement(text, range.clone(), replacement),
+                range,
+            })
+            .collect()
+    }
+}
+
+/// `text` with `changes`, worked out from it and in order, made.
+pub fn apply(text: &str, changes: &[Change]) -> String {
+    let mut result = String::with_capacity(text.len());
+    let mut last = 0;
+    for change in changes {
+        result.push_str(&text[last..change.range.start]);
+        result.push_str(&change.replacement);
+        last = change.range.end;
+    }
+    result.push_str(&text[last..]);
+    result
+}
+
+fn is_whole_word(text: &str, range: &Range<usize>) -> bool {
+    let is_word = |c: char| c.is_alphanumeric() || c == '_';
+    !text[..range.start].chars().next_back().is_some_and(is_word) && !text[range.end..].chars().next().is_some_and(is_word)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::store::tests::store_with;
+
+    fn finder(pattern: &str, options: FindOptions) -> Finder {
+        Finder::new(pattern, options).unwrap()
+    }
+
+    #[test]
+    fn plain_text_is_not_a_pattern() {
+        let found = finder("a.b", FindOptions::default()).find_all("a.b axb A.B");
+        assert_eq!(found, vec![0..3, 8..11]);
+    }
+
+    #[test]
+    fn match_case() {
+        let options = FindOptions { match_case: true, ..Default::default() };
+        assert_eq!(finder("Cat", options).find_all("cat Cat CAT"), vec![4..7]);
+    }
+
+    #[test]
+    fn whole_words_only() {
+        let options = FindOptions { whole_word: true, ..Default::default() };
+        assert_eq!(finder("cat", options).find_all("cat concat cat_ cats (cat)"), vec![0..3, 22..25]);
+        assert_eq!(finder("é", options).find_all("é aé"), vec![0..2]);
+    }
+
+    #[test]
+    fn regular_expressions() {
+        let options = FindOptions { regex: true, ..Default::default() };
+        assert_eq!(finder(r"\d+", options).find_all("a1 b22"), vec![1..2, 4..6]);
+        // `^` and `$` are the start and end of every line
+        assert_eq!(finder("^x$", options).find_all("x\ny\nx"), vec![0..1, 4..5]);
+        assert!(Finder::new("(", options).is_err());
+        assert!(Finder::new("(", FindOptions::default()).is_ok());
+    }
+
+    #[test]
+    fn empty_matches_are_left_out() {
+        assert!(finder("", FindOptions::default()).find_all("text").is_empty());
+        let options = FindOptions { regex: true, ..Default::default() };
+        assert_eq!(finder("x*", options).find_all("axxb"), vec![1..3]);
+    }
+
+    #[test]
+    fn replacements_fill_in_groups() {
+        let text = "2024-03-09 and 1999-12-31";
+        let options = FindOptions { regex: true, ..Default::default() };
+        let dates = finder(r"(?<y>\d{4})-(\d\d)-(\d\d)", options);
+        let changes = dates.changes(text, "$3/$2/${y}");
+        let replacements: Vec<&str> = changes.iter().map(|change| change.replacement.as_str()).collect();
+        assert_eq!(replacements, vec!["09/03/2024", "31/12/1999"]);
+        assert_eq!(apply(text, &changes), "09/03/2024 and 31/12/1999");
+        // Without a regular expression `$1` is just text
+        let plain = finder("and", FindOptions::default());
+        assert_eq!(apply(text, &plain.changes(text, "$1")), "2024-03-09 $1 1999-12-31");
+    }
+
+    #[test]
+    fn changes_carry_their_line() {
+        let text = "first\nsecond cat\n";
+        let changes = finder("cat", FindOptions::default()).changes(text, "dog");
+        assert_eq!(changes.len(), 1);
+        assert_eq!(changes[0].range, 13..16);
+        assert_eq!(changes[0].snippet.line, 2);
+        assert_eq!(&changes[0].snippet.text[changes[0].snippet.highlight.clone()], "cat");
+        assert_eq!(apply(text, &changes), "first\nsecond dog\n");
+        assert_eq!(apply(text, &[]), text);
+    }
+
+    #[test]
+    fn replacing_in_notes_skips_a_body_changed_since() {
+        let (mut store, ids) = store_with(&[("First", "red apple"), ("Second", "red pear")]);
+        let [first, second] = ids[..] else { unreachable!() };
+
+        let notes = store.replacements(&finder("red", FindOptions::default()), "green");
+        assert_eq!(notes.len(), 2);
+        store.get_mut(second).unwrap().set_body("red pear, edited".to_string());
+        let applied: Vec<bool> = notes.iter().map(|changes| store.apply_changes(changes).unwrap()).collect();
+        let by_id = |id| applied[notes.iter().position(|changes| changes.id == id).unwrap()];
+        assert!(by_id(first) && !by_id(second));
+        assert_eq!(store.get(first).unwrap().body(), "green apple");
+        assert_eq!(store.get(second).unwrap().body(), "red pear, edited");
+    }
+}
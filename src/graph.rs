@@ -0,0 +1,218 @@
+//! The notes as a graph: a node per note, and an edge wherever a note links
+//! to another or notes share a tag. [`ForceLayout`] places the nodes
+//! by simulating springs along the edges and repulsion between all nodes.
+
+use crate::store::{Note, NoteId, NoteStore};
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+
+/// Distance at which the pull of an edge balances the push between its
+/// nodes.
+const EDGE_LENGTH: f32 = 80.0;
+/// Pull towards the origin, which keeps unconnected nodes from drifting off.
+const GRAVITY: f32 = 0.1;
+/// How much speed a unit of force adds in one step.
+const STEP: f32 = 0.05;
+/// Fraction of its speed a node keeps from one step to the next.
+const DAMPING: f32 = 0.8;
+const MAX_SPEED: f32 = 50.0;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum EdgeKind {
+    Link,
+    Tag,
+}
+
+/// Two nodes, by index into [`Graph::nodes`].
+#[derive(Clone, Copy, Debug)]
+pub struct Edge {
+    pub from: usize,
+    pub to: usize,
+    pub kind: EdgeKind,
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct Graph {
+    pub nodes: Vec<NoteId>,
+    pub edges: Vec<Edge>,
+}
+
+impl Graph {
+    /// The notes `include` accepts, with their links as last saved and,
+    /// if `tag_edges`, an edge from each note with a tag to the first note
+    /// that has it. Joining every two such notes instead would square the
+    /// edges of a tag most notes have.
+    pub fn build(store: &NoteStore, include: impl Fn(&Note) -> bool, tag_edges: bool) -> Self {
+        let notes: Vec<&Note> = store.notes().iter().filter(|note| include(note)).collect();
+        let by_title: HashMap<String, usize> =
+            notes.iter().enumerate().rev().map(|(i, note)| (note.title.trim().to_lowercase(), i)).collect();
+
+        let mut pairs: BTreeMap<(usize, usize), EdgeKind> = BTreeMap::new();
+        for (from, note) in notes.iter().enumerate() {
+            for target in store.links().targets(note.id) {
+                if let Some(&to) = by_title.get(target).filter(|&&to| to != from) {
+                    pairs.insert((from.min(to), from.max(to)), EdgeKind::Link);
+                }
+            }
+        }
+        if tag_edges {
+            let mut by_tag: BTreeMap<&str, Vec<usize>> = BTreeMap::new();
+            for (i, note) in notes.iter().enumerate() {
+                for tag in &note.tags {
+                    by_tag.entry(tag).or_default().push(i);
+                }
+            }
+            for members in by_tag.values() {
+                let (&hub, rest) = members.split_first().expect("a tag has a note");
+                for &other in rest {
+                    // A link says more than a shared tag
+                    pairs.entry((hub, other)).or_insert(EdgeKind::Tag);
+                }
+            }
+        }
+
+        let edges = pairs.into_iter().map(|((from, to), kind)| Edge { from, to, kind }).collect();
+        Self { nodes: notes.iter().map(|note| note.id).collect(), edges }
+    }
+
+    /// How many edges each node has.
+    pub fn degrees(&self) -> Vec<usize> {
+        let mut degrees = vec![0; self.nodes.len()];
+        for edge in &self.edges {
+            degrees[edge.from] += 1;
+            degrees[edge.to] += 1;
+        }
+        degrees
+    }
+
+    /// The nodes sharing an edge with `node`.
+    pub fn neighbours(&self, node: usize) -> BTreeSet<usize> {
+        self.edges
+            .iter()
+            .filter_map(|edge| match (edge.from == node, edge.to == node) {
+                (true, _) => Some(edge.to),
+                (_, true) => Some(edge.from),
+                _ => None,
+            })
+            .collect()
+    }
+}
+
+/// Where each note's node is, kept by note so that the layout survives
+/// rebuilding the graph. Nodes push each other apart with a force of
+/// `EDGE_LENGTH² / d` and edges pull with `d² / EDGE_LENGTH`, as in the
+/// Fruchterman-Reingold layout.
+#[derive(Default)]
+pub struct ForceLayout {
+    positions: HashMap<NoteId, [f32; 2]>,
+    velocities: HashMap<NoteId, [f32; 2]>,
+}
+
+impl ForceLayout {
+    pub fn position(&self, id: NoteId) -> Option<[f32; 2]> {
+        self.positions.get(&id).copied()
+    }
+
+    /// Puts a node somewhere by hand; the simulation carries on from there.
+    pub fn set_position(&mut self, id: NoteId, position: [f32; 2]) {
+        self.positions.insert(id, position);
+        self.velocities.insert(id, [0.0, 0.0]);
+    }
+
+    /// Advances the simulation by one step and returns how far the nodes
+    /// moved in all, which falls towards zero as the layout settles. Nodes
+    /// new to the layout are placed first.
+    pub fn step(&mut self, graph: &Graph) -> f32 {
+        for (i, &id) in graph.nodes.iter().enumerate() {
+            self.positions.entry(id).or_insert_with(|| spiral(i));
+        }
+        let positions: Vec<[f32; 2]> = graph.nodes.iter().map(|id| self.positions[id]).collect();
+        let mut forces = vec![[0.0f32; 2]; positions.len()];
+
+        for i in 0..positions.len() {
+            for j in i + 1..positions.len() {
+                let (dx, dy) = (positions[i][0] - positions[j][0], positions[i][1] - positions[j][1]);
+                // Nodes on top of each other still need a direction to part in
+                let distance_sq = (dx * dx + dy * dy).max(0.01);
+                let (dx, dy) = if dx == 0.0 && dy == 0.0 { (1.0, 0.0) } else { (dx, dy) };
+                let push = EDGE_LENGTH * EDGE_LENGTH / distance_sq;
+                forces[i][0] += dx * push;
+                forces[i][1] += dy * push;
+                forces[j][0] -= dx * push;
+                forces[j][1] -= dy * push;
+            }
+        }
+        for edge in &graph.edges {
+            let (a, b) = (positions[edge.from], positions[edge.to]);
+            let (dx, dy) = (b[0] - a[0], b[1] - a[1]);
+            let distance = (dx * dx + dy * dy).sqrt().max(0.01);
+            let pull = distance / EDGE_LENGTH;
+            forces[edge.from][0] += dx * pull;
+            forces[edge.from][1] += dy * pull;
+            forces[edge.to][0] -= dx * pull;
+            forces[edge.to][1] -= dy * pull;
+        }
+
+        let mut moved = 0.0;
+        for (i, &id) in graph.nodes.iter().enumerate() {
+            let position = positions[i];
+            let velocity = self.velocities.entry(id).or_default();
+            for axis in 0..2 {
+                let force = forces[i][axis] - position[axis] * GRAVITY;
+                velocity[axis] = ((velocity[axis] + force * STEP) * DAMPING).clamp(-MAX_SPEED, MAX_SPEED);
+            }
+            moved += velocity[0].abs() + velocity[1].abs();
+            self.positions.insert(id, [position[0] + velocity[0], position[1] + velocity[1]]);
+        }
+        moved
+    }
+}
+
+// Spreads new nodes out along a sunflower spiral, so no two start together
+fn spiral(i: usize) -> [f32; 2] {
+    const GOLDEN_ANGLE: f32 = 2.399_963;
+    let radius = EDGE_LENGTH * (i as f32 + 1.0).sqrt();
+    let angle = i as f32 * GOLDEN_ANGLE;
+    [radius * angle.cos(), radius * angle.sin()]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::store::tests::store_with;
+
+    fn store(notes: &[(&str, &str)]) -> NoteStore {
+        store_with(notes).0
+    }
+
+    fn edges(graph: &Graph) -> Vec<(usize, usize, EdgeKind)> {
+        graph.edges.iter().map(|edge| (edge.from, edge.to, edge.kind)).collect()
+    }
+
+    #[test]
+    fn links_join_notes_and_outrank_tags() {
+        let store = store(&[("A", "[[B]] #t"), ("B", "#t [[Missing]] [[B]]"), ("C", "")]);
+        let graph = Graph::build(&store, |_| true, true);
+        assert_eq!(edges(&graph), vec![(0, 1, EdgeKind::Link)]);
+        assert_eq!(graph.degrees(), vec![1, 1, 0]);
+    }
+
+    #[test]
+    fn notes_sharing_a_tag_join_the_first_of_them() {
+        let store = store(&[("A", "#t"), ("B", "#t"), ("C", "#t"), ("D", "#t #u"), ("E", "#u")]);
+        let graph = Graph::build(&store, |_| true, true);
+        let tag = EdgeKind::Tag;
+        assert_eq!(edges(&graph), vec![(0, 1, tag), (0, 2, tag), (0, 3, tag), (3, 4, tag)]);
+        assert_eq!(graph.degrees(), vec![3, 1, 1, 2, 1]);
+        assert_eq!(graph.neighbours(3), BTreeSet::from([0, 4]));
+        assert!(Graph::build(&store, |_| true, false).edges.is_empty());
+    }
+
+    #[test]
+    fn filtered_out_notes_are_left_out() {
+        let store = store(&[("A", "[[B]]"), ("B", "[[C]]"), ("C", "")]);
+        let graph = Graph::build(&store, |note| note.title != "B", true);
+        assert_eq!(graph.nodes.len(), 2);
+        assert!(graph.edges.is_empty());
+    }
+}
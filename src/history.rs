@@ -0,0 +1,135 @@
+//! Saved versions of every note, kept in a hidden `.history` folder of the
+//! notes directory: one subfolder per note id, one file per save, named by
+//! the time of the save in milliseconds since the Unix epoch.
+
+use crate::backend::Backend;
+use crate::store::NoteId;
+use serde::{Deserialize, Serialize};
+use std::io;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+pub const HISTORY_DIR: &str = ".history";
+
+/// One saved version of a note.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Version {
+    pub path: PathBuf,
+    pub saved_at: SystemTime,
+}
+
+/// How many versions to keep. The newest version of a note is always kept.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Retention {
+    /// Versions kept per note; 0 keeps every version.
+    pub max_versions: usize,
+    /// Versions older than this are dropped; 0 keeps them forever.
+    pub max_age_days: u64,
+}
+
+impl Default for Retention {
+    fn default() -> Self {
+        Self { max_versions: 50, max_age_days: 90 }
+    }
+}
+
+pub(crate) fn note_dir(notes_dir: &Path, id: NoteId) -> PathBuf {
+    notes_dir.join(HISTORY_DIR).join(id.to_string())
+}
+
+/// Versions in `dir`, newest first. Files not named by a timestamp are ignored.
+pub(crate) fn versions(backend: &dyn Backend, dir: &Path) -> io::Result<Vec<Version>> {
+    let files = match backend.list(dir) {
+        Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(vec![]),
+        files => files?,
+    };
+    let mut versions: Vec<Version> = files
+        .into_iter()
+        .filter_map(|path| {
+            let millis: u64 = path.file_stem()?.to_str()?.parse().ok()?;
+            let saved_at = UNIX_EPOCH + Duration::from_millis(millis);
+            Some(Version { path, saved_at })
+        })
+        .collect();
+    versions.sort_by_key(|version| std::cmp::Reverse(version.saved_at));
+    Ok(versions)
+}
+
+/// Adds `content` to the history in `dir` as saved at `at`.
+pub(crate) fn snapshot(backend: &mut dyn Backend, dir: &Path, content: &str, at: SystemTime) -> io::Result<()> {
+    backend.create_dir_all(dir)?;
+    let millis = |time: SystemTime| time.duration_since(UNIX_EPOCH).unwrap_or_default().as_millis();
+    // Saves within the same millisecond still get a file each, in order
+    let after_latest = versions(backend, dir)?.first().map_or(0, |latest| millis(latest.saved_at) + 1);
+    let name = millis(at).max(after_latest);
+    backend.write(&dir.join(format!("{}.md", name)), content)
+}
+
+/// Removes the versions in `dir` that `retention` no longer keeps.
+pub(crate) fn prune(backend: &mut dyn Backend, dir: &Path, retention: Retention) -> io::Result<()> {
+    let versions = versions(backend, dir)?;
+    let max_age = Duration::from_secs(retention.max_age_days.saturating_mul(24 * 60 * 60));
+    for (i, version) in versions.iter().enumerate().skip(1) {
+        let too_many = retention.max_versions > 0 && i >= retention.max_versions;
+        let too_old = retention.max_age_days > 0 && version.saved_at.elapsed().is_ok_and(|age| age > max_age);
+        if too_many || too_old {
+            backend.remove(&version.path)?;
+        }
+    }
+    Ok(())
+}
+
+/// Removes every version in `dir`.
+pub(crate) fn remove_all(backend: &mut dyn Backend, dir: &Path) -> io::Result<()> {
+    for version in versions(backend, dir)? {
+        backend.remove(&version.path)?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::backend::MemoryBackend;
+
+    const DAY: Duration = Duration::from_secs(24 * 60 * 60);
+
+    // A history with a version saved each of `days_ago`, newest first
+    fn history(days_ago: &[u32]) -> (MemoryBackend, PathBuf) {
+        let mut backend = MemoryBackend::new();
+        let dir = PathBuf::from("notes/.history/1");
+        for &days in days_ago.iter().rev() {
+            snapshot(&mut backend, &dir, "text", SystemTime::now() - DAY * days).unwrap();
+        }
+        (backend, dir)
+    }
+
+    fn ages(backend: &MemoryBackend, dir: &Path) -> Vec<u64> {
+        let versions = versions(backend, dir).unwrap();
+        versions.iter().map(|version| version.saved_at.elapsed().unwrap().as_secs() / DAY.as_secs()).collect()
+    }
+
+    #[test]
+    fn prune_keeps_the_newest_versions() {
+        let (mut backend, dir) = history(&[0, 1, 2, 3]);
+        prune(&mut backend, &dir, Retention { max_versions: 2, max_age_days: 0 }).unwrap();
+        assert_eq!(ages(&backend, &dir), vec![0, 1]);
+    }
+
+    #[test]
+    fn prune_drops_old_versions_but_never_the_newest() {
+        let (mut backend, dir) = history(&[10, 20, 40]);
+        prune(&mut backend, &dir, Retention { max_versions: 0, max_age_days: 15 }).unwrap();
+        assert_eq!(ages(&backend, &dir), vec![10]);
+        prune(&mut backend, &dir, Retention { max_versions: 0, max_age_days: 5 }).unwrap();
+        assert_eq!(ages(&backend, &dir), vec![10]);
+    }
+
+    #[test]
+    fn prune_with_a_huge_age_keeps_everything() {
+        let (mut backend, dir) = history(&[0, 400]);
+        prune(&mut backend, &dir, Retention { max_versions: 0, max_age_days: u64::MAX }).unwrap();
+        assert_eq!(ages(&backend, &dir), vec![0, 400]);
+    }
+}
@@ -0,0 +1,34 @@
+//! Note storage and Markdown handling for rusty-notes, usable without the
+//! egui front end.
+
+pub mod backend;
+pub mod backlinks;
+pub mod config;
+pub mod date;
+pub mod diff;
+pub mod error;
+pub mod find;
+pub mod graph;
+pub mod history;
+pub mod links;
+pub mod markdown;
+pub mod metadata;
+pub mod search;
+pub mod settings;
+pub mod store;
+pub mod tags;
+pub mod title;
+pub mod trash;
+pub mod vault;
+
+pub use backend::{Backend, FsBackend, MemoryBackend};
+pub use backlinks::{Backlink, LinkIndex, Mention, Snippet};
+pub use error::{SettingsError, StoreError};
+pub use find::{Change, FindOptions, Finder, NoteChanges};
+pub use history::{Retention, Version};
+pub use metadata::{Metadata, Value};
+pub use search::{Hit, Query, SearchIndex, SearchResult};
+pub use store::{AutosaveReport, DiskChange, Note, NoteId, NoteStore, Recovery, SaveOutcome, SortOrder};
+pub use settings::Settings;
+pub use trash::TrashEntry;
+pub use vault::RecentVaults;
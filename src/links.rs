@@ -0,0 +1,227 @@
+//! Wiki links: `[[Title]]` refers to another note by its title, and
+//! `[[Title|alias]]` shows the alias instead. Titles are matched without
+//! regard to case.
+
+use regex::RegexBuilder;
+use std::ops::Range;
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct WikiLink {
+    pub target: String,
+    pub alias: Option<String>,
+    /// Byte range of the whole link, brackets included.
+    pub range: Range<usize>,
+}
+
+impl WikiLink {
+    /// The text the link shows.
+    pub fn label(&self) -> &str {
+        self.alias.as_deref().unwrap_or(&self.target)
+    }
+}
+
+/// The link at the very start of `text`: its target, alias and length.
+pub fn parse_at(text: &str) -> Option<(String, Option<String>, usize)> {
+    let inner = text.strip_prefix("[[")?;
+    let end = inner.find("]]")?;
+    let inner = &inner[..end];
+    if inner.contains(['[', ']', '\n']) {
+        return None;
+    }
+    let (target, alias) = match inner.split_once('|') {
+        Some((target, alias)) => (target.trim(), Some(alias.trim()).filter(|alias| !alias.is_empty())),
+        None => (inner.trim(), None),
+    };
+    if target.is_empty() {
+        return None;
+    }
+    Some((target.to_string(), alias.map(String::from), end + 4))
+}
+
+/// Every link in `text`, in order, outside code blocks and code spans.
+pub fn find(text: &str) -> Vec<WikiLink> {
+    let mut links = vec![];
+    for span in prose(text) {
+        let mut i = span.start;
+        while i < span.end {
+            let rest = &text[i..span.end];
+            if let Some((target, alias, len)) = parse_at(rest) {
+                links.push(WikiLink { target, alias, range: i..i + len });
+                i += len;
+            } else {
+                i += rest.chars().next().map_or(1, char::len_utf8);
+            }
+        }
+    }
+    links
+}
+
+/// Where `title` appears in `text` as whole words, in any case, but not
+/// inside a link or code.
+pub fn mentions(text: &str, title: &str) -> Vec<Range<usize>> {
+    let title = title.trim();
+    if title.is_empty() {
+        return vec![];
+    }
+    // Case folding can change the length of a title, as with "İ", so the
+    // matcher decides where a mention ends
+    let Ok(pattern) = RegexBuilder::new(&regex::escape(title)).case_insensitive(true).build() else {
+        return vec![];
+    };
+    let links = find(text);
+    let mut found = vec![];
+    for span in prose(text) {
+        let mut from = span.start;
+        while let Some(candidate) = pattern.find_at(&text[..span.end], from) {
+            let (start, end) = (candidate.start(), candidate.end());
+            let before = text[..start].chars().next_back();
+            let after = text[end..].chars().next();
+            let whole_word = !before.is_some_and(char::is_alphanumeric) && !after.is_some_and(char::is_alphanumeric);
+            let in_link = links.iter().any(|link| link.range.start < end && start < link.range.end);
+            if whole_word && !in_link {
+                found.push(start..end);
+                from = end;
+            } else {
+                // A mention may still start inside this match
+                from = start + text[start..].chars().next().map_or(1, char::len_utf8);
+            }
+        }
+    }
+    found
+}
+
+// The parts of `text` outside fenced code blocks and code spans
+fn prose(text: &str) -> Vec<Range<usize>> {
+    let mut spans = vec![];
+    let mut fence: Option<&str> = None;
+    let mut offset = 0;
+    for line in text.split_inclusive('\n') {
+        let start = offset;
+        offset += line.len();
+        let trimmed = line.trim_start();
+        if let Some(open) = fence {
+            if trimmed.starts_with(open) {
+                fence = None;
+            }
+            continue;
+        }
+        if let Some(marker) = ["```", "~~~"].into_iter().find(|marker| trimmed.starts_with(marker)) {
+            fence = Some(marker);
+            continue;
+        }
+        let mut in_code = false;
+        let mut span_start = start;
+        for (i, _) in line.match_indices('`') {
+            let tick = start + i;
+            if !in_code && span_start < tick {
+                spans.push(span_start..tick);
+            }
+            in_code = !in_code;
+            span_start = tick + 1;
+        }
+        if !in_code && span_start < offset {
+            spans.push(span_start..offset);
+        }
+    }
+    spans
+}
+
+/// Whether a link target names the note titled `title`.
+pub fn targets(target: &str, title: &str) -> bool {
+    target.trim().to_lowercase() == title.trim().to_lowercase()
+}
+
+/// `content` with every link to `old_title` pointed at `new_title`, aliases
+/// kept; `None` if it has no such link.
+pub fn retarget(content: &str, old_title: &str, new_title: &str) -> Option<String> {
+    let links: Vec<WikiLink> = find(content).into_iter().filter(|link| targets(&link.target, old_title)).collect();
+    if links.is_empty() {
+        return None;
+    }
+    let mut out = String::with_capacity(content.len());
+    let mut last = 0;
+    for link in links {
+        out.push_str(&content[last..link.range.start]);
+        match &link.alias {
+            Some(alias) => out.push_str(&format!("[[{}|{}]]", new_title, alias)),
+            None => out.push_str(&format!("[[{}]]", new_title)),
+        }
+        last = link.range.end;
+    }
+    out.push_str(&content[last..]);
+    Some(out)
+}
+
+/// The title being typed into an unclosed `[[` that ends `before_cursor`.
+pub fn partial(before_cursor: &str) -> Option<&str> {
+    let line = before_cursor.rsplit('\n').next().unwrap_or(before_cursor);
+    let start = line.rfind("[[")? + 2;
+    let typed = &line[start..];
+    (!typed.contains([']', '|', '['])).then_some(typed)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn link(target: &str, alias: Option<&str>, range: Range<usize>) -> WikiLink {
+        WikiLink { target: target.to_string(), alias: alias.map(String::from), range }
+    }
+
+    #[test]
+    fn parse_targets_and_aliases() {
+        assert_eq!(parse_at("[[Note]] rest"), Some(("Note".to_string(), None, 8)));
+        assert_eq!(parse_at("[[ Note | shown ]]"), Some(("Note".to_string(), Some("shown".to_string()), 18)));
+        assert_eq!(parse_at("[[Note|]]"), Some(("Note".to_string(), None, 9)));
+        for text in ["[[]]", "[[|alias]]", "[[Note", "[[a\nb]]", "[[a[b]]", "x[[Note]]"] {
+            assert_eq!(parse_at(text), None, "{:?}", text);
+        }
+    }
+
+    #[test]
+    fn find_skips_code() {
+        let text = "See [[A]] and [[B|b]].\n`[[C]]` ```\n```\n[[D]]\n```\n[[E]]é[[F]]";
+        let links = find(text);
+        let targets: Vec<&str> = links.iter().map(|link| link.target.as_str()).collect();
+        assert_eq!(targets, vec!["A", "B", "E", "F"]);
+        assert_eq!(links[0], link("A", None, 4..9));
+        assert_eq!(links[1].label(), "b");
+        assert_eq!(&text[links[3].range.clone()], "[[F]]");
+    }
+
+    #[test]
+    fn mentions_are_whole_words_outside_links() {
+        let text = "Rust and rust, not Rusty or [[Rust]]; `rust` too.";
+        let found: Vec<&str> = mentions(text, "rust").into_iter().map(|range| &text[range]).collect();
+        assert_eq!(found, vec!["Rust", "rust"]);
+        assert_eq!(mentions("a big idea", " Big Idea "), vec![2..10]);
+        assert!(mentions("anything", "  ").is_empty());
+    }
+
+    #[test]
+    fn mentions_of_titles_whose_case_changes_length() {
+        assert_eq!(mentions("see İ here", "İ"), vec![4..6]);
+        assert_eq!(mentions("Die STRAẞE und die straße", "Straße"), vec![4..12, 21..28]);
+        assert_eq!(mentions("ba a a", "a a"), vec![3..6]);
+    }
+
+    #[test]
+    fn retarget_keeps_aliases() {
+        let content = "[[old]] and [[Old|alias]] but not [[Older]] or `[[Old]]`";
+        assert_eq!(
+            retarget(content, "Old", "New").as_deref(),
+            Some("[[New]] and [[New|alias]] but not [[Older]] or `[[Old]]`")
+        );
+        assert_eq!(retarget("[[Other]]", "Old", "New"), None);
+        assert!(targets(" note ", "Note"));
+    }
+
+    #[test]
+    fn partial_title_before_the_cursor() {
+        assert_eq!(partial("text [[Par"), Some("Par"));
+        assert_eq!(partial("[["), Some(""));
+        assert_eq!(partial("[[Done]] more"), None);
+        assert_eq!(partial("[[a|b"), None);
+        assert_eq!(partial("[[a\nb"), None);
+    }
+}
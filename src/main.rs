@@ -1,260 +1,1009 @@
 use eframe::egui::{self, CentralPanel, Context, Key, Layout, RichText, 
     ScrollArea, SidePanel, TextEdit, TextStyle, TopBottomPanel, Visuals};
+use eframe::egui::text::{CCursor, CCursorRange};
+use eframe::egui::text_edit::TextEditState;
+use rusty_notes::settings::{Settings, Theme};
+use rusty_notes::markdown;
+use rusty_notes::vault::{self, RecentVaults};
+use rusty_notes::{DiskChange, Mention, NoteId, NoteStore, SaveOutcome, SortOrder, StoreError, TrashEntry};
+use session::{Session, TabView, VaultSession, View, SESSION_KEY};
+use std::collections::{BTreeMap, HashMap, HashSet};
 use std::fs;
+use std::ops::Range;
 use std::path::{Path, PathBuf};
 use std::time::{Duration, Instant};
+use ui::backlinks::{BacklinkAction, BacklinksPanel};
+use ui::editor::{self as note_editor, LinkCompletion};
+use ui::find::{FindAction, FindBar};
+use ui::graph::GraphView;
+use ui::history::{HistoryOutcome, HistoryView};
+use ui::merge::{MergeOutcome, MergeView};
+use ui::note_tree::{FolderOutcome, FolderPrompt, NoteTree, TreeAction};
+use ui::preview::MarkdownPreview;
+use ui::properties::{PropertiesPanel, PropertyEdit};
+use ui::recovery::{RecoveryAction, RecoveryView};
+use ui::replace::{ReplaceOutcome, ReplaceView};
+use ui::search::{SearchAction, SearchView};
+use ui::settings::{parse_shortcut, SettingsOutcome, SettingsWindow};
+use ui::tag_pane::TagPane;
+use ui::time::{format_age, format_datetime};
+use ui::toasts::Toasts;
+use watcher::DiskWatcher;
 
-struct Note {
-    title: String,
-    content: String,
-    path: PathBuf,
-    unsaved_changes: bool,
-    last_saved: Instant,
-}
+mod session;
+mod ui;
+mod watcher;
+
+// How often the notes directory is checked for changes made by other
+// programs where it cannot be watched, and how soon a change the watcher
+// saw is acted on at the latest
+const DISK_SYNC_INTERVAL: Duration = Duration::from_secs(2);
+
+// How long edits may go without a recovery copy in the swap folder
+const JOURNAL_DELAY: Duration = Duration::from_secs(2);
+
+const USAGE: &str = "Usage: rusty-notes [--vault <folder>]
+
+Options:
+  --vault <folder>  Open the notes in <folder>, creating it if needed
+  -h, --help        Show this help";
+
+// Search, sort order, tag filter and store revision the sidebar list was
+// worked out for
+type SidebarKey = (String, SortOrder, Option<String>, u64);
 
 struct ConfirmationDialog {
     open: bool,
     title: String,
     message: String,
     action_type: DialogAction,
-    target_index: Option<usize>,
+    target: Option<NoteId>,
 }
 
 #[derive(Clone)]
 enum DialogAction {
     DeleteNote,
+    DeleteFolder(PathBuf),
+    PurgeFromTrash(String),
+    EmptyTrash,
     CloseUnsavedTab,
+    ResolveConflict(ConflictChoice),
+    CloseWithUnsaved(CloseChoice),
+}
+
+// How to settle a note that changed both on disk and in the editor
+#[derive(Clone, Copy)]
+enum ConflictChoice {
+    Merge,
+    KeepMine,
+    KeepTheirs,
+    SaveAsCopy,
+}
+
+// What to do with unsaved notes when the window is closed
+#[derive(Clone, Copy)]
+enum CloseChoice {
+    SaveAll,
+    Discard,
+}
+
+// A store operation that failed and can be attempted again from its toast
+#[derive(Clone)]
+enum Retry {
+    Create(PathBuf, String),
+    Save(NoteId),
+    Rename(NoteId, String),
+    Move(NoteId, PathBuf),
+    Delete(NoteId),
+    CreateFolder(PathBuf, String),
+    RenameFolder(PathBuf, String),
+    DeleteFolder(PathBuf),
+    Restore(String),
+    Purge(String),
+    Reload(NoteId),
+    Resolve(NoteId, ConflictChoice),
+    Export(NoteId),
+    Sync,
+    OpenVault(PathBuf),
+    SaveSettings,
+    History(NoteId),
 }
 
 struct AppState {
-    notes: Vec<Note>,
-    open_tabs: Vec<usize>,
-    current_tab: Option<usize>,
+    store: NoteStore,
+    // Folder the store was opened on; None while notes only live in memory
+    vault: Option<PathBuf>,
+    recent_vaults: RecentVaults,
+    // Path typed into the "Open Vault" window while it is shown
+    vault_path_input: Option<String>,
+    open_tabs: Vec<NoteId>,
+    current_tab: Option<NoteId>,
+    // Cursor and scroll position of each open tab
+    tab_views: HashMap<NoteId, TabView>,
+    // Tabs whose restored view has not been applied to the editor yet
+    pending_views: HashSet<NoteId>,
+    // Tabs of the other vaults, kept for the next session
+    vault_sessions: BTreeMap<PathBuf, VaultSession>,
+    sidebar_width: f32,
     search_query: String,
-    notes_dir: PathBuf,
-    editing_title: Option<usize>,
+    // Only notes with this tag, or one nested below it, are listed
+    tag_filter: Option<String>,
+    // Notes the sidebar lists, kept until the search or the notes change
+    // since searching every note each frame would not keep up
+    sidebar_notes: Option<(SidebarKey, Vec<NoteId>)>,
+    // The sidebar lists the trash instead of the notes
+    show_trash: bool,
+    // What is in the trash, refreshed whenever it changes
+    trash: Vec<TrashEntry>,
+    editing_title: Option<NoteId>,
     editing_title_buffer: String,
-    dark_mode: bool,
-    show_preview: bool,
+    settings: Settings,
+    settings_window: Option<SettingsWindow>,
+    folder_prompt: Option<FolderPrompt>,
+    view: View,
+    graph_view: GraphView,
+    search_view: SearchView,
+    // Characters of a note to select and scroll to once its editor is
+    // shown, and whether to focus the editor
+    jump: Option<(NoteId, Range<usize>, bool)>,
+    find_bar: FindBar,
+    replace_view: Option<ReplaceView>,
     confirmation_dialog: ConfirmationDialog,
-    autosave_interval: Duration,
+    last_disk_sync: Instant,
+    // None where the notes directory cannot be watched
+    disk_watcher: Option<DiskWatcher>,
+    merge_view: Option<MergeView>,
+    recovery_view: Option<RecoveryView>,
+    history_view: Option<HistoryView>,
+    properties: PropertiesPanel,
+    backlinks: BacklinksPanel,
+    link_completion: LinkCompletion,
+    toasts: Toasts<Retry>,
+    // Set once unsaved notes have been dealt with and the window may close
+    allow_close: bool,
 }
 
-impl Default for AppState {
-    fn default() -> Self {
-        let notes_dir = std::env::current_dir().unwrap().join("notes");
-        let _ = fs::create_dir_all(&notes_dir);
-        let mut notes = vec![];
-
-        if let Ok(entries) = fs::read_dir(&notes_dir) {
-            for entry in entries.flatten() {
-                let path = entry.path();
-                if path.extension().map_or(false, |e| e == "md") {
-                    let content = fs::read_to_string(&path).unwrap_or_default();
-                    let title = path.file_stem().unwrap_or_default().to_string_lossy().into_owned();
-                    notes.push(Note { 
-                        title, 
-                        content, 
-                        path,
-                        unsaved_changes: false,
-                        last_saved: Instant::now(),
-                    });
-                }
+impl AppState {
+    /// Opens `vault`, or else the default vault from the settings, or else
+    /// the most recently used vault, or else a folder in the user's home,
+    /// and reopens the tabs `session` recorded for it.
+    fn new(vault: Option<PathBuf>, session: Session) -> Self {
+        let mut toasts = Toasts::default();
+        let settings = match Settings::path().map(|path| Settings::load_from(&path)) {
+            Some(Ok(settings)) => settings,
+            Some(Err(e)) => {
+                toasts.error(format!("Using default settings: {}", e), None);
+                Settings::default()
             }
-        }
-        notes.sort_by(|a, b| a.title.to_lowercase().cmp(&b.title.to_lowercase()));
+            None => Settings::default(),
+        };
+        let recent_vaults = RecentVaults::load();
+        let dir = vault
+            .or_else(|| settings.default_vault.clone())
+            .or_else(|| recent_vaults.most_recent().map(Path::to_path_buf))
+            .or_else(vault::default_vault);
 
-        Self {
-            notes,
+        let mut app = Self {
+            store: NoteStore::in_memory(),
+            vault: None,
+            recent_vaults,
+            vault_path_input: None,
             open_tabs: vec![],
             current_tab: None,
+            tab_views: HashMap::new(),
+            pending_views: HashSet::new(),
+            vault_sessions: session.vaults,
+            sidebar_width: session.sidebar_width.unwrap_or(180.0),
             search_query: String::new(),
-            notes_dir,
+            tag_filter: None,
+            sidebar_notes: None,
+            show_trash: false,
+            trash: Vec::new(),
             editing_title: None,
             editing_title_buffer: String::new(),
-            dark_mode: true,
-            show_preview: false,
+            settings,
+            settings_window: None,
+            folder_prompt: None,
+            view: session.view,
+            graph_view: GraphView::default(),
+            search_view: SearchView::default(),
+            jump: None,
+            find_bar: FindBar::default(),
+            replace_view: None,
             confirmation_dialog: ConfirmationDialog {
                 open: false,
                 title: String::new(),
                 message: String::new(),
                 action_type: DialogAction::DeleteNote,
-                target_index: None,
+                target: None,
             },
-            autosave_interval: Duration::from_secs(30),
+            last_disk_sync: Instant::now(),
+            disk_watcher: None,
+            merge_view: None,
+            recovery_view: None,
+            history_view: None,
+            properties: PropertiesPanel::default(),
+            backlinks: BacklinksPanel::default(),
+            link_completion: LinkCompletion::default(),
+            toasts,
+            allow_close: false,
+        };
+        match dir {
+            Some(dir) => app.open_vault(dir),
+            None => app.toasts.error("No home folder found; open a vault to keep notes on disk", None),
         }
+        app
+    }
+
+    /// Switches to the notes in `dir`, creating the folder if needed. Every
+    /// unsaved note of the current vault is saved first; if one cannot be,
+    /// the current vault stays open.
+    fn open_vault(&mut self, dir: PathBuf) {
+        if !self.save_all_notes() {
+            self.toasts.error("Save or resolve the unsaved notes before switching vaults", None);
+            return;
+        }
+        let result = NoteStore::open(&dir);
+        let failure = format!("Could not open vault {}", dir.display());
+        let Some(mut store) = self.report(result, failure, Retry::OpenVault(dir.clone())) else {
+            return;
+        };
+        store.set_retention(self.settings.history);
+        let dir = fs::canonicalize(&dir).unwrap_or(dir);
+
+        self.remember_vault_session();
+        self.store = store;
+        self.open_tabs.clear();
+        self.current_tab = None;
+        self.tab_views.clear();
+        self.pending_views.clear();
+        self.editing_title = None;
+        self.merge_view = None;
+        self.history_view = None;
+        self.replace_view = None;
+        self.folder_prompt = None;
+        self.tag_filter = None;
+        // A new store may start at a revision the old one had reached
+        self.sidebar_notes = None;
+        self.search_view = SearchView::default();
+        self.backlinks = BacklinksPanel::default();
+        self.confirmation_dialog.open = false;
+        self.last_disk_sync = Instant::now();
+        self.disk_watcher = DiskWatcher::new(self.store.dir()).ok();
+        self.purge_expired_trash();
+        for note in self.store.notes() {
+            if let Some(error) = &note.load_error {
+                self.toasts.error(format!("Could not load \"{}\": {}", note.title, error), Some(Retry::Reload(note.id)));
+            }
+        }
+        let recoveries = self.store.recoverable();
+        self.recovery_view = (!recoveries.is_empty()).then(|| RecoveryView::new(&self.store, recoveries));
+        if let Err(e) = self.recent_vaults.touch(&dir) {
+            self.toasts.error(format!("Could not update the recent vaults list: {}", e), None);
+        }
+        self.vault = Some(dir);
+        self.restore_vault_session();
+    }
+
+    // Records the open tabs of the current vault for the next session
+    fn remember_vault_session(&mut self) {
+        let Some(dir) = self.vault.clone() else {
+            return;
+        };
+        let tabs = self
+            .open_tabs
+            .iter()
+            .map(|id| (*id, self.tab_views.get(id).copied().unwrap_or_default()))
+            .collect();
+        self.vault_sessions.insert(dir, VaultSession { tabs, active: self.current_tab });
+    }
+
+    // Reopens the tabs recorded for the current vault, skipping notes that
+    // have been deleted since
+    fn restore_vault_session(&mut self) {
+        let Some(session) = self.vault.as_ref().and_then(|dir| self.vault_sessions.get(dir)) else {
+            return;
+        };
+        let session = session.clone();
+        for (id, view) in session.tabs {
+            if self.store.contains(id) && !self.open_tabs.contains(&id) {
+                self.open_tabs.push(id);
+                self.tab_views.insert(id, view);
+                self.pending_views.insert(id);
+            }
+        }
+        self.current_tab = session
+            .active
+            .filter(|id| self.open_tabs.contains(id))
+            .or_else(|| self.open_tabs.last().copied());
+    }
+
+    fn save_settings(&mut self) {
+        let Some(path) = Settings::path() else {
+            return;
+        };
+        if let Err(e) = self.settings.save_to(&path) {
+            self.toasts.error(format!("Could not save settings: {}", e), Some(Retry::SaveSettings));
+        }
+    }
+
+    fn show_settings_window(&mut self, ctx: &Context) {
+        let Some(window) = self.settings_window.as_mut() else {
+            return;
+        };
+        match window.show(ctx) {
+            Some(SettingsOutcome::Save(settings)) => {
+                self.settings = *settings;
+                self.store.set_retention(self.settings.history);
+                self.save_settings();
+                self.purge_expired_trash();
+                self.settings_window = None;
+            }
+            Some(SettingsOutcome::Cancel) => self.settings_window = None,
+            None => {}
+        }
+    }
+
+    // Theme and font sizes from the settings
+    fn apply_style(&self, ctx: &Context) {
+        ctx.set_visuals(match self.settings.theme {
+            Theme::Dark => Visuals::dark(),
+            Theme::Light => Visuals::light(),
+        });
+        let (editor, interface) = (self.settings.editor_font_size, self.settings.interface_font_size);
+        ctx.style_mut(|style| {
+            for (text_style, font) in style.text_styles.iter_mut() {
+                // Keep egui's proportions between the interface text styles
+                font.size = match text_style {
+                    TextStyle::Monospace => editor,
+                    TextStyle::Heading => interface * 1.44,
+                    TextStyle::Small => interface * 0.72,
+                    _ => interface,
+                };
+            }
+        });
+    }
+
+    fn forget_vault(&mut self, dir: &Path) {
+        if let Err(e) = self.recent_vaults.forget(dir) {
+            self.toasts.error(format!("Could not update the recent vaults list: {}", e), None);
+        }
+    }
+
+    // Saves every note with unsaved edits; false if any is left unsaved
+    fn save_all_notes(&mut self) -> bool {
+        let dirty: Vec<NoteId> = self
+            .store
+            .notes()
+            .iter()
+            .filter(|note| note.unsaved_changes)
+            .map(|note| note.id)
+            .collect();
+        // Try every note rather than stopping at the first failure
+        let saved = dirty.iter().filter(|&&id| self.save_note(id)).count();
+        saved == dirty.len()
     }
-}
 
-impl AppState {
     fn create_note(&mut self) {
-        let title = format!("Note_{}", self.notes.len() + 1);
-        let safe_title = title.replace(|c: char| !c.is_alphanumeric() && c != '_', "_");
-        let path = self.notes_dir.join(format!("{}.md", safe_title));
-        if fs::write(&path, "").is_ok() {
-            let note = Note {
-                title: safe_title.trim_end_matches(".md").to_string(),
-                content: String::new(),
-                path,
-                unsaved_changes: false,
-                last_saved: Instant::now(),
-            };
-            self.notes.push(note);
-            let _idx = self.notes.len() - 1;
-            self.notes.sort_by(|a, b| a.title.to_lowercase().cmp(&b.title.to_lowercase()));
-            
-            // Find the index after sorting
-            let new_idx = self.notes.iter().position(|n| n.title == safe_title).unwrap_or(0);
-            self.open_tabs.push(new_idx);
-            self.current_tab = Some(new_idx);
+        self.create_note_in(PathBuf::new());
+    }
+
+    fn create_note_in(&mut self, folder: PathBuf) {
+        let title = self.store.untitled_title();
+        self.create_note_titled(folder, title);
+    }
+
+    fn create_note_titled(&mut self, folder: PathBuf, title: String) {
+        let result = self.store.create_in(&folder, &title);
+        let failure = format!("Could not create \"{}\"", title);
+        if let Some(id) = self.report(result, failure, Retry::Create(folder, title)) {
+            self.open_note(id);
         }
     }
 
-    fn delete_note(&mut self, i: usize) {
-        let _ = fs::remove_file(&self.notes[i].path);
-        self.notes.remove(i);
-        
-        // Update open tabs
-        self.open_tabs.retain(|&x| x != i);
-        for tab in self.open_tabs.iter_mut() {
-            if *tab > i {
-                *tab -= 1;
+    // Turns a failed store operation into an error toast offering `retry`
+    fn report<T>(&mut self, result: Result<T, StoreError>, failure: String, retry: Retry) -> Option<T> {
+        match result {
+            Ok(value) => Some(value),
+            Err(e) => {
+                // Saving a note that never loaded cannot succeed until it is reloaded
+                let retry = match e {
+                    StoreError::NotLoaded(id) => Retry::Reload(id),
+                    _ => retry,
+                };
+                self.toasts.error(format!("{}: {}", failure, e), Some(retry));
+                None
             }
         }
-        
-        // Update current tab
-        if let Some(current) = self.current_tab {
-            if current == i {
-                self.current_tab = self.open_tabs.last().copied();
-            } else if current > i {
-                self.current_tab = Some(current - 1);
+    }
+
+    fn retry(&mut self, retry: Retry) {
+        match retry {
+            Retry::Create(folder, title) => self.create_note_titled(folder, title),
+            Retry::Save(id) => {
+                self.save_note(id);
+            }
+            Retry::Rename(id, title) => self.rename_note(id, &title),
+            Retry::Move(id, folder) => self.move_note(id, folder),
+            Retry::Delete(id) => self.delete_note(id),
+            Retry::CreateFolder(parent, name) => self.create_folder(parent, &name),
+            Retry::RenameFolder(folder, name) => self.rename_folder(folder, &name),
+            Retry::DeleteFolder(folder) => self.delete_folder(folder),
+            Retry::Restore(file) => self.restore_note(&file),
+            Retry::Purge(file) => self.purge_note(&file),
+            Retry::Reload(id) => {
+                let result = self.store.reload(id);
+                let failure = format!("Could not load \"{}\"", self.note_title(id));
+                self.report(result, failure, Retry::Reload(id));
             }
+            Retry::Resolve(id, choice) => self.resolve_conflict(id, choice),
+            Retry::Export(id) => self.export_note(id),
+            Retry::Sync => self.sync_with_disk(),
+            Retry::OpenVault(dir) => self.open_vault(dir),
+            Retry::SaveSettings => self.save_settings(),
+            Retry::History(id) => self.show_history(id),
         }
-        
-        if self.notes.is_empty() {
-            self.current_tab = None;
+    }
+
+    fn note_title(&self, id: NoteId) -> String {
+        self.store.get(id).map(|note| note.title.clone()).unwrap_or_default()
+    }
+
+    fn open_note(&mut self, id: NoteId) {
+        if !self.open_tabs.contains(&id) {
+            self.open_tabs.push(id);
         }
+        self.current_tab = Some(id);
     }
 
-    fn rename_note(&mut self, idx: usize, new_title: &str) {
-        if new_title.is_empty() {
+    // Opens the note in the editor with `range` of its body selected
+    fn jump_to(&mut self, id: NoteId, range: Range<usize>, focus: bool) {
+        let Some(body) = self.store.get(id).map(|note| note.body()) else {
             return;
+        };
+        let chars = |bytes: Range<usize>| body.get(bytes).map_or(0, |text| text.chars().count());
+        let start = chars(0..range.start);
+        self.jump = Some((id, start..start + chars(range), focus));
+        self.view = View::Edit;
+        self.open_note(id);
+    }
+
+    // Follows a wiki link from the current note, creating the note it
+    // names next to the current one if there is none
+    fn open_wiki_link(&mut self, target: &str) {
+        match self.store.find_by_title(target) {
+            Some(id) => self.open_note(id),
+            None => {
+                let folder = self.current_tab.and_then(|id| self.store.get(id)).map(|note| self.store.folder_of(note));
+                self.create_note_titled(folder.unwrap_or_default(), target.to_string());
+            }
         }
-        
-        if let Some(note) = self.notes.get_mut(idx) {
-            let safe_title = new_title.replace(|c: char| !c.is_alphanumeric() && c != '_', "_");
-            let new_path = self.notes_dir.join(format!("{}.md", safe_title));
-            
-            // Skip if the title hasn't changed
-            if note.title == safe_title {
-                return;
+    }
+
+    // Turns plain-text mentions of note `id` into links to it; the notes
+    // they are in are saved like any other edit
+    fn link_mentions(&mut self, id: NoteId, mentions: &[Mention]) {
+        let title = self.note_title(id);
+        for mention in mentions {
+            if let Err(e) = self.store.link_mention(mention, &title) {
+                self.toasts.error(format!("Could not link \"{}\": {}", self.note_title(mention.source), e), None);
+                break;
             }
-            
-            // Make a reference to the old path to compare later
-            let old_path = note.path.clone();
-            
-            if fs::rename(&note.path, &new_path).is_ok() {
-                note.title = safe_title;
-                note.path = new_path;
-                note.unsaved_changes = true;
-                
-                // Store the current index for this note
-                let current_idx = idx;
-                
-                // Re-sort notes
-                self.notes.sort_by(|a, b| a.title.to_lowercase().cmp(&b.title.to_lowercase()));
-                
-                // Find the new index of the renamed note
-                let new_idx = self.notes.iter().position(|n| n.path == old_path).unwrap_or(current_idx);
-                
-                // Update open tabs indices
-                for i in 0..self.open_tabs.len() {
-                    if self.open_tabs[i] == current_idx {
-                        self.open_tabs[i] = new_idx;
+        }
+    }
+
+    fn close_tab(&mut self, id: NoteId) {
+        self.open_tabs.retain(|&x| x != id);
+        self.tab_views.remove(&id);
+        self.pending_views.remove(&id);
+        if self.current_tab == Some(id) {
+            self.current_tab = self.open_tabs.last().copied();
+        }
+    }
+
+    fn delete_note(&mut self, id: NoteId) {
+        let result = self.store.delete(id);
+        let failure = format!("Could not delete \"{}\"", self.note_title(id));
+        if self.report(result, failure, Retry::Delete(id)).is_some() {
+            self.close_tab(id);
+        }
+        self.trash = self.store.trash();
+    }
+
+    fn move_note(&mut self, id: NoteId, folder: PathBuf) {
+        let result = self.store.move_note(id, &folder);
+        let failure = format!("Could not move \"{}\"", self.note_title(id));
+        self.report(result, failure, Retry::Move(id, folder));
+    }
+
+    fn create_folder(&mut self, parent: PathBuf, name: &str) {
+        let result = self.store.create_folder(&parent, name);
+        let failure = format!("Could not create folder \"{}\"", name);
+        self.report(result, failure, Retry::CreateFolder(parent, name.to_string()));
+    }
+
+    fn rename_folder(&mut self, folder: PathBuf, name: &str) {
+        let result = self.store.rename_folder(&folder, name);
+        let failure = format!("Could not rename folder {}", folder.display());
+        self.report(result, failure, Retry::RenameFolder(folder, name.to_string()));
+    }
+
+    // Moves the folder's notes to the trash and closes their tabs
+    fn delete_folder(&mut self, folder: PathBuf) {
+        let result = self.store.delete_folder(&folder);
+        let failure = format!("Could not delete folder {}", folder.display());
+        self.report(result, failure, Retry::DeleteFolder(folder));
+        let deleted: Vec<NoteId> = self.open_tabs.iter().copied().filter(|&id| !self.store.contains(id)).collect();
+        for id in deleted {
+            self.close_tab(id);
+        }
+        self.trash = self.store.trash();
+    }
+
+    fn show_folder_prompt(&mut self, ctx: &Context) {
+        let Some(prompt) = self.folder_prompt.as_mut() else {
+            return;
+        };
+        let Some(outcome) = prompt.show(ctx) else {
+            return;
+        };
+        self.folder_prompt = None;
+        match outcome {
+            FolderOutcome::Create(parent, name) => self.create_folder(parent, &name),
+            FolderOutcome::Rename(folder, name) => self.rename_folder(folder, &name),
+            FolderOutcome::Cancel => {}
+        }
+    }
+
+    fn restore_note(&mut self, file: &str) {
+        let title = self.trash.iter().find(|entry| entry.file == file).map(|entry| entry.title.clone());
+        let result = self.store.restore_from_trash(file);
+        let failure = format!("Could not restore \"{}\"", title.unwrap_or_default());
+        if let Some(id) = self.report(result, failure, Retry::Restore(file.to_string())) {
+            self.toasts.info(format!("Restored \"{}\"", self.note_title(id)));
+        }
+        self.trash = self.store.trash();
+    }
+
+    fn purge_note(&mut self, file: &str) {
+        let title = self.trash.iter().find(|entry| entry.file == file).map(|entry| entry.title.clone());
+        let result = self.store.purge(file);
+        let failure = format!("Could not purge \"{}\"", title.unwrap_or_default());
+        self.report(result, failure, Retry::Purge(file.to_string()));
+        self.trash = self.store.trash();
+    }
+
+    fn empty_trash(&mut self) {
+        for entry in self.store.trash() {
+            if let Err(e) = self.store.purge(&entry.file) {
+                self.toasts.error(format!("Could not empty the trash: {}", e), None);
+                break;
+            }
+        }
+        self.trash = self.store.trash();
+    }
+
+    // Purges notes that have been in the trash longer than the settings allow
+    fn purge_expired_trash(&mut self) {
+        if let Some(age) = self.settings.trash_retention() {
+            if let Err(e) = self.store.purge_older_than(age) {
+                self.toasts.error(format!("Could not empty old notes from the trash: {}", e), None);
+            }
+        }
+        self.trash = self.store.trash();
+    }
+
+    fn rename_note(&mut self, id: NoteId, new_title: &str) {
+        let result = self.store.rename(id, new_title);
+        let failure = format!("Could not rename \"{}\"", self.note_title(id));
+        self.report(result, failure, Retry::Rename(id, new_title.to_string()));
+    }
+    
+    fn save_current_note(&mut self) -> bool {
+        match self.current_tab {
+            Some(id) => self.save_note(id),
+            None => false,
+        }
+    }
+
+    fn save_note(&mut self, id: NoteId) -> bool {
+        let result = self.store.save(id);
+        let failure = format!("Could not save \"{}\"", self.note_title(id));
+        match self.report(result, failure, Retry::Save(id)) {
+            Some(SaveOutcome::Saved) => true,
+            Some(SaveOutcome::Conflict) => {
+                self.show_conflict_dialog(id);
+                false
+            }
+            _ => false,
+        }
+    }
+    
+    fn show_history(&mut self, id: NoteId) {
+        let result = HistoryView::new(&self.store, id);
+        let failure = format!("Could not read the history of \"{}\"", self.note_title(id));
+        self.history_view = self.report(result, failure, Retry::History(id));
+    }
+
+    fn open_find_bar(&mut self, replace: bool) {
+        if self.current_tab.is_some() {
+            self.view = View::Edit;
+            self.find_bar.open(replace);
+        }
+    }
+
+    fn show_replace_view(&mut self, ctx: &Context) {
+        let Some(view) = self.replace_view.as_mut() else {
+            return;
+        };
+        match view.show(ctx, &self.store) {
+            Some(ReplaceOutcome::Apply(notes)) => {
+                self.replace_view = None;
+                for changes in notes {
+                    let id = changes.id;
+                    match self.store.apply_changes(&changes) {
+                        Ok(true) => {
+                            self.save_note(id);
+                        }
+                        Ok(false) => {
+                            let message = format!("\"{}\" was left alone, as it changed after the preview", self.note_title(id));
+                            self.toasts.error(message, None);
+                        }
+                        Err(e) => self.toasts.error(format!("Could not replace in \"{}\": {}", self.note_title(id), e), None),
                     }
                 }
-                
-                // Update current tab
-                if let Some(tab_idx) = self.current_tab {
-                    if tab_idx == current_idx {
-                        self.current_tab = Some(new_idx);
+            }
+            Some(ReplaceOutcome::Close) => self.replace_view = None,
+            None => {}
+        }
+    }
+
+    fn show_history_view(&mut self, ctx: &Context) {
+        let Some(view) = self.history_view.as_mut() else {
+            return;
+        };
+        match view.show(ctx, &self.store) {
+            Some(HistoryOutcome::Restore(content)) => {
+                let id = view.id;
+                if let Some(note) = self.store.get_mut(id) {
+                    note.set_content(content);
+                }
+                self.open_note(id);
+                self.history_view = None;
+            }
+            Some(HistoryOutcome::Close) => self.history_view = None,
+            None => {}
+        }
+    }
+
+    fn write_journal(&mut self) {
+        if let Err(e) = self.store.write_journal(JOURNAL_DELAY) {
+            self.toasts.error(format!("Could not keep a recovery copy of unsaved edits: {}", e), None);
+        }
+    }
+
+    fn show_recovery_view(&mut self, ctx: &Context) {
+        let Some(view) = self.recovery_view.as_mut() else {
+            return;
+        };
+        let mut open = true;
+        let actions = view.show(ctx, &mut open);
+        if !open || view.is_empty() {
+            self.recovery_view = None;
+        }
+        for action in actions {
+            match action {
+                RecoveryAction::Restore(id, content) => {
+                    if let Some(note) = self.store.get_mut(id) {
+                        note.set_content(content);
                     }
+                    self.open_note(id);
                 }
+                RecoveryAction::Discard(id) => self.store.discard_swap(id),
+            }
+        }
+    }
+
+    fn autosave_notes(&mut self) {
+        let report = self.store.autosave(self.settings.autosave_interval());
+        for (id, e) in report.failed {
+            let failure = format!("Could not autosave \"{}\"", self.note_title(id));
+            self.report::<()>(Err(e), failure, Retry::Save(id));
+        }
+        if let Some(&id) = report.conflicts.first() {
+            if !self.confirmation_dialog.open && self.merge_view.is_none() {
+                self.show_conflict_dialog(id);
             }
         }
     }
     
-    fn find_note_by_path(&self, path: &Path) -> Option<usize> {
-        self.notes.iter().position(|note| note.path == path)
+    fn show_conflict_dialog(&mut self, id: NoteId) {
+        let Some(note) = self.store.get(id) else {
+            return;
+        };
+        self.confirmation_dialog = ConfirmationDialog {
+            open: true,
+            title: "Note Changed on Disk".to_string(),
+            message: format!(
+                "\"{}\" was changed by another program since it was opened, and also has unsaved edits here.",
+                note.title
+            ),
+            action_type: DialogAction::ResolveConflict(ConflictChoice::Merge),
+            target: Some(id),
+        };
     }
     
-    fn save_current_note(&mut self) -> bool {
-        if let Some(idx) = self.current_tab {
-            let note = &mut self.notes[idx];
-            if note.unsaved_changes {
-                if fs::write(&note.path, &note.content).is_ok() {
-                    note.unsaved_changes = false;
-                    note.last_saved = Instant::now();
-                    return true;
+    fn resolve_conflict(&mut self, id: NoteId, choice: ConflictChoice) {
+        match choice {
+            ConflictChoice::Merge => {
+                let result = self.store.read_disk(id);
+                let failure = format!("Could not read \"{}\" from disk", self.note_title(id));
+                let Some(theirs) = self.report(result, failure, Retry::Resolve(id, choice)) else {
+                    return;
+                };
+                let Some(note) = self.store.get(id) else {
+                    return;
+                };
+                self.merge_view = Some(MergeView::new(id, &note.title, &note.disk_content, &note.content, &theirs));
+            }
+            ConflictChoice::KeepMine => {
+                let result = self.store.overwrite(id);
+                let failure = format!("Could not save \"{}\"", self.note_title(id));
+                self.report(result, failure, Retry::Resolve(id, choice));
+            }
+            ConflictChoice::KeepTheirs => {
+                let result = self.store.reload(id);
+                let failure = format!("Could not reload \"{}\"", self.note_title(id));
+                self.report(result, failure, Retry::Reload(id));
+            }
+            ConflictChoice::SaveAsCopy => {
+                let result = self.store.save_as_copy(id);
+                let failure = format!("Could not save a copy of \"{}\"", self.note_title(id));
+                if let Some(copy) = self.report(result, failure, Retry::Resolve(id, choice)) {
+                    self.open_note(copy);
                 }
             }
         }
-        false
     }
     
-    fn autosave_notes(&mut self) {
-        let now = Instant::now();
-        for (_i, note) in self.notes.iter_mut().enumerate() {
-            if note.unsaved_changes && now.duration_since(note.last_saved) >= self.autosave_interval {
-                if fs::write(&note.path, &note.content).is_ok() {
-                    note.unsaved_changes = false;
-                    note.last_saved = now;
+    fn show_vault_menu(&mut self, ui: &mut egui::Ui) {
+        let label = match &self.vault {
+            Some(dir) => format!("🗄 {}", vault::vault_name(dir)),
+            None => "🗄 Not saved".to_string(),
+        };
+        let recent = self.recent_vaults.list().to_vec();
+        let hover = self.vault.as_ref().map_or("Notes are kept in memory only".to_string(), |dir| dir.display().to_string());
+        ui.menu_button(label, |ui| {
+            ui.label(RichText::new("Recent vaults").strong());
+            for dir in &recent {
+                let current = self.vault.as_ref() == Some(dir);
+                ui.horizontal(|ui| {
+                    if dir.is_dir() {
+                        if ui.selectable_label(current, vault::vault_name(dir)).on_hover_text(dir.display().to_string()).clicked() {
+                            if !current {
+                                self.open_vault(dir.clone());
+                            }
+                            ui.close_menu();
+                        }
+                    } else {
+                        ui.label(RichText::new(vault::vault_name(dir)).strikethrough())
+                            .on_hover_text(format!("{} no longer exists", dir.display()));
+                        if ui.small_button("✖").on_hover_text("Remove from list").clicked() {
+                            self.forget_vault(dir);
+                        }
+                    }
+                });
+            }
+            if recent.is_empty() {
+                ui.label("None yet");
+            }
+            ui.separator();
+            if ui.button("Open or create folder…").clicked() {
+                self.vault_path_input = Some(String::new());
+                ui.close_menu();
+            }
+        })
+        .response
+        .on_hover_text(hover);
+    }
+
+    fn show_open_vault_window(&mut self, ctx: &Context) {
+        let Some(input) = self.vault_path_input.as_mut() else {
+            return;
+        };
+        let mut open = true;
+        let mut chosen = None;
+        egui::Window::new("Open Vault")
+            .open(&mut open)
+            .collapsible(false)
+            .resizable(false)
+            .show(ctx, |ui| {
+                ui.label("Folder to keep notes in. It is created if it does not exist.");
+                let response = ui.text_edit_singleline(input);
+                let entered = response.lost_focus() && ui.input(|i| i.key_pressed(Key::Enter));
+                if (ui.button("Open").clicked() || entered) && !input.trim().is_empty() {
+                    chosen = Some(vault::expand_home(input));
+                }
+            });
+        if !open || chosen.is_some() {
+            self.vault_path_input = None;
+        }
+        if let Some(dir) = chosen {
+            self.open_vault(dir);
+        }
+    }
+
+    fn show_merge_view(&mut self, ctx: &Context) {
+        let Some(view) = self.merge_view.as_mut() else {
+            return;
+        };
+        match view.show(ctx) {
+            Some(MergeOutcome::Apply(merged)) => {
+                let id = view.id;
+                if let Some(note) = self.store.get_mut(id) {
+                    note.set_content(merged);
                 }
+                let result = self.store.overwrite(id);
+                let failure = format!("Could not save \"{}\"", self.note_title(id));
+                self.report(result, failure, Retry::Resolve(id, ConflictChoice::KeepMine));
+                self.merge_view = None;
             }
+            Some(MergeOutcome::Cancel) => self.merge_view = None,
+            None => {}
         }
     }
     
-    fn count_words_and_chars(&self, idx: usize) -> (usize, usize) {
-        if let Some(note) = self.notes.get(idx) {
-            let chars = note.content.chars().count();
-            let words = note.content.split_whitespace().count();
-            (words, chars)
+    fn sync_with_disk(&mut self) {
+        self.last_disk_sync = Instant::now();
+        let result = self.store.sync_with_disk();
+        let failure = "Could not check the notes folder for changes".to_string();
+        let Some(changes) = self.report(result, failure, Retry::Sync) else {
+            return;
+        };
+        for change in changes {
+            match change {
+                DiskChange::Removed(note) => {
+                    self.close_tab(note.id);
+                    if self.editing_title == Some(note.id) {
+                        self.editing_title = None;
+                    }
+                }
+                // The editor shows the new text next frame; a title being
+                // typed would undo a new title from disk
+                DiskChange::Reloaded(id) if self.open_tabs.contains(&id) => {
+                    if self.editing_title == Some(id) {
+                        self.editing_title = None;
+                    }
+                    self.toasts.info(format!("Reloaded \"{}\", which changed on disk", self.note_title(id)));
+                }
+                DiskChange::Diverged(id) => self.report_divergence(id),
+                _ => {}
+            }
+        }
+    }
+
+    // Tells the user that a note with unsaved edits changed on disk, and
+    // lets them resolve it while the outside change is fresh
+    fn report_divergence(&mut self, id: NoteId) {
+        let title = self.note_title(id);
+        if self.store.get(id).is_some_and(|note| note.disk_modified.is_none()) {
+            self.toasts.info(format!("\"{}\" was deleted on disk; saving writes it again", title));
+        } else if !self.confirmation_dialog.open && self.merge_view.is_none() {
+            self.show_conflict_dialog(id);
         } else {
-            (0, 0)
+            // Saving runs into the conflict and asks again
+            self.toasts.error(format!("\"{}\" changed on disk and has unsaved edits", title), Some(Retry::Save(id)));
         }
     }
     
-    fn render_markdown_to_html(&self, markdown: &str) -> String {
-        // Simple markdown rendering without using pulldown_cmark
-        let mut html_output = String::new();
-        
-        // Process line by line for basic markdown support
-        for line in markdown.lines() {
-            let trimmed = line.trim();
-            if trimmed.is_empty() {
-                html_output.push_str("<p></p>\n");
-            } else if trimmed.starts_with("# ") {
-                html_output.push_str(&format!("<h1>{}</h1>\n", &trimmed[2..]));
-            } else if trimmed.starts_with("## ") {
-                html_output.push_str(&format!("<h2>{}</h2>\n", &trimmed[3..]));
-            } else if trimmed.starts_with("### ") {
-                html_output.push_str(&format!("<h3>{}</h3>\n", &trimmed[4..]));
-            } else if trimmed.starts_with("- ") || trimmed.starts_with("* ") {
-                html_output.push_str(&format!("<li>{}</li>\n", &trimmed[2..]));
-            } else if trimmed.starts_with("> ") {
-                html_output.push_str(&format!("<blockquote>{}</blockquote>\n", &trimmed[2..]));
-            } else if trimmed.starts_with("```") {
-                html_output.push_str("<pre><code>\n");
-            } else if trimmed.ends_with("```") {
-                html_output.push_str("</code></pre>\n");
-            } else {
-                html_output.push_str(&format!("<p>{}</p>\n", trimmed));
-            }
+    fn count_words_and_chars(&self, id: NoteId) -> (usize, usize) {
+        self.store.get(id).map_or((0, 0), |note| note.count_words_and_chars())
+    }
+    
+    fn export_current_note(&mut self) {
+        if let Some(id) = self.current_tab {
+            self.export_note(id);
+        }
+    }
+
+    fn export_note(&mut self, id: NoteId) {
+        let result = self.store.export_html(id);
+        let failure = format!("Could not export \"{}\"", self.note_title(id));
+        if let Some(path) = self.report(result, failure, Retry::Export(id)) {
+            self.toasts.info(format!("Exported to {}", path.display()));
         }
-        
-        html_output
     }
     
+    // Keeps the window open while notes have unsaved changes, saving them
+    // or asking what to do with them
+    fn handle_close_request(&mut self, ctx: &Context) {
+        let unsaved = self.store.notes().iter().filter(|note| note.unsaved_changes).count();
+        if unsaved == 0 {
+            return;
+        }
+        ctx.send_viewport_cmd(egui::ViewportCommand::CancelClose);
+        let mut problem = "have unsaved changes";
+        if self.settings.save_on_close {
+            if self.save_all_notes() {
+                self.close_window(ctx);
+                return;
+            }
+            // A note that can never be saved must not keep the window open
+            // for good, so ask as if saving on close were off
+            problem = "could not be saved";
+        }
+        let unsaved = self.store.notes().iter().filter(|note| note.unsaved_changes).count();
+        self.confirmation_dialog = ConfirmationDialog {
+            open: true,
+            title: "Unsaved Changes".to_string(),
+            message: format!(
+                "{} note(s) {}. Discarded edits are still copied to the vault's {} folder.",
+                unsaved,
+                problem,
+                rusty_notes::store::SWAP_DIR
+            ),
+            action_type: DialogAction::CloseWithUnsaved(CloseChoice::SaveAll),
+            target: None,
+        };
+    }
+
+    fn close_window(&mut self, ctx: &Context) {
+        self.allow_close = true;
+        ctx.send_viewport_cmd(egui::ViewportCommand::Close);
+    }
+
+    // Deleted notes with the buttons to restore or purge them
+    fn show_trash_list(&mut self, ui: &mut egui::Ui) {
+        if self.trash.is_empty() {
+            ui.label("The trash is empty.");
+            return;
+        }
+        if ui.button("Empty Trash").clicked() {
+            self.confirmation_dialog = ConfirmationDialog {
+                open: true,
+                title: "Empty Trash".to_string(),
+                message: format!("Delete the {} note(s) in the trash forever?", self.trash.len()),
+                action_type: DialogAction::EmptyTrash,
+                target: None,
+            };
+        }
+        if self.settings.trash_retention_days > 0 {
+            let days = self.settings.trash_retention_days;
+            ui.label(RichText::new(format!("Notes are deleted forever after {} days.", days)).small().weak());
+        }
+        ui.add_space(5.0);
+
+        let mut restore = None;
+        ScrollArea::vertical().show(ui, |ui| {
+            for entry in &self.trash {
+                let deleted = entry.deleted_at.elapsed().map(|age| format_age(age.as_secs())).unwrap_or_default();
+                ui.horizontal(|ui| {
+                    ui.label(&entry.title)
+                        .on_hover_text(format!("{}\nDeleted {}", entry.original.display(), format_datetime(entry.deleted_at)));
+                    ui.label(RichText::new(format!("{} ago", deleted)).small().weak());
+                });
+                ui.horizontal(|ui| {
+                    if ui.button("↩ Restore").clicked() {
+                        restore = Some(entry.file.clone());
+                    }
+                    if ui.button("Delete Forever").clicked() {
+                        self.confirmation_dialog = ConfirmationDialog {
+                            open: true,
+                            title: "Confirm Deletion".to_string(),
+                            message: format!("Delete \"{}\" and its history forever?", entry.title),
+                            action_type: DialogAction::PurgeFromTrash(entry.file.clone()),
+                            target: None,
+                        };
+                    }
+                });
+                ui.separator();
+            }
+        });
+        if let Some(file) = restore {
+            self.restore_note(&file);
+        }
+    }
+
     fn show_confirmation_dialog(&mut self, ctx: &Context) -> Option<DialogAction> {
         if !self.confirmation_dialog.open {
             return None;
@@ -274,8 +1023,35 @@ impl AppState {
                     }
                     
                     let confirm_text = match self.confirmation_dialog.action_type {
-                        DialogAction::DeleteNote => "Delete",
+                        DialogAction::DeleteNote | DialogAction::DeleteFolder(_) => "Move to Trash",
+                        DialogAction::PurgeFromTrash(_) => "Delete Forever",
+                        DialogAction::EmptyTrash => "Empty Trash",
                         DialogAction::CloseUnsavedTab => "Close without saving",
+                        DialogAction::ResolveConflict(_) => {
+                            let choices = [
+                                ("Merge…", ConflictChoice::Merge),
+                                ("Keep mine", ConflictChoice::KeepMine),
+                                ("Keep theirs", ConflictChoice::KeepTheirs),
+                                ("Save as copy", ConflictChoice::SaveAsCopy),
+                            ];
+                            for (text, choice) in choices {
+                                if ui.button(text).clicked() {
+                                    action = Some(DialogAction::ResolveConflict(choice));
+                                    self.confirmation_dialog.open = false;
+                                }
+                            }
+                            return;
+                        }
+                        DialogAction::CloseWithUnsaved(_) => {
+                            let choices = [("Save all", CloseChoice::SaveAll), ("Discard", CloseChoice::Discard)];
+                            for (text, choice) in choices {
+                                if ui.button(text).clicked() {
+                                    action = Some(DialogAction::CloseWithUnsaved(choice));
+                                    self.confirmation_dialog.open = false;
+                                }
+                            }
+                            return;
+                        }
                     };
                     
                     if ui.button(confirm_text).clicked() {
@@ -290,69 +1066,118 @@ impl AppState {
 }
 
 impl eframe::App for AppState {
+    fn save(&mut self, storage: &mut dyn eframe::Storage) {
+        self.remember_vault_session();
+        let session = Session {
+            view: self.view,
+            sidebar_width: Some(self.sidebar_width),
+            vaults: self.vault_sessions.clone(),
+        };
+        eframe::set_value(storage, SESSION_KEY, &session);
+    }
+
+    fn on_exit(&mut self, _gl: Option<&eframe::glow::Context>) {
+        // Last chance for edits that were discarded or could not be saved
+        if let Err(e) = self.store.write_swap_files() {
+            eprintln!("Could not keep a copy of unsaved notes: {}", e);
+        }
+    }
+
     fn update(&mut self, ctx: &Context, _frame: &mut eframe::Frame) {
-        // Process keyboard shortcuts
-        let ctrl = ctx.input(|i| i.modifiers.ctrl);
-        let _shift = ctx.input(|i| i.modifiers.shift);
-        
-        // Keyboard shortcuts
-        if ctrl {
-            if ctx.input(|i| i.key_pressed(Key::N)) {
-                // Ctrl+N: New note
-                self.create_note();
-            } else if ctx.input(|i| i.key_pressed(Key::S)) {
-                // Ctrl+S: Save current note
-                self.save_current_note();
-            } else if ctx.input(|i| i.key_pressed(Key::P)) {
-                // Ctrl+P: Toggle preview
-                self.show_preview = !self.show_preview;
-            } else if ctx.input(|i| i.key_pressed(Key::W)) {
-                // Ctrl+W: Close current tab
-                if let Some(idx) = self.current_tab {
-                    let note = &self.notes[idx];
-                    if note.unsaved_changes {
-                        // Show confirmation dialog
-                        self.confirmation_dialog = ConfirmationDialog {
-                            open: true,
-                            title: "Unsaved Changes".to_string(),
-                            message: format!("The note \"{}\" has unsaved changes. Close without saving?", note.title),
-                            action_type: DialogAction::CloseUnsavedTab,
-                            target_index: Some(idx),
-                        };
-                    } else {
-                        self.open_tabs.retain(|&x| x != idx);
-                        self.current_tab = self.open_tabs.last().copied();
-                    }
+        // Keyboard shortcuts, as configured in the settings
+        let bindings = self.settings.keybindings.clone();
+        let pressed = |binding: &str| {
+            parse_shortcut(binding).is_some_and(|shortcut| ctx.input_mut(|i| i.consume_shortcut(&shortcut)))
+        };
+        if pressed(&bindings.new_note) {
+            self.create_note();
+        } else if pressed(&bindings.save) {
+            self.save_current_note();
+        } else if pressed(&bindings.toggle_preview) {
+            self.view = if self.view == View::Preview { View::Edit } else { View::Preview };
+        } else if pressed(&bindings.toggle_graph) {
+            self.view = if self.view == View::Graph { View::Edit } else { View::Graph };
+        } else if pressed(&bindings.find) {
+            self.open_find_bar(false);
+        } else if pressed(&bindings.replace) {
+            self.open_find_bar(true);
+        } else if pressed(&bindings.close_tab) {
+            if let Some(note) = self.current_tab.and_then(|id| self.store.get(id)) {
+                if note.unsaved_changes {
+                    // Show confirmation dialog
+                    self.confirmation_dialog = ConfirmationDialog {
+                        open: true,
+                        title: "Unsaved Changes".to_string(),
+                        message: format!("The note \"{}\" has unsaved changes. Close without saving?", note.title),
+                        action_type: DialogAction::CloseUnsavedTab,
+                        target: Some(note.id),
+                    };
+                } else {
+                    let id = note.id;
+                    self.close_tab(id);
                 }
             }
         }
         
-        // Apply theme
-        ctx.set_visuals(if self.dark_mode {
-            Visuals::dark()
-        } else {
-            Visuals::light()
-        });
+        if ctx.input(|i| i.viewport().close_requested()) && !self.allow_close {
+            self.handle_close_request(ctx);
+        }
+        
+        self.apply_style(ctx);
         
         // Process any dialog actions
         if let Some(action) = self.show_confirmation_dialog(ctx) {
             match action {
                 DialogAction::DeleteNote => {
-                    if let Some(idx) = self.confirmation_dialog.target_index {
-                        self.delete_note(idx);
+                    if let Some(id) = self.confirmation_dialog.target {
+                        self.delete_note(id);
                     }
                 },
+                DialogAction::DeleteFolder(folder) => self.delete_folder(folder),
+                DialogAction::PurgeFromTrash(file) => self.purge_note(&file),
+                DialogAction::EmptyTrash => self.empty_trash(),
                 DialogAction::CloseUnsavedTab => {
-                    if let Some(idx) = self.confirmation_dialog.target_index {
-                        self.open_tabs.retain(|&x| x != idx);
-                        self.current_tab = self.open_tabs.last().copied();
+                    if let Some(id) = self.confirmation_dialog.target {
+                        self.close_tab(id);
                     }
                 }
+                DialogAction::ResolveConflict(choice) => {
+                    if let Some(id) = self.confirmation_dialog.target {
+                        self.resolve_conflict(id, choice);
+                    }
+                }
+                DialogAction::CloseWithUnsaved(CloseChoice::SaveAll) => {
+                    if self.save_all_notes() {
+                        self.close_window(ctx);
+                    }
+                }
+                DialogAction::CloseWithUnsaved(CloseChoice::Discard) => self.close_window(ctx),
             }
         }
+        self.show_merge_view(ctx);
+        self.show_open_vault_window(ctx);
+        self.show_settings_window(ctx);
+        self.show_folder_prompt(ctx);
+        self.show_recovery_view(ctx);
+        self.show_history_view(ctx);
+        self.show_replace_view(ctx);
+        if let Some(retry) = self.toasts.show(ctx) {
+            self.retry(retry);
+        }
+        
+        // Pick up edits made outside the app before autosave can clobber them
+        let changed = match &self.disk_watcher {
+            Some(watcher) => watcher.take_changed(),
+            None => self.last_disk_sync.elapsed() >= DISK_SYNC_INTERVAL,
+        };
+        if changed {
+            self.sync_with_disk();
+        }
+        ctx.request_repaint_after(DISK_SYNC_INTERVAL);
         
         // Periodic autosave check
         self.autosave_notes();
+        self.write_journal();
 
         TopBottomPanel::top("top_panel").show(ctx, |ui| {
             ui.horizontal(|ui| {
@@ -365,101 +1190,154 @@ impl eframe::App for AppState {
                     self.save_current_note();
                 }
                 
+                if ui.button("🌐").on_hover_text("Export as HTML").clicked() {
+                    self.export_current_note();
+                }
+                
+                ui.separator();
+                
+                self.show_vault_menu(ui);
+                
                 ui.separator();
                 
                 ui.label("Search:");
-                ui.text_edit_singleline(&mut self.search_query);
+                let search = ui.text_edit_singleline(&mut self.search_query).on_hover_text(
+                    "Words, \"exact phrases\", OR, NOT or -word, (groups),\n\
+                     tag:name, title:word, path:folder, modified:2024-03, date:>=2024-01-01\n\
+                     Enter lists every match",
+                );
+                if search.lost_focus() && ui.input(|i| i.key_pressed(Key::Enter)) {
+                    self.view = View::Search;
+                }
                 
                 ui.separator();
                 
-                if ui.button(if self.dark_mode { "🌞 Light" } else { "🌙 Dark" }).clicked() {
-                    self.dark_mode = !self.dark_mode;
+                let dark = self.settings.theme == Theme::Dark;
+                if ui.button(if dark { "🌞 Light" } else { "🌙 Dark" }).clicked() {
+                    self.settings.theme = if dark { Theme::Light } else { Theme::Dark };
+                    self.save_settings();
+                }
+                
+                if ui.button("⚙").on_hover_text("Settings").clicked() && self.settings_window.is_none() {
+                    self.settings_window = Some(SettingsWindow::new(&self.settings));
                 }
                 
                 ui.with_layout(Layout::right_to_left(egui::Align::Center), |ui| {
-                    if let Some(_idx) = self.current_tab {
-                        if ui.button(if self.show_preview { "✏️ Edit" } else { "👁️ Preview" })
-                            .on_hover_text("Toggle Preview (Ctrl+P)")
-                            .clicked() 
-                        {
-                            self.show_preview = !self.show_preview;
+                    let views = [
+                        (View::Search, "🔍 Results", "Every match of the search".to_string()),
+                        (View::Graph, "🕸 Graph", format!("Links between notes ({})", bindings.toggle_graph)),
+                        (View::Preview, "👁️ Preview", format!("Rendered note ({})", bindings.toggle_preview)),
+                        (View::Edit, "✏️ Edit", "Edit the note".to_string()),
+                    ];
+                    for (view, text, hover) in views {
+                        if matches!(view, View::Search | View::Graph) || self.current_tab.is_some() {
+                            ui.selectable_value(&mut self.view, view, text).on_hover_text(hover);
                         }
                     }
                 });
             });
         });
 
-        SidePanel::left("side_panel")
+        let side_panel = SidePanel::left("side_panel")
             .resizable(true)
-            .default_width(180.0)
+            .default_width(self.sidebar_width)
             .min_width(120.0)
             .show(ctx, |ui| {
-                ui.heading("Notes");
+                ui.horizontal(|ui| {
+                    ui.heading(if self.show_trash { "Trash" } else { "Notes" });
+                    ui.with_layout(Layout::right_to_left(egui::Align::Center), |ui| {
+                        let label = if self.show_trash { "⬅ Notes".to_string() } else { format!("🗑 {}", self.trash.len()) };
+                        let hover = if self.show_trash { "Back to the notes" } else { "Show deleted notes" };
+                        if ui.button(label).on_hover_text(hover).clicked() {
+                            self.show_trash = !self.show_trash;
+                        }
+                        if !self.show_trash && ui.button("📁+").on_hover_text("New folder").clicked() {
+                            self.folder_prompt = Some(FolderPrompt::new_folder(PathBuf::new()));
+                        }
+                    });
+                });
                 ui.separator();
-                
-                let filtered_notes: Vec<_> = self.notes.iter().enumerate()
-                    .filter(|(_, note)| {
-                        let query = self.search_query.to_lowercase();
-                        query.is_empty() || 
-                            note.title.to_lowercase().contains(&query) || 
-                            note.content.to_lowercase().contains(&query)
-                    })
-                    .collect();
-                
-                ScrollArea::vertical().show(ui, |ui| {
-                    for &(i, note) in &filtered_notes {
-                        ui.horizontal(|ui| {
-                            let mut title_text = note.title.clone();
-                            if note.unsaved_changes {
-                                title_text.push('*');
-                            }
-                            
-                            // Highlight open notes
-                            let is_open = self.open_tabs.contains(&i);
-                            let is_current = self.current_tab == Some(i);
-                            
-                            let text = if is_current {
-                                RichText::new(&title_text).strong()
-                            } else if is_open {
-                                RichText::new(&title_text).italics()
-                            } else {
-                                RichText::new(&title_text)
-                            };
-                            
-                            if ui.button(text).on_hover_text("Open note").clicked() {
-                                if !self.open_tabs.contains(&i) {
-                                    self.open_tabs.push(i);
-                                }
-                                self.current_tab = Some(i);
-                            }
-                            
-                            if ui.button("🗑").on_hover_text("Delete note").clicked() {
-                                self.confirmation_dialog = ConfirmationDialog {
-                                    open: true,
-                                    title: "Confirm Deletion".to_string(),
-                                    message: format!("Are you sure you want to delete \"{}\"?", note.title),
-                                    action_type: DialogAction::DeleteNote,
-                                    target_index: Some(i),
-                                };
-                            }
-                        });
-                    }
-                    
-                    if filtered_notes.is_empty() {
-                        ui.label("No notes match your search.");
+
+                if self.show_trash {
+                    self.show_trash_list(ui);
+                    return;
+                }
+
+                let tag_counts = self.store.tag_counts();
+                TopBottomPanel::bottom("tags_panel").resizable(true).default_height(150.0).show_inside(ui, |ui| {
+                    ui.add_space(4.0);
+                    ui.strong("Tags");
+                    if let Some(tag) = TagPane::new(&tag_counts, self.tag_filter.as_deref()).show(ui) {
+                        self.tag_filter = if self.tag_filter.as_ref() == Some(&tag) { None } else { Some(tag) };
                     }
                 });
+
+                if let Some(tag) = &self.tag_filter {
+                    let mut clear = false;
+                    ui.horizontal(|ui| {
+                        ui.label("Tagged");
+                        clear = ui.button(format!("#{} ✖", tag)).on_hover_text("Show all notes").clicked();
+                    });
+                    if clear {
+                        self.tag_filter = None;
+                    }
+                }
+
+                let order = self.settings.sort_order;
+                let key = (self.search_query.clone(), order, self.tag_filter.clone(), self.store.revision());
+                let (_, filtered_notes) = match self.sidebar_notes.take() {
+                    Some(cached) if cached.0 == key => self.sidebar_notes.insert(cached),
+                    _ => {
+                        let mut notes = self.store.query(&self.search_query, order);
+                        if let Some(tag) = &self.tag_filter {
+                            notes.retain(|&id| self.store.get(id).is_some_and(|note| note.has_tag(tag)));
+                        }
+                        self.sidebar_notes.insert((key, notes))
+                    }
+                };
+                let searching = !self.search_query.is_empty() || self.tag_filter.is_some();
+                let tree = NoteTree::new(&self.store, filtered_notes, searching)
+                    .highlight(&self.open_tabs, self.current_tab);
+                match tree.show(ui) {
+                    Some(TreeAction::Open(id)) => self.open_note(id),
+                    Some(TreeAction::Delete(id)) => {
+                        self.confirmation_dialog = ConfirmationDialog {
+                            open: true,
+                            title: "Confirm Deletion".to_string(),
+                            message: format!("Move \"{}\" to the trash?", self.note_title(id)),
+                            action_type: DialogAction::DeleteNote,
+                            target: Some(id),
+                        };
+                    }
+                    Some(TreeAction::Move(id, folder)) => self.move_note(id, folder),
+                    Some(TreeAction::NewNote(folder)) => self.create_note_in(folder),
+                    Some(TreeAction::NewFolder(parent)) => self.folder_prompt = Some(FolderPrompt::new_folder(parent)),
+                    Some(TreeAction::RenameFolder(folder)) => self.folder_prompt = Some(FolderPrompt::rename(folder)),
+                    Some(TreeAction::DeleteFolder(folder)) => {
+                        self.confirmation_dialog = ConfirmationDialog {
+                            open: true,
+                            title: "Delete Folder".to_string(),
+                            message: format!("Move {} and every note in it to the trash?", folder.display()),
+                            action_type: DialogAction::DeleteFolder(folder),
+                            target: None,
+                        };
+                    }
+                    None => {}
+                }
             });
+        self.sidebar_width = side_panel.response.rect.width();
 
         CentralPanel::default().show(ctx, |ui| {
             ui.with_layout(Layout::top_down(eframe::egui::Align::Min), |ui| {
                 // Tab bar
                 ui.horizontal_wrapped(|ui| {
-                    let mut tab_to_close: Option<usize> = None;
+                    let mut tab_to_close: Option<NoteId> = None;
                     
-                    for &tab_idx in &self.open_tabs {
-                        let note = &self.notes[tab_idx];
-                        let selected = self.current_tab == Some(tab_idx);
+                    for &tab_id in &self.open_tabs {
+                        let Some(note) = self.store.get(tab_id) else {
+                            continue;
+                        };
+                        let selected = self.current_tab == Some(tab_id);
                         
                         ui.horizontal(|ui| {
                             let mut title_text = note.title.clone();
@@ -474,11 +1352,10 @@ impl eframe::App for AppState {
                             };
                             
                             if ui.selectable_label(selected, text).clicked() {
-                                self.current_tab = Some(tab_idx);
+                                self.current_tab = Some(tab_id);
                             }
                             
                             if ui.button("❌").on_hover_text("Close tab (Ctrl+W)").clicked() {
-                                let note = &self.notes[tab_idx];
                                 if note.unsaved_changes {
                                     // Show confirmation dialog
                                     self.confirmation_dialog = ConfirmationDialog {
@@ -486,30 +1363,40 @@ impl eframe::App for AppState {
                                         title: "Unsaved Changes".to_string(),
                                         message: format!("The note \"{}\" has unsaved changes. Close without saving?", note.title),
                                         action_type: DialogAction::CloseUnsavedTab,
-                                        target_index: Some(tab_idx),
+                                        target: Some(tab_id),
                                     };
                                 } else {
-                                    tab_to_close = Some(tab_idx);
+                                    tab_to_close = Some(tab_id);
                                 }
                             }
                         });
                     }
 
-                    if let Some(idx) = tab_to_close {
-                        self.open_tabs.retain(|&x| x != idx);
-                        if self.current_tab == Some(idx) {
-                            self.current_tab = self.open_tabs.last().copied();
-                        }
+                    if let Some(id) = tab_to_close {
+                        self.close_tab(id);
                     }
                 });
 
                 ui.separator();
 
-                if let Some(idx) = self.current_tab {
+                if self.view == View::Graph {
+                    if let Some(id) = self.graph_view.show(ui, &self.store, self.current_tab) {
+                        self.open_note(id);
+                    }
+                } else if self.view == View::Search {
+                    let tag = self.tag_filter.as_deref();
+                    match self.search_view.show(ui, &self.store, &self.search_query, self.settings.sort_order, tag) {
+                        Some(SearchAction::Open(id)) => self.open_note(id),
+                        Some(SearchAction::Jump(id, range)) => self.jump_to(id, range, true),
+                        None => {}
+                    }
+                } else if let Some(note) = self.current_tab.and_then(|id| self.store.get(id)) {
                     // Note title area
-                    let title = self.notes[idx].title.clone();
+                    let id = note.id;
+                    let title = note.title.clone();
+                    let load_error = note.load_error.clone();
 
-                    if self.editing_title == Some(idx) {
+                    if self.editing_title == Some(id) {
                         // Title editing mode
                         let mut new_title = self.editing_title_buffer.clone();
                         ui.horizontal(|ui| {
@@ -522,7 +1409,7 @@ impl eframe::App for AppState {
 
                             if enter_pressed || ok_clicked {
                                 let new_title = self.editing_title_buffer.clone();
-        self.rename_note(idx, &new_title);
+                                self.rename_note(id, &new_title);
                                 self.editing_title = None;
                             } else if cancel_clicked {
                                 self.editing_title = None;
@@ -533,92 +1420,163 @@ impl eframe::App for AppState {
                         ui.horizontal(|ui| {
                             ui.heading(&title);
                             if ui.button("✏️ Rename").clicked() {
-                                self.editing_title = Some(idx);
+                                self.editing_title = Some(id);
                                 self.editing_title_buffer = title;
                             }
+                            if ui.button("🕘 History").on_hover_text("Saved versions of this note").clicked() {
+                                self.show_history(id);
+                            }
+                        });
+                    }
+
+                    if let Some(error) = load_error {
+                        ui.horizontal(|ui| {
+                            ui.colored_label(
+                                ui.visuals().error_fg_color,
+                                format!("This note could not be loaded and will not be saved: {}", error),
+                            );
+                            if ui.button("Retry").clicked() {
+                                self.retry(Retry::Reload(id));
+                            }
                         });
                     }
 
+                    if let Some(note) = self.store.get(id) {
+                        match self.properties.show(ui, note) {
+                            Some(PropertyEdit::Set(key, value)) => {
+                                if let Some(note) = self.store.get_mut(id) {
+                                    note.set_property(&key, value);
+                                }
+                            }
+                            Some(PropertyEdit::Remove(key)) => {
+                                if let Some(note) = self.store.get_mut(id) {
+                                    note.remove_property(&key);
+                                }
+                            }
+                            None => {}
+                        }
+                    }
+
+                    let action = TopBottomPanel::bottom("backlinks_panel")
+                        .show_inside(ui, |ui| self.backlinks.show(ui, &self.store, id))
+                        .inner;
+                    match action {
+                        Some(BacklinkAction::Open(source)) => self.open_note(source),
+                        Some(BacklinkAction::Link(mentions)) => self.link_mentions(id, &mentions),
+                        None => {}
+                    }
+
                     // Note content area with preview
-                    if self.show_preview {
+                    if self.view == View::Preview {
                         // Make a copy of the content for preview
-                        let content_copy = self.notes[idx].content.clone();
-                        let html_content = self.render_markdown_to_html(&content_copy);
+                        let content_copy = self.store.get(id).map(|note| note.body().to_string()).unwrap_or_default();
+                        let document = markdown::parse(&content_copy);
+                        let store = &self.store;
+                        let link_exists = |title: &str| store.find_by_title(title).is_some();
                         
-                        ScrollArea::vertical().show(ui, |ui| {
+                        let clicked = ScrollArea::vertical().show(ui, |ui| {
                             ui.add_space(5.0);
                             ui.label(RichText::new("Preview Mode").italics());
                             ui.separator();
                             
-                            // Basic HTML rendering with Label
-                            for line in html_content.lines() {
-                                let clean_line = line.trim();
-                                if !clean_line.is_empty() {
-                                    if clean_line.starts_with("<h1>") {
-                                        let text = clean_line.replace("<h1>", "").replace("</h1>", "");
-                                        ui.heading(text);
-                                    } else if clean_line.starts_with("<h2>") {
-                                        let text = clean_line.replace("<h2>", "").replace("</h2>", "");
-                                        ui.heading(text);
-                                    } else if clean_line.starts_with("<h3>") {
-                                        let text = clean_line.replace("<h3>", "").replace("</h3>", "");
-                                        ui.heading(text);
-                                    } else if clean_line.starts_with("<p>") {
-                                        let text = clean_line.replace("<p>", "").replace("</p>", "");
-                                        ui.label(text);
-                                    } else if clean_line.starts_with("<ul>") || 
-                                              clean_line.starts_with("</ul>") || 
-                                              clean_line.starts_with("<ol>") || 
-                                              clean_line.starts_with("</ol>") {
-                                        // Skip list container tags
-                                        continue;
-                                    } else if clean_line.starts_with("<li>") {
-                                        let text = clean_line.replace("<li>", "• ").replace("</li>", "");
-                                        ui.label(text);
-                                    } else if clean_line.starts_with("<blockquote>") {
-                                        let text = clean_line.replace("<blockquote>", "").replace("</blockquote>", "");
-                                        ui.label(RichText::new(text).italics());
-                                    } else if clean_line.starts_with("<pre>") || 
-                                              clean_line.starts_with("<code>") || 
-                                              clean_line.starts_with("</pre>") || 
-                                              clean_line.starts_with("</code>") {
-                                        // Handle code blocks
-                                        let text = clean_line
-                                            .replace("<pre>", "")
-                                            .replace("</pre>", "")
-                                            .replace("<code>", "")
-                                            .replace("</code>", "");
-                                        if !text.is_empty() {
-                                            ui.monospace(text);
-                                        }
-                                    } else {
-                                        // Default rendering for other elements
-                                        ui.label(clean_line);
-                                    }
-                                } else {
-                                    ui.add_space(5.0);
-                                }
-                            }
+                            MarkdownPreview::new(&document).link_exists(&link_exists).show(ui)
                         });
+                        if let Some(target) = clicked.inner {
+                            self.open_wiki_link(&target);
+                        }
                     } else {
-                        // Edit mode
+                        // Edit mode; the front matter is edited in the properties panel
+                        let mut content = self.store.get(id).map(|note| note.body().to_string()).unwrap_or_default();
+                        let mut replaced = false;
+                        match self.find_bar.show(ui, &mut content) {
+                            Some(FindAction::Select { range, focus }) => self.jump_to(id, range, focus),
+                            Some(FindAction::Replaced) => replaced = true,
+                            Some(FindAction::ReplaceInVault(finder, replacement)) => {
+                                self.replace_view = Some(ReplaceView::new(&self.store, &finder, &replacement));
+                            }
+                            None => {}
+                        }
                         let available_size = ui.available_size();
                         let editor_size = egui::Vec2::new(
                             available_size.x,
                             available_size.y - 20.0  // Reserve space for status bar
                         );
                         
-                        let mut content = self.notes[idx].content.clone();
-                        let response = ui.add_sized(
-                            editor_size,
-                            TextEdit::multiline(&mut content)
+                        // Put the cursor and scroll back where they were last session
+                        let editor_id = egui::Id::new(("editor", id));
+                        let mut scroll_area = ScrollArea::vertical().id_source(("editor_scroll", id)).max_height(editor_size.y);
+                        let restore = self.pending_views.remove(&id).then(|| self.tab_views.get(&id).copied()).flatten();
+                        if let Some(view) = restore {
+                            scroll_area = scroll_area.vertical_scroll_offset(view.scroll);
+                            if let Some(cursor) = view.cursor {
+                                let mut state = TextEditState::load(ui.ctx(), editor_id).unwrap_or_default();
+                                state.cursor.set_char_range(Some(CCursorRange::one(CCursor::new(cursor))));
+                                state.store(ui.ctx(), editor_id);
+                            }
+                        }
+                        let jump = self.jump.take_if(|(note, ..)| *note == id).map(|(_, range, focus)| (range, focus));
+                        if let Some((range, _)) = &jump {
+                            let mut state = TextEditState::load(ui.ctx(), editor_id).unwrap_or_default();
+                            let (start, end) = (CCursor::new(range.start), CCursor::new(range.end));
+                            state.cursor.set_char_range(Some(CCursorRange::two(start, end)));
+                            state.store(ui.ctx(), editor_id);
+                        }
+                        
+                        let mut completed = self
+                            .link_completion
+                            .take_keys(ui)
+                            .is_some_and(|title| note_editor::complete_link(ui, editor_id, &mut content, &title));
+                        let titles: HashSet<String> = self.store.notes().iter().map(|note| note.title.to_lowercase()).collect();
+                        let find_bar = &self.find_bar;
+                        let mut layouter = |ui: &egui::Ui, text: &str, wrap_width: f32| {
+                            let mut job = note_editor::highlight(ui, text, &titles);
+                            let (matches, current) = find_bar.matches(text);
+                            note_editor::mark_matches(ui, &mut job, &matches, current);
+                            job.wrap.max_width = wrap_width;
+                            ui.fonts(|fonts| fonts.layout_job(job))
+                        };
+                        let output = scroll_area.show(ui, |ui| {
+                            let output = TextEdit::multiline(&mut content)
+                                .id(editor_id)
                                 .font(TextStyle::Monospace)
                                 .desired_width(f32::INFINITY)
-                        );
+                                .min_size(editor_size)
+                                .layouter(&mut layouter)
+                                .show(ui);
+                            if let Some((range, _)) = &jump {
+                                let cursor = output.galley.pos_from_ccursor(CCursor::new(range.start));
+                                ui.scroll_to_rect(cursor.translate(output.galley_pos.to_vec2()), Some(egui::Align::Center));
+                            }
+                            output
+                        });
+                        let response = &output.inner.response;
+                        if restore.is_some() || jump.is_some_and(|(_, focus)| focus) {
+                            response.request_focus();
+                        }
+                        
+                        let view = self.tab_views.entry(id).or_default();
+                        view.scroll = output.state.offset.y;
+                        if let Some(range) = output.inner.cursor_range {
+                            view.cursor = Some(range.primary.ccursor.index);
+                        }
+
+                        // Ctrl+click follows a wiki link
+                        let mut follow = None;
+                        if response.clicked() && ui.input(|i| i.modifiers.command) {
+                            follow = note_editor::link_at_cursor(&content, &output.inner);
+                        }
+                        let titles = self.store.notes().iter().map(|note| note.title.as_str());
+                        if let Some(title) = self.link_completion.show(ui, &content, &output.inner, titles) {
+                            completed |= note_editor::complete_link(ui, editor_id, &mut content, &title);
+                        }
                         
-                        if response.changed() {
-                            self.notes[idx].unsaved_changes = true;
-                            self.notes[idx].content = content;
+                        if response.changed() || completed || replaced {
+                            if let Some(note) = self.store.get_mut(id) {
+                                note.set_body(content);
+                            }
+                        }
+                        if let Some(target) = follow {
+                            self.open_wiki_link(&target);
                         }
                     }
                     
@@ -626,8 +1584,8 @@ impl eframe::App for AppState {
                     TopBottomPanel::bottom("status_bar").show_inside(ui, |ui| {
                         ui.horizontal(|ui| {
                             // Get a copy of the note info for the status bar
-                            let unsaved = self.notes[idx].unsaved_changes;
-                            let (words, chars) = self.count_words_and_chars(idx);
+                            let unsaved = self.store.get(id).is_some_and(|note| note.unsaved_changes);
+                            let (words, chars) = self.count_words_and_chars(id);
                             
                             ui.label(format!("Words: {}, Characters: {}", words, chars));
                             
@@ -657,8 +1615,32 @@ impl eframe::App for AppState {
     }
 }
 
+// Reads the command line, returning the vault to open if one was given
+fn parse_args(mut args: impl Iterator<Item = String>) -> Result<Option<PathBuf>, String> {
+    let mut vault = None;
+    while let Some(arg) = args.next() {
+        if arg == "-h" || arg == "--help" {
+            println!("{}", USAGE);
+            std::process::exit(0);
+        } else if arg == "--vault" {
+            let dir = args.next().ok_or("--vault needs a folder")?;
+            vault = Some(PathBuf::from(dir));
+        } else if let Some(dir) = arg.strip_prefix("--vault=") {
+            vault = Some(PathBuf::from(dir));
+        } else {
+            return Err(format!("Unknown argument: {}", arg));
+        }
+    }
+    Ok(vault)
+}
+
 fn main() -> eframe::Result<()> {
-    // Note: No need for the pulldown_cmark dependency as we're using our own markdown renderer
+    let vault = parse_args(std::env::args().skip(1)).unwrap_or_else(|message| {
+        eprintln!("{}\n\n{}", message, USAGE);
+        std::process::exit(2);
+    });
+    
+    // Note: No need for the pulldown_cmark dependency as we're using our own markdown parser
     
     let options = eframe::NativeOptions {
         // Since the API changed, use default options
@@ -668,6 +1650,9 @@ fn main() -> eframe::Result<()> {
     eframe::run_native(
         "rusty-notes",
         options,
-        Box::new(|_cc| Box::new(AppState::default())),
+        Box::new(|cc| {
+            let session = cc.storage.and_then(|storage| eframe::get_value(storage, SESSION_KEY)).unwrap_or_default();
+            Box::new(AppState::new(vault, session))
+        }),
     )
 }
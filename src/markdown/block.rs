@@ -0,0 +1,822 @@
+use super::inline::{self, Context};
+use super::{Alignment, Block, Document, Footnote, List, ListItem, Table};
+use std::collections::HashMap;
+
+/// Blocks as the line pass sees them: inline content is still raw text,
+/// because link reference and footnote definitions anywhere in the
+/// document decide how it parses.
+enum RawBlock {
+    Heading(u8, String),
+    Paragraph(String),
+    CodeBlock { info: String, code: String },
+    BlockQuote(Vec<RawBlock>),
+    List(RawList),
+    Table {
+        alignments: Vec<Alignment>,
+        header: Vec<String>,
+        rows: Vec<Vec<String>>,
+    },
+    ThematicBreak,
+}
+
+struct RawList {
+    start: Option<u64>,
+    tight: bool,
+    items: Vec<(Option<bool>, Vec<RawBlock>)>,
+}
+
+#[derive(Default)]
+struct Definitions {
+    links: HashMap<String, (String, String)>,
+    footnotes: Vec<(String, Vec<RawBlock>)>,
+}
+
+pub(super) fn parse_document(source: &str) -> Document {
+    let lines: Vec<String> = source.lines().map(expand_leading_tabs).collect();
+    let mut defs = Definitions::default();
+    let (raw, _) = parse_blocks(&lines, &mut defs);
+
+    let ctx = Context {
+        links: defs.links,
+        footnotes: defs.footnotes.iter().map(|(label, _)| normalize_label(label)).collect(),
+    };
+    Document {
+        blocks: finish_blocks(raw, &ctx),
+        footnotes: defs
+            .footnotes
+            .into_iter()
+            .map(|(label, blocks)| Footnote { label, blocks: finish_blocks(blocks, &ctx) })
+            .collect(),
+    }
+}
+
+/// Case-folded, whitespace-collapsed form used to match reference labels.
+pub(super) fn normalize_label(label: &str) -> String {
+    label.split_whitespace().collect::<Vec<_>>().join(" ").to_lowercase()
+}
+
+fn finish_blocks(raw: Vec<RawBlock>, ctx: &Context) -> Vec<Block> {
+    raw.into_iter().map(|block| finish_block(block, ctx)).collect()
+}
+
+fn finish_block(raw: RawBlock, ctx: &Context) -> Block {
+    match raw {
+        RawBlock::Heading(level, text) => Block::Heading { level, content: inline::parse(&text, ctx) },
+        RawBlock::Paragraph(text) => Block::Paragraph(inline::parse(&text, ctx)),
+        RawBlock::CodeBlock { info, code } => Block::CodeBlock { info, code },
+        RawBlock::BlockQuote(children) => Block::BlockQuote(finish_blocks(children, ctx)),
+        RawBlock::List(list) => Block::List(List {
+            start: list.start,
+            tight: list.tight,
+            items: list
+                .items
+                .into_iter()
+                .map(|(task, blocks)| ListItem { task, blocks: finish_blocks(blocks, ctx) })
+                .collect(),
+        }),
+        RawBlock::Table { alignments, header, rows } => Block::Table(Table {
+            alignments,
+            header: header.iter().map(|cell| inline::parse(cell, ctx)).collect(),
+            rows: rows
+                .iter()
+                .map(|row| row.iter().map(|cell| inline::parse(cell, ctx)).collect())
+                .collect(),
+        }),
+        RawBlock::ThematicBreak => Block::ThematicBreak,
+    }
+}
+
+fn expand_leading_tabs(line: &str) -> String {
+    let mut out = String::new();
+    let mut column = 0;
+    let mut chars = line.char_indices();
+    for (i, c) in chars.by_ref() {
+        match c {
+            ' ' => {
+                out.push(' ');
+                column += 1;
+            }
+            '\t' => {
+                let width = 4 - column % 4;
+                out.extend(std::iter::repeat_n(' ', width));
+                column += width;
+            }
+            _ => {
+                out.push_str(&line[i..]);
+                return out;
+            }
+        }
+    }
+    out
+}
+
+fn indent_of(line: &str) -> usize {
+    line.len() - line.trim_start_matches(' ').len()
+}
+
+fn is_blank(line: &str) -> bool {
+    line.trim().is_empty()
+}
+
+/// Removes up to `n` leading spaces.
+fn strip_indent(line: &str, n: usize) -> &str {
+    let strip = indent_of(line).min(n);
+    &line[strip..]
+}
+
+/// Parses a run of lines into blocks. The flag reports whether a blank line
+/// separated two of the blocks, which makes a containing list item loose.
+fn parse_blocks(lines: &[String], defs: &mut Definitions) -> (Vec<RawBlock>, bool) {
+    let mut blocks = vec![];
+    let mut blank_between = false;
+    let mut pending_blank = false;
+    let mut i = 0;
+
+    while i < lines.len() {
+        let line = lines[i].as_str();
+        if is_blank(line) {
+            pending_blank = true;
+            i += 1;
+            continue;
+        }
+
+        let blocks_before = blocks.len();
+        i = parse_block(lines, i, defs, &mut blocks);
+        if blocks.len() > blocks_before {
+            if pending_blank && blocks_before > 0 {
+                blank_between = true;
+            }
+            pending_blank = false;
+        }
+    }
+
+    (blocks, blank_between)
+}
+
+/// Parses the block starting at `lines[start]` and returns the index of the
+/// first line after it.
+fn parse_block(lines: &[String], start: usize, defs: &mut Definitions, blocks: &mut Vec<RawBlock>) -> usize {
+    let line = lines[start].as_str();
+    let indent = indent_of(line);
+
+    if indent >= 4 {
+        return parse_indented_code(lines, start, blocks);
+    }
+
+    let rest = &line[indent..];
+    if let Some((level, text)) = atx_heading(rest) {
+        blocks.push(RawBlock::Heading(level, text));
+        return start + 1;
+    }
+    if let Some((fence_char, fence_len, info)) = fence_open(rest) {
+        return parse_fenced_code(lines, start, indent, fence_char, fence_len, info, blocks);
+    }
+    if is_thematic_break(rest) {
+        blocks.push(RawBlock::ThematicBreak);
+        return start + 1;
+    }
+    if rest.starts_with('>') {
+        return parse_block_quote(lines, start, defs, blocks);
+    }
+    if let Some(label) = footnote_definition_label(rest) {
+        return parse_footnote_definition(lines, start, label, defs);
+    }
+    if list_marker(line).is_some() {
+        return parse_list(lines, start, defs, blocks);
+    }
+    if let Some(end) = parse_table(lines, start, blocks) {
+        return end;
+    }
+    if let Some((label, url, title)) = link_reference_definition(rest) {
+        defs.links.entry(normalize_label(&label)).or_insert((url, title));
+        return start + 1;
+    }
+    parse_paragraph(lines, start, blocks)
+}
+
+fn parse_indented_code(lines: &[String], start: usize, blocks: &mut Vec<RawBlock>) -> usize {
+    let mut end = start;
+    let mut code_lines = vec![];
+    while end < lines.len() && (is_blank(&lines[end]) || indent_of(&lines[end]) >= 4) {
+        code_lines.push(strip_indent(&lines[end], 4));
+        end += 1;
+    }
+    while code_lines.last().is_some_and(|l| is_blank(l)) {
+        code_lines.pop();
+        end -= 1;
+    }
+    let mut code = code_lines.join("\n");
+    code.push('\n');
+    blocks.push(RawBlock::CodeBlock { info: String::new(), code });
+    end
+}
+
+fn atx_heading(rest: &str) -> Option<(u8, String)> {
+    let level = rest.chars().take_while(|&c| c == '#').count();
+    if !(1..=6).contains(&level) {
+        return None;
+    }
+    let after = &rest[level..];
+    if !after.is_empty() && !after.starts_with(' ') {
+        return None;
+    }
+    let mut text = after.trim();
+    // An optional closing sequence of `#`s must be preceded by a space
+    let without_closing = text.trim_end_matches('#');
+    if without_closing.is_empty() {
+        text = "";
+    } else if without_closing.ends_with(' ') {
+        text = without_closing.trim_end();
+    }
+    Some((level as u8, text.to_string()))
+}
+
+fn fence_open(rest: &str) -> Option<(char, usize, String)> {
+    let fence_char = rest.chars().next().filter(|&c| c == '`' || c == '~')?;
+    let fence_len = rest.chars().take_while(|&c| c == fence_char).count();
+    if fence_len < 3 {
+        return None;
+    }
+    let info = rest[fence_len..].trim();
+    if fence_char == '`' && info.contains('`') {
+        return None;
+    }
+    Some((fence_char, fence_len, info.to_string()))
+}
+
+fn is_fence_close(line: &str, fence_char: char, fence_len: usize) -> bool {
+    if indent_of(line) >= 4 {
+        return false;
+    }
+    let rest = line.trim();
+    rest.chars().take_while(|&c| c == fence_char).count() >= fence_len && rest.chars().all(|c| c == fence_char)
+}
+
+fn parse_fenced_code(
+    lines: &[String],
+    start: usize,
+    indent: usize,
+    fence_char: char,
+    fence_len: usize,
+    info: String,
+    blocks: &mut Vec<RawBlock>,
+) -> usize {
+    let mut code = String::new();
+    let mut i = start + 1;
+    while i < lines.len() {
+        if is_fence_close(&lines[i], fence_char, fence_len) {
+            i += 1;
+            break;
+        }
+        code.push_str(strip_indent(&lines[i], indent));
+        code.push('\n');
+        i += 1;
+    }
+    // Only the first word of the info string names the language
+    let info = info.split_whitespace().next().unwrap_or_default().to_string();
+    blocks.push(RawBlock::CodeBlock { info, code });
+    i
+}
+
+fn is_thematic_break(rest: &str) -> bool {
+    let mut chars = rest.chars().filter(|c| !c.is_whitespace());
+    let Some(first) = chars.next() else {
+        return false;
+    };
+    if !matches!(first, '-' | '*' | '_') {
+        return false;
+    }
+    let mut count = 1;
+    for c in chars {
+        if c != first {
+            return false;
+        }
+        count += 1;
+    }
+    count >= 3
+}
+
+/// Whether `line` would start a block other than a paragraph, i.e. whether
+/// it interrupts a paragraph instead of continuing it.
+fn interrupts_paragraph(line: &str) -> bool {
+    let indent = indent_of(line);
+    if indent >= 4 || is_blank(line) {
+        return false;
+    }
+    let rest = &line[indent..];
+    if atx_heading(rest).is_some() || fence_open(rest).is_some() || is_thematic_break(rest) || rest.starts_with('>') {
+        return true;
+    }
+    match list_marker(line) {
+        // Empty items and ordered lists not starting at 1 cannot interrupt
+        Some(marker) => !marker.empty && marker.start.is_none_or(|n| n == 1),
+        None => false,
+    }
+}
+
+fn parse_block_quote(lines: &[String], start: usize, defs: &mut Definitions, blocks: &mut Vec<RawBlock>) -> usize {
+    let mut inner = vec![];
+    let mut i = start;
+    while i < lines.len() {
+        let line = lines[i].as_str();
+        let indent = indent_of(line);
+        if indent < 4 && line[indent..].starts_with('>') {
+            let after = &line[indent + 1..];
+            inner.push(after.strip_prefix(' ').unwrap_or(after).to_string());
+        } else if !is_blank(line)
+            && inner.last().is_some_and(|l: &String| !is_blank(l) && !is_fence_open_line(l))
+            && !interrupts_paragraph(line)
+        {
+            // Lazy continuation of a paragraph inside the quote
+            inner.push(line.to_string());
+        } else {
+            break;
+        }
+        i += 1;
+    }
+    let (children, _) = parse_blocks(&inner, defs);
+    blocks.push(RawBlock::BlockQuote(children));
+    i
+}
+
+fn is_fence_open_line(line: &str) -> bool {
+    indent_of(line) < 4 && fence_open(line.trim_start()).is_some()
+}
+
+fn footnote_definition_label(rest: &str) -> Option<String> {
+    let after = rest.strip_prefix("[^")?;
+    let close = after.find("]:")?;
+    let label = &after[..close];
+    if label.is_empty() || label.contains(char::is_whitespace) {
+        return None;
+    }
+    Some(label.to_string())
+}
+
+fn parse_footnote_definition(lines: &[String], start: usize, label: String, defs: &mut Definitions) -> usize {
+    let first = lines[start].trim_start();
+    let first = first[label.len() + 4..].trim_start();
+    let mut inner = vec![first.to_string()];
+    let mut i = start + 1;
+    while i < lines.len() {
+        let line = lines[i].as_str();
+        if is_blank(line) || indent_of(line) >= 4 {
+            inner.push(strip_indent(line, 4).to_string());
+        } else if inner.last().is_some_and(|l| !is_blank(l)) && !interrupts_paragraph(line) && footnote_definition_label(line.trim_start()).is_none() {
+            inner.push(line.to_string());
+        } else {
+            break;
+        }
+        i += 1;
+    }
+    // The definition's own line stays, even when nothing follows the label
+    while inner.len() > 1 && inner.last().is_some_and(|l| is_blank(l)) {
+        inner.pop();
+        i -= 1;
+    }
+    let (children, _) = parse_blocks(&inner, defs);
+    defs.footnotes.push((label, children));
+    i
+}
+
+struct ListMarker {
+    /// Bullet character, or the `.`/`)` after an ordered number.
+    kind: char,
+    start: Option<u64>,
+    /// Column where the item's content begins.
+    content_indent: usize,
+    /// Nothing follows the marker on its line.
+    empty: bool,
+}
+
+fn list_marker(line: &str) -> Option<ListMarker> {
+    let indent = indent_of(line);
+    if indent >= 4 {
+        return None;
+    }
+    let rest = &line[indent..];
+    let (kind, start, marker_len) = match rest.chars().next()? {
+        c @ ('-' | '+' | '*') => (c, None, 1),
+        c if c.is_ascii_digit() => {
+            let digits = rest.chars().take_while(|c| c.is_ascii_digit()).count();
+            if digits > 9 {
+                return None;
+            }
+            let kind = rest[digits..].chars().next().filter(|&c| c == '.' || c == ')')?;
+            (kind, Some(rest[..digits].parse().ok()?), digits + 1)
+        }
+        _ => return None,
+    };
+    let after = &rest[marker_len..];
+    if after.trim().is_empty() {
+        return Some(ListMarker { kind, start, content_indent: indent + marker_len + 1, empty: true });
+    }
+    let spaces = indent_of(after);
+    if spaces == 0 {
+        return None;
+    }
+    // Five or more spaces means the content is an indented code block
+    let spaces = if spaces > 4 { 1 } else { spaces };
+    Some(ListMarker { kind, start, content_indent: indent + marker_len + spaces, empty: false })
+}
+
+fn parse_list(lines: &[String], start: usize, defs: &mut Definitions, blocks: &mut Vec<RawBlock>) -> usize {
+    let first = list_marker(&lines[start]).expect("caller checked for a list marker");
+    let mut items = vec![];
+    let mut tight = true;
+    let mut i = start;
+
+    while i < lines.len() {
+        let Some(marker) = list_marker(&lines[i]).filter(|m| m.kind == first.kind && !(is_thematic_break(lines[i].trim()) && m.start.is_none())) else {
+            break;
+        };
+
+        let item_start = i;
+        let first_line = &lines[i];
+        let mut item_lines = vec![if marker.empty {
+            String::new()
+        } else {
+            first_line[marker.content_indent.min(first_line.len())..].to_string()
+        }];
+        i += 1;
+
+        while i < lines.len() {
+            let line = lines[i].as_str();
+            if is_blank(line) {
+                // An empty item ends at its first blank line
+                if marker.empty && item_lines.len() == 1 {
+                    break;
+                }
+                item_lines.push(String::new());
+            } else if indent_of(line) >= marker.content_indent {
+                item_lines.push(line[marker.content_indent..].to_string());
+            } else if item_lines.last().is_some_and(|l| !is_blank(l) && !is_fence_open_line(l))
+                && !interrupts_paragraph(line)
+                && list_marker(line).is_none()
+            {
+                item_lines.push(line.trim_start().to_string());
+            } else {
+                break;
+            }
+            i += 1;
+        }
+
+        // The marker's line is never a trailing blank, even in an empty item
+        let mut trailing_blanks = 0;
+        while item_lines.len() > 1 && item_lines.last().is_some_and(|l| is_blank(l)) {
+            item_lines.pop();
+            trailing_blanks += 1;
+        }
+
+        let (mut children, blank_between) = parse_blocks(&item_lines, defs);
+        if blank_between {
+            tight = false;
+        }
+        let task = take_task_marker(&mut children);
+        items.push((task, children));
+
+        // A blank line before the next item of this list makes it loose
+        let continues = i < lines.len() && list_marker(&lines[i]).is_some_and(|m| m.kind == first.kind);
+        if trailing_blanks > 0 {
+            if continues {
+                tight = false;
+            } else {
+                // Hand the blank lines back to whatever follows the list
+                i = (i - trailing_blanks).max(item_start + 1);
+                break;
+            }
+        }
+    }
+
+    blocks.push(RawBlock::List(RawList { start: first.start, tight, items }));
+    i
+}
+
+/// Strips a leading `[ ]` / `[x]` from the item's first paragraph.
+fn take_task_marker(children: &mut [RawBlock]) -> Option<bool> {
+    let Some(RawBlock::Paragraph(text)) = children.first_mut() else {
+        return None;
+    };
+    let checked = match text.get(..3)? {
+        "[ ]" => false,
+        "[x]" | "[X]" => true,
+        _ => return None,
+    };
+    let rest = &text[3..];
+    if !(rest.is_empty() || rest.starts_with(' ')) {
+        return None;
+    }
+    *text = rest.trim_start().to_string();
+    Some(checked)
+}
+
+fn split_table_row(line: &str) -> Vec<String> {
+    let mut row = line.trim();
+    row = row.strip_prefix('|').unwrap_or(row);
+    if row.ends_with('|') && !row.ends_with("\\|") {
+        row = &row[..row.len() - 1];
+    }
+
+    let mut cells = vec![];
+    let mut cell = String::new();
+    let mut in_code = false;
+    let mut chars = row.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' if chars.peek() == Some(&'|') => {
+                cell.push('|');
+                chars.next();
+            }
+            '`' => {
+                in_code = !in_code;
+                cell.push(c);
+            }
+            '|' if !in_code => cells.push(std::mem::take(&mut cell).trim().to_string()),
+            _ => cell.push(c),
+        }
+    }
+    cells.push(cell.trim().to_string());
+    cells
+}
+
+fn table_alignments(line: &str) -> Option<Vec<Alignment>> {
+    if !line.contains('-') || indent_of(line) >= 4 {
+        return None;
+    }
+    let trimmed = line.trim();
+    if !trimmed.contains('|') && !trimmed.starts_with(':') {
+        // A bare `---` is a setext underline, not a one-column table
+        return None;
+    }
+    split_table_row(line)
+        .iter()
+        .map(|cell| {
+            let dashes = cell.trim_start_matches(':').trim_end_matches(':');
+            if dashes.is_empty() || !dashes.chars().all(|c| c == '-') {
+                return None;
+            }
+            Some(match (cell.starts_with(':'), cell.ends_with(':')) {
+                (true, true) => Alignment::Center,
+                (true, false) => Alignment::Left,
+                (false, true) => Alignment::Right,
+                (false, false) => Alignment::None,
+            })
+        })
+        .collect()
+}
+
+/// Whether `lines[i]` and the line after it form a table header.
+fn is_table_start(lines: &[String], i: usize) -> bool {
+    let Some(delimiter) = lines.get(i + 1) else {
+        return false;
+    };
+    if !lines[i].contains('|') || indent_of(&lines[i]) >= 4 {
+        return false;
+    }
+    table_alignments(delimiter).is_some_and(|a| a.len() == split_table_row(&lines[i]).len())
+}
+
+fn parse_table(lines: &[String], start: usize, blocks: &mut Vec<RawBlock>) -> Option<usize> {
+    if !is_table_start(lines, start) {
+        return None;
+    }
+    let alignments = table_alignments(&lines[start + 1])?;
+    let header = split_table_row(&lines[start]);
+    let mut rows = vec![];
+    let mut i = start + 2;
+    while i < lines.len() && !is_blank(&lines[i]) && !interrupts_paragraph(&lines[i]) {
+        let mut row = split_table_row(&lines[i]);
+        row.resize(alignments.len(), String::new());
+        rows.push(row);
+        i += 1;
+    }
+    blocks.push(RawBlock::Table { alignments, header, rows });
+    Some(i)
+}
+
+/// `[label]: destination "optional title"` on a single line.
+fn link_reference_definition(rest: &str) -> Option<(String, String, String)> {
+    let after = rest.strip_prefix('[')?;
+    if after.starts_with('^') {
+        return None;
+    }
+    let close = after.find("]:")?;
+    let label = &after[..close];
+    if label.trim().is_empty() || label.contains('[') || label.contains(']') {
+        return None;
+    }
+    let tail = after[close + 2..].trim();
+    let (url, tail) = if let Some(angled) = tail.strip_prefix('<') {
+        let end = angled.find('>')?;
+        (&angled[..end], angled[end + 1..].trim())
+    } else {
+        let end = tail.find(char::is_whitespace).unwrap_or(tail.len());
+        (&tail[..end], tail[end..].trim())
+    };
+    if url.is_empty() && !tail.is_empty() {
+        return None;
+    }
+    let title = if tail.is_empty() {
+        ""
+    } else {
+        let quote = tail.chars().next()?;
+        let closing = match quote {
+            '"' => '"',
+            '\'' => '\'',
+            '(' => ')',
+            _ => return None,
+        };
+        tail.strip_prefix(quote)?.strip_suffix(closing)?
+    };
+    Some((label.to_string(), url.to_string(), title.to_string()))
+}
+
+fn setext_level(line: &str) -> Option<u8> {
+    if indent_of(line) >= 4 {
+        return None;
+    }
+    let trimmed = line.trim();
+    if !trimmed.is_empty() && trimmed.chars().all(|c| c == '=') {
+        Some(1)
+    } else if !trimmed.is_empty() && trimmed.chars().all(|c| c == '-') {
+        Some(2)
+    } else {
+        None
+    }
+}
+
+fn parse_paragraph(lines: &[String], start: usize, blocks: &mut Vec<RawBlock>) -> usize {
+    let mut text_lines = vec![lines[start].trim_start()];
+    let mut i = start + 1;
+    while i < lines.len() {
+        let line = lines[i].as_str();
+        if let Some(level) = setext_level(line) {
+            let text = text_lines.join("\n").trim_end().to_string();
+            blocks.push(RawBlock::Heading(level, text));
+            return i + 1;
+        }
+        if is_blank(line) || interrupts_paragraph(line) || is_table_start(lines, i) {
+            break;
+        }
+        text_lines.push(line.trim_start());
+        i += 1;
+    }
+    blocks.push(RawBlock::Paragraph(text_lines.join("\n").trim_end().to_string()));
+    i
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::markdown::{parse, Alignment, Block, Document, Footnote, Inline, List, ListItem, Table};
+
+    fn text(text: &str) -> Inline {
+        Inline::Text(text.to_string())
+    }
+
+    fn item(text: &str) -> ListItem {
+        ListItem { task: None, blocks: vec![Block::Paragraph(vec![self::text(text)])] }
+    }
+
+    fn empty_list(start: Option<u64>) -> Block {
+        Block::List(List { start, tight: true, items: vec![ListItem { task: None, blocks: vec![] }] })
+    }
+
+    #[test]
+    fn empty_list_items_end() {
+        for source in ["-", "- ", "*", "+"] {
+            assert_eq!(parse(source).blocks, vec![empty_list(None)], "{:?}", source);
+        }
+        assert_eq!(parse("1.").blocks, vec![empty_list(Some(1))]);
+    }
+
+    #[test]
+    fn empty_item_after_another() {
+        let Block::List(list) = &parse("- a\n-").blocks[0] else {
+            panic!("expected a list");
+        };
+        assert_eq!(list.items.len(), 2);
+        assert!(list.items[1].blocks.is_empty());
+    }
+
+    #[test]
+    fn empty_item_in_quote() {
+        assert_eq!(parse("> -").blocks, vec![Block::BlockQuote(vec![empty_list(None)])]);
+    }
+
+    #[test]
+    fn empty_footnote_definitions_end() {
+        for source in ["[^1]:", "[^1]: "] {
+            let document = parse(source);
+            assert!(document.blocks.is_empty(), "{:?}", source);
+            assert_eq!(document.footnotes, vec![Footnote { label: "1".to_string(), blocks: vec![] }]);
+        }
+        let document = parse("text[^1]\n\n[^1]:\n");
+        assert_eq!(
+            document.blocks,
+            vec![Block::Paragraph(vec![Inline::Text("text".to_string()), Inline::FootnoteReference("1".to_string())])]
+        );
+        assert_eq!(document.footnotes.len(), 1);
+    }
+
+    #[test]
+    fn empty_item_after_blank_line() {
+        let Document { blocks, .. } = parse("x\n\n-");
+        assert_eq!(blocks.len(), 2);
+        assert_eq!(blocks[1], empty_list(None));
+    }
+
+    #[test]
+    fn tight_and_loose_lists() {
+        let tight = List { start: None, tight: true, items: vec![item("a"), item("b")] };
+        assert_eq!(parse("- a\n- b\n").blocks, vec![Block::List(tight.clone())]);
+        let loose = List { tight: false, ..tight };
+        assert_eq!(parse("- a\n\n- b\n").blocks, vec![Block::List(loose)]);
+    }
+
+    #[test]
+    fn ordered_lists_keep_their_start() {
+        let Block::List(list) = &parse("1. one\n2. two\n").blocks[0] else {
+            panic!("expected a list");
+        };
+        assert_eq!(list.start, Some(1));
+        assert_eq!(list.items, vec![item("one"), item("two")]);
+        let Block::List(list) = &parse("3) x\n").blocks[0] else {
+            panic!("expected a list");
+        };
+        assert_eq!(list.start, Some(3));
+    }
+
+    #[test]
+    fn nested_lists() {
+        let list = |item: ListItem| Block::List(List { start: None, tight: true, items: vec![item] });
+        let mut b = item("b");
+        b.blocks.push(list(item("c")));
+        let mut a = item("a");
+        a.blocks.push(list(b));
+        assert_eq!(parse("- a\n  - b\n    - c\n").blocks, vec![list(a)]);
+    }
+
+    #[test]
+    fn task_list_items() {
+        let Block::List(list) = &parse("- [ ] todo\n- [x] done\n").blocks[0] else {
+            panic!("expected a list");
+        };
+        let tasks: Vec<_> = list.items.iter().map(|item| item.task).collect();
+        assert_eq!(tasks, vec![Some(false), Some(true)]);
+        assert_eq!(list.items[0].blocks, vec![Block::Paragraph(vec![text("todo")])]);
+    }
+
+    #[test]
+    fn fenced_code_blocks() {
+        let code = |info: &str, code: &str| Block::CodeBlock { info: info.to_string(), code: code.to_string() };
+        assert_eq!(parse("```rust\nfn x() {}\n```\n").blocks, vec![code("rust", "fn x() {}\n")]);
+        assert_eq!(parse("~~~\na\n~~~\n").blocks, vec![code("", "a\n")]);
+        // An unclosed fence runs to the end of the document
+        assert_eq!(parse("```\nunclosed\n").blocks, vec![code("", "unclosed\n")]);
+        assert_eq!(parse("    code\n").blocks, vec![code("", "code\n")]);
+        // Markdown inside a fence is left as it is
+        assert_eq!(parse("```\n# not a heading\n```").blocks, vec![code("", "# not a heading\n")]);
+    }
+
+    #[test]
+    fn tables() {
+        let table = Table {
+            alignments: vec![Alignment::Left, Alignment::Right],
+            header: vec![vec![text("a")], vec![text("b")]],
+            rows: vec![vec![vec![text("1")], vec![text("2")]]],
+        };
+        assert_eq!(parse("| a | b |\n|:--|--:|\n| 1 | 2 |\n").blocks, vec![Block::Table(table)]);
+    }
+
+    #[test]
+    fn table_rows_are_cut_to_the_header() {
+        let Block::Table(table) = &parse("| a |\n|---|\n| x | extra |\n").blocks[0] else {
+            panic!("expected a table");
+        };
+        assert_eq!(table.alignments, vec![Alignment::None]);
+        assert_eq!(table.rows, vec![vec![vec![text("x")]]]);
+    }
+
+    #[test]
+    fn footnotes() {
+        let document = parse("Text[^n].\n\n[^n]: Note *here*.\n");
+        assert_eq!(
+            document.blocks,
+            vec![Block::Paragraph(vec![text("Text"), Inline::FootnoteReference("n".to_string()), text(".")])]
+        );
+        let note = vec![Block::Paragraph(vec![text("Note "), Inline::Emphasis(vec![text("here")]), text(".")])];
+        assert_eq!(document.footnotes, vec![Footnote { label: "n".to_string(), blocks: note }]);
+    }
+
+    #[test]
+    fn headings_quotes_and_breaks() {
+        let heading = |level, content: &str| Block::Heading { level, content: vec![text(content)] };
+        assert_eq!(parse("# H1\n## H2 ##\n").blocks, vec![heading(1, "H1"), heading(2, "H2")]);
+        let nested = Block::BlockQuote(vec![Block::Paragraph(vec![text("n")])]);
+        assert_eq!(
+            parse("> q\n> > n\n").blocks,
+            vec![Block::BlockQuote(vec![Block::Paragraph(vec![text("q")]), nested])]
+        );
+        assert_eq!(parse("---\n").blocks, vec![Block::ThematicBreak]);
+    }
+}
@@ -0,0 +1,278 @@
+use super::{Alignment, Block, Document, Inline, List, Table};
+
+/// Renders a document as an HTML fragment.
+pub fn to_html(doc: &Document) -> String {
+    let mut out = String::new();
+    let footnote_number = |label: &str| {
+        doc.footnotes
+            .iter()
+            .position(|f| f.label.eq_ignore_ascii_case(label))
+            .map_or(0, |i| i + 1)
+    };
+    let renderer = Renderer { footnote_number: &footnote_number };
+    renderer.blocks(&doc.blocks, false, &mut out);
+
+    if !doc.footnotes.is_empty() {
+        out.push_str("<section class=\"footnotes\">\n<ol>\n");
+        for footnote in &doc.footnotes {
+            let id = escape(&footnote.label);
+            out.push_str(&format!("<li id=\"fn-{}\">\n", id));
+            renderer.blocks(&footnote.blocks, false, &mut out);
+            out.push_str(&format!("<a href=\"#fnref-{}\" class=\"footnote-backref\">↩</a>\n</li>\n", id));
+        }
+        out.push_str("</ol>\n</section>\n");
+    }
+    out
+}
+
+/// Renders a document as a standalone HTML page.
+pub fn to_html_page(title: &str, doc: &Document) -> String {
+    format!(
+        "<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n<title>{}</title>\n</head>\n<body>\n{}</body>\n</html>\n",
+        escape(title),
+        to_html(doc)
+    )
+}
+
+pub(super) fn escape(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => out.push_str("&amp;"),
+            '<' => out.push_str("&lt;"),
+            '>' => out.push_str("&gt;"),
+            '"' => out.push_str("&quot;"),
+            _ => out.push(c),
+        }
+    }
+    out
+}
+
+struct Renderer<'a> {
+    footnote_number: &'a dyn Fn(&str) -> usize,
+}
+
+impl Renderer<'_> {
+    fn blocks(&self, blocks: &[Block], tight: bool, out: &mut String) {
+        for block in blocks {
+            self.block(block, tight, out);
+        }
+    }
+
+    fn block(&self, block: &Block, tight: bool, out: &mut String) {
+        match block {
+            Block::Heading { level, content } => {
+                out.push_str(&format!("<h{}>", level));
+                self.inlines(content, out);
+                out.push_str(&format!("</h{}>\n", level));
+            }
+            Block::Paragraph(content) if tight => {
+                self.inlines(content, out);
+                out.push('\n');
+            }
+            Block::Paragraph(content) => {
+                out.push_str("<p>");
+                self.inlines(content, out);
+                out.push_str("</p>\n");
+            }
+            Block::CodeBlock { info, code } => {
+                if info.is_empty() {
+                    out.push_str("<pre><code>");
+                } else {
+                    out.push_str(&format!("<pre><code class=\"language-{}\">", escape(info)));
+                }
+                out.push_str(&escape(code));
+                out.push_str("</code></pre>\n");
+            }
+            Block::BlockQuote(children) => {
+                out.push_str("<blockquote>\n");
+                self.blocks(children, false, out);
+                out.push_str("</blockquote>\n");
+            }
+            Block::List(list) => self.list(list, out),
+            Block::Table(table) => self.table(table, out),
+            Block::ThematicBreak => out.push_str("<hr />\n"),
+        }
+    }
+
+    fn list(&self, list: &List, out: &mut String) {
+        let tag = match list.start {
+            Some(1) => {
+                out.push_str("<ol>\n");
+                "ol"
+            }
+            Some(start) => {
+                out.push_str(&format!("<ol start=\"{}\">\n", start));
+                "ol"
+            }
+            None => {
+                out.push_str("<ul>\n");
+                "ul"
+            }
+        };
+        for item in &list.items {
+            out.push_str("<li>");
+            if let Some(checked) = item.task {
+                out.push_str(if checked {
+                    "<input type=\"checkbox\" disabled=\"\" checked=\"\" /> "
+                } else {
+                    "<input type=\"checkbox\" disabled=\"\" /> "
+                });
+            }
+            if !list.tight || !matches!(item.blocks.first(), Some(Block::Paragraph(_)) | None) {
+                out.push('\n');
+            }
+            self.blocks(&item.blocks, list.tight, out);
+            if out.ends_with('\n') && list.tight && matches!(item.blocks.last(), Some(Block::Paragraph(_))) {
+                out.pop();
+            }
+            out.push_str("</li>\n");
+        }
+        out.push_str(&format!("</{}>\n", tag));
+    }
+
+    fn table(&self, table: &Table, out: &mut String) {
+        let align_attr = |i: usize| match table.alignments.get(i) {
+            Some(Alignment::Left) => " align=\"left\"",
+            Some(Alignment::Center) => " align=\"center\"",
+            Some(Alignment::Right) => " align=\"right\"",
+            _ => "",
+        };
+        out.push_str("<table>\n<thead>\n<tr>\n");
+        for (i, cell) in table.header.iter().enumerate() {
+            out.push_str(&format!("<th{}>", align_attr(i)));
+            self.inlines(cell, out);
+            out.push_str("</th>\n");
+        }
+        out.push_str("</tr>\n</thead>\n");
+        if !table.rows.is_empty() {
+            out.push_str("<tbody>\n");
+            for row in &table.rows {
+                out.push_str("<tr>\n");
+                for (i, cell) in row.iter().enumerate() {
+                    out.push_str(&format!("<td{}>", align_attr(i)));
+                    self.inlines(cell, out);
+                    out.push_str("</td>\n");
+                }
+                out.push_str("</tr>\n");
+            }
+            out.push_str("</tbody>\n");
+        }
+        out.push_str("</table>\n");
+    }
+
+    fn inlines(&self, inlines: &[Inline], out: &mut String) {
+        for inline in inlines {
+            match inline {
+                Inline::Text(text) => out.push_str(&escape(text)),
+                Inline::Code(code) => {
+                    out.push_str("<code>");
+                    out.push_str(&escape(code));
+                    out.push_str("</code>");
+                }
+                Inline::Emphasis(children) => self.wrap("em", children, out),
+                Inline::Strong(children) => self.wrap("strong", children, out),
+                Inline::Strikethrough(children) => self.wrap("del", children, out),
+                Inline::Link { url, title, content } => {
+                    out.push_str(&format!("<a href=\"{}\"", escape(url)));
+                    if !title.is_empty() {
+                        out.push_str(&format!(" title=\"{}\"", escape(title)));
+                    }
+                    out.push('>');
+                    self.inlines(content, out);
+                    out.push_str("</a>");
+                }
+                Inline::Image { url, title, alt } => {
+                    out.push_str(&format!("<img src=\"{}\" alt=\"{}\"", escape(url), escape(alt)));
+                    if !title.is_empty() {
+                        out.push_str(&format!(" title=\"{}\"", escape(title)));
+                    }
+                    out.push_str(" />");
+                }
+                Inline::WikiLink { target, alias } => {
+                    // Exported notes sit next to each other, named after their titles
+                    let href = format!("{}.html", crate::title::slugify(target));
+                    out.push_str(&format!("<a class=\"wikilink\" href=\"{}\">", escape(&href)));
+                    out.push_str(&escape(alias.as_ref().unwrap_or(target)));
+                    out.push_str("</a>");
+                }
+                Inline::FootnoteReference(label) => {
+                    let id = escape(label);
+                    out.push_str(&format!(
+                        "<sup class=\"footnote-ref\"><a href=\"#fn-{}\" id=\"fnref-{}\">{}</a></sup>",
+                        id,
+                        id,
+                        (self.footnote_number)(label)
+                    ));
+                }
+                Inline::SoftBreak => out.push('\n'),
+                Inline::HardBreak => out.push_str("<br />\n"),
+            }
+        }
+    }
+
+    fn wrap(&self, tag: &str, children: &[Inline], out: &mut String) {
+        out.push_str(&format!("<{}>", tag));
+        self.inlines(children, out);
+        out.push_str(&format!("</{}>", tag));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{escape, to_html};
+    use crate::markdown::parse;
+
+    fn html(source: &str) -> String {
+        to_html(&parse(source))
+    }
+
+    #[test]
+    fn escapes_special_characters() {
+        assert_eq!(escape("<b> & \"q\""), "&lt;b&gt; &amp; &quot;q&quot;");
+        assert_eq!(html("<b> & \"q\""), "<p>&lt;b&gt; &amp; &quot;q&quot;</p>\n");
+        assert_eq!(html("`<tag>`"), "<p><code>&lt;tag&gt;</code></p>\n");
+        assert_eq!(html("```\n<script>\n```"), "<pre><code>&lt;script&gt;\n</code></pre>\n");
+        assert_eq!(html("[a](/x?a=1&b=\"2\")"), "<p><a href=\"/x?a=1&amp;b=&quot;2&quot;\">a</a></p>\n");
+    }
+
+    #[test]
+    fn lists() {
+        assert_eq!(html("- a\n- b\n"), "<ul>\n<li>a</li>\n<li>b</li>\n</ul>\n");
+        assert_eq!(html("- a\n\n- b\n"), "<ul>\n<li>\n<p>a</p>\n</li>\n<li>\n<p>b</p>\n</li>\n</ul>\n");
+        assert_eq!(html("3) x\n"), "<ol start=\"3\">\n<li>x</li>\n</ol>\n");
+        assert_eq!(
+            html("- [x] done\n"),
+            "<ul>\n<li><input type=\"checkbox\" disabled=\"\" checked=\"\" /> done</li>\n</ul>\n"
+        );
+    }
+
+    #[test]
+    fn code_blocks_name_their_language() {
+        assert_eq!(html("```rust\nfn x() {}\n```\n"), "<pre><code class=\"language-rust\">fn x() {}\n</code></pre>\n");
+    }
+
+    #[test]
+    fn tables_align_their_cells() {
+        assert_eq!(
+            html("| a | b |\n|:--|--:|\n| 1 | 2 |\n"),
+            "<table>\n<thead>\n<tr>\n<th align=\"left\">a</th>\n<th align=\"right\">b</th>\n</tr>\n</thead>\n\
+             <tbody>\n<tr>\n<td align=\"left\">1</td>\n<td align=\"right\">2</td>\n</tr>\n</tbody>\n</table>\n"
+        );
+    }
+
+    #[test]
+    fn footnotes_link_both_ways() {
+        assert_eq!(
+            html("Text[^n].\n\n[^n]: Note.\n"),
+            "<p>Text<sup class=\"footnote-ref\"><a href=\"#fn-n\" id=\"fnref-n\">1</a></sup>.</p>\n\
+             <section class=\"footnotes\">\n<ol>\n<li id=\"fn-n\">\n<p>Note.</p>\n\
+             <a href=\"#fnref-n\" class=\"footnote-backref\">↩</a>\n</li>\n</ol>\n</section>\n"
+        );
+    }
+
+    #[test]
+    fn wiki_links_point_at_the_exported_note() {
+        assert_eq!(html("[[Note|alias]]"), "<p><a class=\"wikilink\" href=\"Note.html\">alias</a></p>\n");
+    }
+}
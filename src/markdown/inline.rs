@@ -0,0 +1,619 @@
+use super::block::normalize_label;
+use super::Inline;
+use std::collections::{HashMap, HashSet};
+
+/// Document-wide definitions that inline parsing resolves against.
+pub(super) struct Context {
+    /// Normalized label to `(url, title)`.
+    pub links: HashMap<String, (String, String)>,
+    /// Normalized labels of the defined footnotes.
+    pub footnotes: HashSet<String>,
+}
+
+/// Either finished inline content or a run of `*`, `_` or `~` that may still
+/// turn into emphasis once its partner is found.
+enum Piece {
+    Node(Inline),
+    Delimiter {
+        ch: char,
+        count: usize,
+        original: usize,
+        can_open: bool,
+        can_close: bool,
+    },
+}
+
+pub(super) fn parse(text: &str, ctx: &Context) -> Vec<Inline> {
+    Parser { ctx, in_link: false }.parse(text)
+}
+
+struct Parser<'a> {
+    ctx: &'a Context,
+    /// Links cannot nest, so link text is parsed without link syntax.
+    in_link: bool,
+}
+
+fn is_punctuation(c: char) -> bool {
+    c.is_ascii_punctuation() || (!c.is_alphanumeric() && !c.is_whitespace() && !c.is_control())
+}
+
+impl Parser<'_> {
+    fn parse(&self, text: &str) -> Vec<Inline> {
+        let mut pieces = vec![];
+        let mut buffer = String::new();
+        let mut i = 0;
+
+        while i < text.len() {
+            let rest = &text[i..];
+            let c = rest.chars().next().expect("index is on a char boundary");
+
+            match c {
+                '\\' => {
+                    let next = rest[1..].chars().next();
+                    match next {
+                        Some('\n') => {
+                            flush_text(&mut buffer, &mut pieces);
+                            pieces.push(Piece::Node(Inline::HardBreak));
+                            i += 2;
+                        }
+                        Some(p) if p.is_ascii_punctuation() => {
+                            buffer.push(p);
+                            i += 2;
+                        }
+                        _ => {
+                            buffer.push('\\');
+                            i += 1;
+                        }
+                    }
+                }
+                '`' => {
+                    let run = rest.chars().take_while(|&c| c == '`').count();
+                    match code_span(&rest[run..], run) {
+                        Some((code, consumed)) => {
+                            flush_text(&mut buffer, &mut pieces);
+                            pieces.push(Piece::Node(Inline::Code(code)));
+                            i += run + consumed;
+                        }
+                        None => {
+                            buffer.push_str(&rest[..run]);
+                            i += run;
+                        }
+                    }
+                }
+                '\n' => {
+                    let hard = buffer.ends_with("  ");
+                    let trimmed = buffer.trim_end_matches(' ').len();
+                    buffer.truncate(trimmed);
+                    flush_text(&mut buffer, &mut pieces);
+                    pieces.push(Piece::Node(if hard { Inline::HardBreak } else { Inline::SoftBreak }));
+                    // Leading spaces of the next line are not content
+                    i += 1 + text[i + 1..].len() - text[i + 1..].trim_start_matches(' ').len();
+                }
+                '<' if !self.in_link => match angle_autolink(rest) {
+                    Some((url, label, consumed)) => {
+                        flush_text(&mut buffer, &mut pieces);
+                        pieces.push(Piece::Node(Inline::Link {
+                            url,
+                            title: String::new(),
+                            content: vec![Inline::Text(label)],
+                        }));
+                        i += consumed;
+                    }
+                    None => {
+                        buffer.push('<');
+                        i += 1;
+                    }
+                },
+                '!' if rest[1..].starts_with('[') => match self.link(&text[i + 1..]) {
+                    Some((url, title, label, consumed)) => {
+                        flush_text(&mut buffer, &mut pieces);
+                        let alt = super::plain_text(&self.inner_parser().parse(label));
+                        pieces.push(Piece::Node(Inline::Image { url, title, alt }));
+                        i += 1 + consumed;
+                    }
+                    None => {
+                        buffer.push('!');
+                        i += 1;
+                    }
+                },
+                '[' => {
+                    if let Some((target, alias, consumed)) = crate::links::parse_at(rest).filter(|_| !self.in_link) {
+                        flush_text(&mut buffer, &mut pieces);
+                        pieces.push(Piece::Node(Inline::WikiLink { target, alias }));
+                        i += consumed;
+                    } else if let Some((label, consumed)) = self.footnote_reference(rest) {
+                        flush_text(&mut buffer, &mut pieces);
+                        pieces.push(Piece::Node(Inline::FootnoteReference(label)));
+                        i += consumed;
+                    } else if let Some((url, title, label, consumed)) =
+                        self.link(rest).filter(|_| !self.in_link)
+                    {
+                        flush_text(&mut buffer, &mut pieces);
+                        let content = self.inner_parser().parse(label);
+                        pieces.push(Piece::Node(Inline::Link { url, title, content }));
+                        i += consumed;
+                    } else {
+                        buffer.push('[');
+                        i += 1;
+                    }
+                }
+                '*' | '_' | '~' => {
+                    let count = rest.chars().take_while(|&d| d == c).count();
+                    let before = text[..i].chars().next_back().unwrap_or(' ');
+                    let after = rest[count..].chars().next().unwrap_or(' ');
+                    let (can_open, can_close) = flanking(c, before, after);
+                    flush_text(&mut buffer, &mut pieces);
+                    if c == '~' && count > 2 {
+                        buffer.push_str(&rest[..count]);
+                    } else {
+                        pieces.push(Piece::Delimiter { ch: c, count, original: count, can_open, can_close });
+                    }
+                    i += count;
+                }
+                '&' => match entity(rest) {
+                    Some((decoded, consumed)) => {
+                        buffer.push_str(&decoded);
+                        i += consumed;
+                    }
+                    None => {
+                        buffer.push('&');
+                        i += 1;
+                    }
+                },
+                'h' | 'w' if !self.in_link && at_word_start(&text[..i]) => match bare_autolink(rest) {
+                    Some((url, consumed)) => {
+                        flush_text(&mut buffer, &mut pieces);
+                        let label = rest[..consumed].to_string();
+                        pieces.push(Piece::Node(Inline::Link {
+                            url,
+                            title: String::new(),
+                            content: vec![Inline::Text(label)],
+                        }));
+                        i += consumed;
+                    }
+                    None => {
+                        buffer.push(c);
+                        i += 1;
+                    }
+                },
+                _ => {
+                    buffer.push(c);
+                    i += c.len_utf8();
+                }
+            }
+        }
+
+        flush_text(&mut buffer, &mut pieces);
+        process_emphasis(&mut pieces);
+        into_inlines(pieces)
+    }
+
+    fn inner_parser(&self) -> Parser<'_> {
+        Parser { ctx: self.ctx, in_link: true }
+    }
+
+    /// `[^label]` referring to a defined footnote.
+    fn footnote_reference(&self, rest: &str) -> Option<(String, usize)> {
+        let after = rest.strip_prefix("[^")?;
+        let close = after.find(']')?;
+        let label = &after[..close];
+        if label.is_empty() || label.contains(char::is_whitespace) {
+            return None;
+        }
+        if !self.ctx.footnotes.contains(&normalize_label(label)) {
+            return None;
+        }
+        Some((label.to_string(), close + 3))
+    }
+
+    /// Parses `[text](url "title")`, `[text][ref]`, `[text][]` or `[text]`
+    /// at the start of `rest`, returning url, title, raw text and length.
+    fn link<'t>(&self, rest: &'t str) -> Option<(String, String, &'t str, usize)> {
+        let close = matching_bracket(rest)?;
+        let label = &rest[1..close];
+        let after = &rest[close + 1..];
+
+        if let Some(inline) = after.strip_prefix('(') {
+            if let Some((url, title, consumed)) = inline_destination(inline) {
+                return Some((url, title, label, close + 2 + consumed));
+            }
+        }
+
+        let (reference, consumed) = match after.strip_prefix('[') {
+            Some(tail) => {
+                let end = tail.find(']')?;
+                let reference = &tail[..end];
+                let reference = if reference.trim().is_empty() { label } else { reference };
+                (reference, close + 1 + end + 2)
+            }
+            None => (label, close + 1),
+        };
+        let (url, title) = self.ctx.links.get(&normalize_label(reference))?;
+        Some((url.clone(), title.clone(), label, consumed))
+    }
+}
+
+fn flush_text(buffer: &mut String, pieces: &mut Vec<Piece>) {
+    if !buffer.is_empty() {
+        pieces.push(Piece::Node(Inline::Text(std::mem::take(buffer))));
+    }
+}
+
+fn flanking(ch: char, before: char, after: char) -> (bool, bool) {
+    let left = !after.is_whitespace()
+        && (!is_punctuation(after) || before.is_whitespace() || is_punctuation(before));
+    let right = !before.is_whitespace()
+        && (!is_punctuation(before) || after.is_whitespace() || is_punctuation(after));
+    if ch == '_' {
+        (left && (!right || is_punctuation(before)), right && (!left || is_punctuation(after)))
+    } else {
+        (left, right)
+    }
+}
+
+/// Finds the closing backtick run of exactly `run` backticks.
+fn code_span(rest: &str, run: usize) -> Option<(String, usize)> {
+    let mut i = 0;
+    while i < rest.len() {
+        let tail = &rest[i..];
+        let start = i + tail.find('`')?;
+        let closing = rest[start..].chars().take_while(|&c| c == '`').count();
+        if closing == run {
+            let code = rest[..start].replace('\n', " ");
+            let stripped = if code.len() >= 2 && code.starts_with(' ') && code.ends_with(' ') && !code.trim().is_empty() {
+                code[1..code.len() - 1].to_string()
+            } else {
+                code
+            };
+            return Some((stripped, start + closing));
+        }
+        i = start + closing;
+    }
+    None
+}
+
+/// Byte index of the `]` matching the `[` at the start of `rest`.
+fn matching_bracket(rest: &str) -> Option<usize> {
+    let mut depth = 0;
+    let mut chars = rest.char_indices();
+    while let Some((i, c)) = chars.next() {
+        match c {
+            '\\' => {
+                chars.next();
+            }
+            '`' => {
+                // Brackets inside code spans do not count
+                let run = rest[i..].chars().take_while(|&c| c == '`').count();
+                if let Some((_, consumed)) = code_span(&rest[i + run..], run) {
+                    let end = i + run + consumed;
+                    while chars.clone().next().is_some_and(|(j, _)| j < end) {
+                        chars.next();
+                    }
+                }
+            }
+            '[' => depth += 1,
+            ']' => {
+                depth -= 1;
+                if depth == 0 {
+                    return Some(i);
+                }
+            }
+            _ => {}
+        }
+    }
+    None
+}
+
+/// Parses `url "title")` after the opening parenthesis of an inline link.
+fn inline_destination(inline: &str) -> Option<(String, String, usize)> {
+    let trimmed = inline.trim_start();
+    let mut consumed = inline.len() - trimmed.len();
+
+    let url = if let Some(angled) = trimmed.strip_prefix('<') {
+        let end = angled.find(['>', '\n'])?;
+        if !angled[end..].starts_with('>') {
+            return None;
+        }
+        consumed += end + 2;
+        angled[..end].to_string()
+    } else {
+        let mut depth = 0;
+        let mut end = trimmed.len();
+        let mut chars = trimmed.char_indices();
+        while let Some((i, c)) = chars.next() {
+            match c {
+                '\\' => {
+                    chars.next();
+                }
+                '(' => depth += 1,
+                ')' if depth == 0 => {
+                    end = i;
+                    break;
+                }
+                ')' => depth -= 1,
+                c if c.is_whitespace() => {
+                    end = i;
+                    break;
+                }
+                _ => {}
+            }
+        }
+        consumed += end;
+        unescape(&trimmed[..end])
+    };
+
+    let tail = &inline[consumed..];
+    let trimmed = tail.trim_start();
+    consumed += tail.len() - trimmed.len();
+
+    let mut title = String::new();
+    if let Some(quote) = trimmed.chars().next().filter(|c| matches!(c, '"' | '\'' | '(')) {
+        let closing = if quote == '(' { ')' } else { quote };
+        let end = trimmed[1..].find(closing)?;
+        title = unescape(&trimmed[1..1 + end]);
+        let after = &trimmed[end + 2..];
+        let after_trimmed = after.trim_start();
+        consumed += end + 2 + (after.len() - after_trimmed.len());
+    }
+
+    inline[consumed..].starts_with(')').then_some((url, title, consumed + 1))
+}
+
+fn unescape(text: &str) -> String {
+    let mut out = String::new();
+    let mut chars = text.chars().peekable();
+    while let Some(c) = chars.next() {
+        match (c, chars.peek()) {
+            ('\\', Some(&next)) if next.is_ascii_punctuation() => {
+                out.push(next);
+                chars.next();
+            }
+            _ => out.push(c),
+        }
+    }
+    out
+}
+
+/// `<scheme:...>` and `<user@host>` autolinks.
+fn angle_autolink(rest: &str) -> Option<(String, String, usize)> {
+    let end = rest.find('>')?;
+    let inner = &rest[1..end];
+    if inner.is_empty() || inner.contains(|c: char| c.is_whitespace() || c == '<') {
+        return None;
+    }
+    if let Some((scheme, _)) = inner.split_once(':') {
+        let valid_scheme = (2..=32).contains(&scheme.len())
+            && scheme.starts_with(|c: char| c.is_ascii_alphabetic())
+            && scheme.chars().all(|c| c.is_ascii_alphanumeric() || matches!(c, '+' | '.' | '-'));
+        if valid_scheme {
+            return Some((inner.to_string(), inner.to_string(), end + 1));
+        }
+    }
+    let (user, host) = inner.split_once('@')?;
+    let valid_email = !user.is_empty()
+        && host.contains('.')
+        && host.chars().all(|c| c.is_ascii_alphanumeric() || matches!(c, '.' | '-'));
+    valid_email.then(|| (format!("mailto:{}", inner), inner.to_string(), end + 1))
+}
+
+fn at_word_start(before: &str) -> bool {
+    before
+        .chars()
+        .next_back()
+        .is_none_or(|c| c.is_whitespace() || matches!(c, '*' | '_' | '~' | '('))
+}
+
+/// GFM extended autolinks: `http://`, `https://` and `www.` URLs in text.
+fn bare_autolink(rest: &str) -> Option<(String, usize)> {
+    let prefix = ["https://", "http://", "www."].into_iter().find(|p| rest.starts_with(p))?;
+    let mut end = rest.find(|c: char| c.is_whitespace() || c == '<').unwrap_or(rest.len());
+
+    // Trailing punctuation and unbalanced closing parentheses belong to the sentence
+    loop {
+        let candidate = &rest[..end];
+        match candidate.chars().next_back() {
+            Some('?' | '!' | '.' | ',' | ':' | '*' | '_' | '~' | '\'' | '"') => end -= 1,
+            Some(')') if candidate.matches(')').count() > candidate.matches('(').count() => end -= 1,
+            _ => break,
+        }
+    }
+
+    let text = &rest[..end];
+    let domain = text[prefix.len()..].split(['/', '?', '#']).next().unwrap_or_default();
+    if domain.is_empty() || (prefix == "www." && !domain.contains('.')) {
+        return None;
+    }
+    let url = if prefix == "www." { format!("http://{}", text) } else { text.to_string() };
+    Some((url, end))
+}
+
+fn entity(rest: &str) -> Option<(String, usize)> {
+    let end = rest.find(';')?;
+    if end > 32 {
+        return None;
+    }
+    let name = &rest[1..end];
+    let decoded = if let Some(number) = name.strip_prefix('#') {
+        let code = match number.strip_prefix(['x', 'X']) {
+            Some(hex) => u32::from_str_radix(hex, 16).ok()?,
+            None => number.parse().ok()?,
+        };
+        char::from_u32(code).filter(|&c| c != '\0').unwrap_or('\u{FFFD}').to_string()
+    } else {
+        match name {
+            "amp" => "&",
+            "lt" => "<",
+            "gt" => ">",
+            "quot" => "\"",
+            "apos" => "'",
+            "nbsp" => "\u{a0}",
+            "copy" => "©",
+            "reg" => "®",
+            "trade" => "™",
+            "hellip" => "…",
+            "mdash" => "—",
+            "ndash" => "–",
+            _ => return None,
+        }
+        .to_string()
+    };
+    Some((decoded, end + 1))
+}
+
+/// Pairs up delimiter runs into emphasis, strong emphasis and
+/// strikethrough, following the CommonMark delimiter algorithm.
+fn process_emphasis(pieces: &mut Vec<Piece>) {
+    let mut closer = 0;
+    while closer < pieces.len() {
+        let Piece::Delimiter { ch, count: closer_count, original: closer_original, can_open: closer_opens, can_close: true } = pieces[closer] else {
+            closer += 1;
+            continue;
+        };
+
+        let opener = (0..closer).rev().find(|&j| match pieces[j] {
+            Piece::Delimiter { ch: c, count, original, can_open: true, can_close } if c == ch => {
+                if ch == '~' {
+                    return count == closer_count;
+                }
+                // The "rule of three" for runs that can both open and close
+                let both = can_close || closer_opens;
+                !(both && (original + closer_original) % 3 == 0 && !(original % 3 == 0 && closer_original % 3 == 0))
+            }
+            _ => false,
+        });
+
+        let Some(opener) = opener else {
+            closer += 1;
+            continue;
+        };
+
+        let Piece::Delimiter { count: opener_count, .. } = pieces[opener] else {
+            unreachable!("opener is a delimiter");
+        };
+        let used = if ch == '~' {
+            closer_count
+        } else if opener_count >= 2 && closer_count >= 2 {
+            2
+        } else {
+            1
+        };
+
+        let children = into_inlines(pieces.drain(opener + 1..closer).collect());
+        let node = match (ch, used) {
+            ('~', _) => Inline::Strikethrough(children),
+            (_, 2) => Inline::Strong(children),
+            _ => Inline::Emphasis(children),
+        };
+        pieces.insert(opener + 1, Piece::Node(node));
+
+        let mut closer_index = opener + 2;
+        if let Piece::Delimiter { count, .. } = &mut pieces[closer_index] {
+            *count -= used;
+        }
+        if let Piece::Delimiter { count, .. } = &mut pieces[opener] {
+            *count -= used;
+            if *count == 0 {
+                pieces.remove(opener);
+                closer_index -= 1;
+            }
+        }
+        if matches!(pieces[closer_index], Piece::Delimiter { count: 0, .. }) {
+            pieces.remove(closer_index);
+        }
+        closer = closer_index;
+    }
+}
+
+/// Turns leftover delimiters back into text and merges adjacent text.
+fn into_inlines(pieces: Vec<Piece>) -> Vec<Inline> {
+    let mut inlines: Vec<Inline> = vec![];
+    for piece in pieces {
+        let inline = match piece {
+            Piece::Node(inline) => inline,
+            Piece::Delimiter { ch, count, .. } => Inline::Text(ch.to_string().repeat(count)),
+        };
+        match (inlines.last_mut(), inline) {
+            (Some(Inline::Text(previous)), Inline::Text(text)) => previous.push_str(&text),
+            (_, inline) => inlines.push(inline),
+        }
+    }
+    inlines
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{parse, Context};
+    use crate::markdown::Inline;
+    use std::collections::{HashMap, HashSet};
+
+    fn inlines(text: &str) -> Vec<Inline> {
+        let links = HashMap::from([("ref".to_string(), ("/u".to_string(), String::new()))]);
+        parse(text, &Context { links, footnotes: HashSet::new() })
+    }
+
+    fn text(text: &str) -> Inline {
+        Inline::Text(text.to_string())
+    }
+
+    fn link(url: &str, title: &str, content: Vec<Inline>) -> Inline {
+        Inline::Link { url: url.to_string(), title: title.to_string(), content }
+    }
+
+    #[test]
+    fn emphasis_and_strong() {
+        assert_eq!(
+            inlines("*a* **b** ***c***"),
+            vec![
+                Inline::Emphasis(vec![text("a")]),
+                text(" "),
+                Inline::Strong(vec![text("b")]),
+                text(" "),
+                Inline::Emphasis(vec![Inline::Strong(vec![text("c")])]),
+            ]
+        );
+        assert_eq!(
+            inlines("*a **b** c*"),
+            vec![Inline::Emphasis(vec![text("a "), Inline::Strong(vec![text("b")]), text(" c")])]
+        );
+        assert_eq!(inlines("~~del~~"), vec![Inline::Strikethrough(vec![text("del")])]);
+    }
+
+    #[test]
+    fn emphasis_edge_cases() {
+        // `*` may open inside a word, `_` may not
+        assert_eq!(inlines("a*b*c"), vec![text("a"), Inline::Emphasis(vec![text("b")]), text("c")]);
+        assert_eq!(inlines("foo_bar_baz"), vec![text("foo_bar_baz")]);
+        assert_eq!(inlines("**unclosed"), vec![text("**unclosed")]);
+        assert_eq!(inlines("* not emphasis *"), vec![text("* not emphasis *")]);
+        assert_eq!(inlines("\\*lit\\*"), vec![text("*lit*")]);
+    }
+
+    #[test]
+    fn code_spans_are_literal() {
+        assert_eq!(inlines("`code *x*`"), vec![Inline::Code("code *x*".to_string())]);
+        assert_eq!(inlines("`` a ` b ``"), vec![Inline::Code("a ` b".to_string())]);
+    }
+
+    #[test]
+    fn links_and_images() {
+        assert_eq!(inlines("[t](http://x.com \"T\")"), vec![link("http://x.com", "T", vec![text("t")])]);
+        assert_eq!(inlines("[ref]"), vec![link("/u", "", vec![text("ref")])]);
+        assert_eq!(inlines("[missing]"), vec![text("[missing]")]);
+        assert_eq!(
+            inlines("![alt](i.png)"),
+            vec![Inline::Image { url: "i.png".to_string(), title: String::new(), alt: "alt".to_string() }]
+        );
+        assert_eq!(inlines("https://ex.com"), vec![link("https://ex.com", "", vec![text("https://ex.com")])]);
+        assert_eq!(
+            inlines("[[Note|alias]]"),
+            vec![Inline::WikiLink { target: "Note".to_string(), alias: Some("alias".to_string()) }]
+        );
+    }
+
+    #[test]
+    fn breaks_and_entities() {
+        assert_eq!(inlines("a  \nb"), vec![text("a"), Inline::HardBreak, text("b")]);
+        assert_eq!(inlines("a\nb"), vec![text("a"), Inline::SoftBreak, text("b")]);
+        assert_eq!(inlines("&amp; &copy;"), vec![text("& ©")]);
+    }
+}
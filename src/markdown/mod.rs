@@ -0,0 +1,113 @@
+//! Markdown parsing (CommonMark plus the GitHub extensions: tables, task
+//! lists, strikethrough, autolinks and footnotes, plus `[[wiki links]]`)
+//! into a small AST that the preview and the HTML exporter both walk.
+
+mod block;
+mod html;
+mod inline;
+
+pub use html::{to_html, to_html_page};
+
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Document {
+    pub blocks: Vec<Block>,
+    /// Footnote definitions, in the order they were defined.
+    pub footnotes: Vec<Footnote>,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Footnote {
+    pub label: String,
+    pub blocks: Vec<Block>,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum Block {
+    Heading { level: u8, content: Vec<Inline> },
+    Paragraph(Vec<Inline>),
+    CodeBlock { info: String, code: String },
+    BlockQuote(Vec<Block>),
+    List(List),
+    Table(Table),
+    ThematicBreak,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct List {
+    /// Number of the first item for ordered lists, `None` for bullet lists.
+    pub start: Option<u64>,
+    /// Tight lists render their paragraphs without spacing.
+    pub tight: bool,
+    pub items: Vec<ListItem>,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct ListItem {
+    /// `Some(checked)` for GFM task list items.
+    pub task: Option<bool>,
+    pub blocks: Vec<Block>,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Alignment {
+    None,
+    Left,
+    Center,
+    Right,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Table {
+    pub alignments: Vec<Alignment>,
+    pub header: Vec<Vec<Inline>>,
+    pub rows: Vec<Vec<Vec<Inline>>>,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum Inline {
+    Text(String),
+    Code(String),
+    Emphasis(Vec<Inline>),
+    Strong(Vec<Inline>),
+    Strikethrough(Vec<Inline>),
+    Link { url: String, title: String, content: Vec<Inline> },
+    Image { url: String, title: String, alt: String },
+    /// `[[target]]` or `[[target|alias]]`, a link to another note by title.
+    WikiLink { target: String, alias: Option<String> },
+    FootnoteReference(String),
+    SoftBreak,
+    HardBreak,
+}
+
+/// Parses `source` into a document.
+pub fn parse(source: &str) -> Document {
+    block::parse_document(source)
+}
+
+/// The text of `inlines` with all formatting dropped.
+pub fn plain_text(inlines: &[Inline]) -> String {
+    let mut text = String::new();
+    push_plain_text(inlines, &mut text);
+    text
+}
+
+fn push_plain_text(inlines: &[Inline], out: &mut String) {
+    for inline in inlines {
+        match inline {
+            Inline::Text(text) | Inline::Code(text) => out.push_str(text),
+            Inline::Emphasis(children)
+            | Inline::Strong(children)
+            | Inline::Strikethrough(children)
+            | Inline::Link { content: children, .. } => push_plain_text(children, out),
+            Inline::Image { alt, .. } => out.push_str(alt),
+            Inline::WikiLink { target, alias } => out.push_str(alias.as_ref().unwrap_or(target)),
+            Inline::FootnoteReference(label) => {
+                out.push_str("[^");
+                out.push_str(label);
+                out.push(']');
+            }
+            Inline::SoftBreak => out.push(' '),
+            Inline::HardBreak => out.push('\n'),
+        }
+    }
+}
@@ -0,0 +1,349 @@
+//! YAML front matter: the block between `---` lines at the very start of a
+//! note, read as typed properties. Only the flat YAML that notes use is
+//! understood, scalars and lists of scalars; anything else, comments
+//! included, is kept exactly as written.
+
+const FENCE: &str = "---";
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum Value {
+    Text(String),
+    Number(f64),
+    Bool(bool),
+    /// `YYYY-MM-DD`, possibly followed by a time.
+    Date(String),
+    List(Vec<String>),
+    /// YAML this module does not understand, such as a nested map: what
+    /// follows the key's colon, as written.
+    Raw(String),
+}
+
+impl Value {
+    /// Reads a value written on one line, such as `42`, `2024-03-09` or `[a, b]`.
+    pub fn parse(text: &str) -> Self {
+        parse_scalar_or_list(text.trim())
+    }
+
+    /// The value as items: a list's items, or a single text.
+    pub fn items(&self) -> Vec<String> {
+        match self {
+            Value::List(items) => items.clone(),
+            Value::Text(text) if !text.is_empty() => vec![text.clone()],
+            _ => vec![],
+        }
+    }
+}
+
+/// A note's properties, in the order they are written.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Metadata {
+    entries: Vec<Entry>,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+struct Entry {
+    /// Empty for comments and blank lines, which are kept in `source`.
+    key: String,
+    value: Value,
+    /// The lines the entry was read from, written back unless it changes.
+    source: Option<String>,
+}
+
+impl Metadata {
+    /// Reads the lines between the fences.
+    pub fn parse(yaml: &str) -> Self {
+        let mut entries: Vec<Entry> = vec![];
+        let mut lines = yaml.lines().peekable();
+        while let Some(line) = lines.next() {
+            let Some((key, rest)) = split_key(line) else {
+                entries.push(Entry { key: String::new(), value: Value::Raw(line.to_string()), source: Some(line.to_string()) });
+                continue;
+            };
+            // Indented lines, and list items, continue the key's value
+            let mut source = line.to_string();
+            let mut continuation = vec![];
+            while let Some(next) = lines.next_if(|next| next.starts_with([' ', '\t']) || next.starts_with("- ")) {
+                source.push('\n');
+                source.push_str(next);
+                continuation.push(next);
+            }
+            let value = if continuation.is_empty() {
+                parse_scalar_or_list(rest)
+            } else {
+                let items: Option<Vec<String>> = continuation
+                    .iter()
+                    .map(|line| line.trim_start().strip_prefix("- ").map(|item| unquote(item.trim())))
+                    .collect();
+                match items {
+                    Some(items) if rest.is_empty() => Value::List(items),
+                    _ => Value::Raw(source[line.find(':').unwrap_or(0) + 1..].to_string()),
+                }
+            };
+            entries.push(Entry { key: key.to_string(), value, source: Some(source) });
+        }
+        Self { entries }
+    }
+
+    pub fn to_yaml(&self) -> String {
+        let lines: Vec<String> = self
+            .entries
+            .iter()
+            .map(|entry| entry.source.clone().unwrap_or_else(|| format_entry(&entry.key, &entry.value)))
+            .collect();
+        lines.join("\n")
+    }
+
+    pub fn get(&self, key: &str) -> Option<&Value> {
+        self.entries.iter().find(|entry| entry.key == key && !key.is_empty()).map(|entry| &entry.value)
+    }
+
+    /// Replaces the property's value where it is written, or adds it at the end.
+    pub fn set(&mut self, key: &str, value: Value) {
+        match self.entries.iter_mut().find(|entry| entry.key == key) {
+            Some(entry) if entry.value == value => {}
+            Some(entry) => {
+                entry.value = value;
+                entry.source = None;
+            }
+            None => self.entries.push(Entry { key: key.to_string(), value, source: None }),
+        }
+    }
+
+    pub fn remove(&mut self, key: &str) -> Option<Value> {
+        let pos = self.entries.iter().position(|entry| entry.key == key && !key.is_empty())?;
+        Some(self.entries.remove(pos).value)
+    }
+
+    /// The properties, in order.
+    pub fn iter(&self) -> impl Iterator<Item = (&str, &Value)> {
+        self.entries.iter().filter(|entry| !entry.key.is_empty()).map(|entry| (entry.key.as_str(), &entry.value))
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.iter().next().is_none()
+    }
+}
+
+/// The front matter of a note, if it has any, and the rest of the note.
+pub fn split(content: &str) -> (Option<&str>, &str) {
+    let Some(rest) = content
+        .strip_prefix(FENCE)
+        .and_then(|rest| rest.strip_prefix("\r\n").or_else(|| rest.strip_prefix('\n')))
+    else {
+        return (None, content);
+    };
+    let mut offset = 0;
+    for line in rest.split_inclusive('\n') {
+        if line.trim_end() == FENCE {
+            let yaml = rest[..offset].trim_end_matches(['\r', '\n']);
+            return (Some(yaml), &rest[offset + line.len()..]);
+        }
+        offset += line.len();
+    }
+    (None, content)
+}
+
+/// The note's text after its front matter.
+pub fn body(content: &str) -> &str {
+    split(content).1
+}
+
+/// A note made of `metadata` as front matter followed by `body`. Without
+/// any properties or comments there is no front matter.
+pub fn join(metadata: &Metadata, body: &str) -> String {
+    if metadata.entries.is_empty() {
+        body.to_string()
+    } else {
+        format!("{fence}\n{}\n{fence}\n{}", metadata.to_yaml(), body, fence = FENCE)
+    }
+}
+
+// `key: rest` on an unindented line
+fn split_key(line: &str) -> Option<(&str, &str)> {
+    if line.starts_with([' ', '\t', '#', '-']) {
+        return None;
+    }
+    let (key, rest) = line.split_once(':')?;
+    if key.is_empty() || !(rest.is_empty() || rest.starts_with(' ')) {
+        return None;
+    }
+    Some((key.trim_end(), rest.trim()))
+}
+
+fn parse_scalar_or_list(text: &str) -> Value {
+    if let Some(inner) = text.strip_prefix('[').and_then(|t| t.strip_suffix(']')) {
+        if inner.contains(['[', ']', '{', '}']) {
+            return Value::Raw(format!(" {}", text));
+        }
+        let items = inner.split(',').map(|item| unquote(item.trim())).filter(|item| !item.is_empty());
+        return Value::List(items.collect());
+    }
+    if text.starts_with('{') || text.starts_with('|') || text.starts_with('>') || text.starts_with('&') {
+        return Value::Raw(format!(" {}", text));
+    }
+    if text.starts_with(['"', '\'']) {
+        // A comment may follow the closing quote
+        let closed = text.len() > 1 && text.ends_with(&text[..1]);
+        return Value::Text(unquote(if closed { text } else { strip_comment(text) }));
+    }
+    let text = strip_comment(text);
+    match text {
+        "true" => Value::Bool(true),
+        "false" => Value::Bool(false),
+        _ if is_date(text) => Value::Date(text.to_string()),
+        _ if is_number(text) => text.parse().map_or_else(|_| Value::Text(text.to_string()), Value::Number),
+        _ => Value::Text(text.to_string()),
+    }
+}
+
+fn format_entry(key: &str, value: &Value) -> String {
+    match value {
+        Value::Text(text) => format!("{}: {}", key, quote(text)),
+        Value::Number(n) => format!("{}: {}", key, n),
+        Value::Bool(b) => format!("{}: {}", key, b),
+        Value::Date(date) => format!("{}: {}", key, date),
+        Value::List(items) if items.is_empty() => format!("{}: []", key),
+        Value::List(items) => {
+            let items: Vec<String> = items.iter().map(|item| format!("  - {}", quote(item))).collect();
+            format!("{}:\n{}", key, items.join("\n"))
+        }
+        Value::Raw(raw) => format!("{}:{}", key, raw),
+    }
+}
+
+/// `text` as a YAML scalar, quoted whenever it would otherwise read as
+/// something else.
+fn quote(text: &str) -> String {
+    let plain = !text.is_empty()
+        && !text.contains(": ")
+        && !text.contains(" #")
+        && !text.ends_with(':')
+        && !text.starts_with(|c: char| "-?:,[]{}#&*!|>'\"%@`".contains(c))
+        && text.trim() == text
+        && !matches!(text, "true" | "false" | "null" | "~")
+        && !is_number(text)
+        && !is_date(text);
+    if plain {
+        text.to_string()
+    } else {
+        format!("\"{}\"", text.replace('\\', "\\\\").replace('"', "\\\""))
+    }
+}
+
+fn unquote(text: &str) -> String {
+    if let Some(inner) = text.strip_prefix('"').and_then(|t| t.strip_suffix('"')) {
+        inner.replace("\\\"", "\"").replace("\\n", "\n").replace("\\\\", "\\")
+    } else if let Some(inner) = text.strip_prefix('\'').and_then(|t| t.strip_suffix('\'')) {
+        inner.replace("''", "'")
+    } else {
+        strip_comment(text).to_string()
+    }
+}
+
+fn strip_comment(text: &str) -> &str {
+    text.split_once(" #").map_or(text, |(value, _)| value).trim_end()
+}
+
+fn is_number(text: &str) -> bool {
+    let digits = text.strip_prefix(['-', '+']).unwrap_or(text);
+    digits.starts_with(|c: char| c.is_ascii_digit())
+        && digits.chars().all(|c| c.is_ascii_digit() || c == '.')
+        && digits.matches('.').count() <= 1
+}
+
+fn is_date(text: &str) -> bool {
+    let bytes = text.as_bytes();
+    bytes.len() >= 10
+        && bytes[..10].iter().enumerate().all(|(i, b)| if i == 4 || i == 7 { *b == b'-' } else { b.is_ascii_digit() })
+        && (bytes.len() == 10 || matches!(bytes[10], b' ' | b'T'))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn text(text: &str) -> Value {
+        Value::Text(text.to_string())
+    }
+
+    #[test]
+    fn values_are_typed() {
+        assert_eq!(Value::parse("42"), Value::Number(42.0));
+        assert_eq!(Value::parse("-1.5"), Value::Number(-1.5));
+        assert_eq!(Value::parse("true"), Value::Bool(true));
+        assert_eq!(Value::parse("2024-03-09"), Value::Date("2024-03-09".to_string()));
+        assert_eq!(Value::parse("2024-03-09T10:00"), Value::Date("2024-03-09T10:00".to_string()));
+        assert_eq!(Value::parse("[a, 'b c', \"d\"]"), Value::List(vec!["a".into(), "b c".into(), "d".into()]));
+        assert_eq!(Value::parse("\"42\""), text("42"));
+        assert_eq!(Value::parse("plain # comment"), text("plain"));
+        assert_eq!(Value::parse("1.2.3"), text("1.2.3"));
+        assert_eq!(Value::parse("{a: 1}"), Value::Raw(" {a: 1}".to_string()));
+    }
+
+    #[test]
+    fn block_lists_and_nested_maps() {
+        let metadata = Metadata::parse("tags:\n  - one\n  - \"two\"\nauthor:\n  name: Ann");
+        assert_eq!(metadata.get("tags"), Some(&Value::List(vec!["one".into(), "two".into()])));
+        assert_eq!(metadata.get("author"), Some(&Value::Raw("\n  name: Ann".to_string())));
+        // Unindented items belong to the key above too
+        let metadata = Metadata::parse("tags:\n- a\n- b");
+        assert_eq!(metadata.get("tags"), Some(&Value::List(vec!["a".into(), "b".into()])));
+    }
+
+    #[test]
+    fn unchanged_entries_keep_how_they_were_written() {
+        let yaml = "# a comment\ntitle: 'Quoted'  # why\ntags: [a,b]\n\nnested:\n  x: 1";
+        let mut metadata = Metadata::parse(yaml);
+        assert_eq!(metadata.to_yaml(), yaml);
+        assert_eq!(metadata.iter().map(|(key, _)| key).collect::<Vec<_>>(), vec!["title", "tags", "nested"]);
+
+        metadata.set("tags", Value::List(vec!["a".into(), "b".into()]));
+        assert_eq!(metadata.to_yaml(), yaml);
+        metadata.set("tags", Value::List(vec!["c".into()]));
+        metadata.set("done", Value::Bool(false));
+        assert_eq!(metadata.to_yaml(), "# a comment\ntitle: 'Quoted'  # why\ntags:\n  - c\n\nnested:\n  x: 1\ndone: false");
+
+        assert_eq!(metadata.remove("title"), Some(text("Quoted")));
+        assert_eq!(metadata.get("title"), None);
+    }
+
+    #[test]
+    fn text_is_quoted_when_it_would_read_as_something_else() {
+        for value in ["plain words", "42", "true", "2024-01-01", "a: b", "#hash", " padded", "say \"hi\"", "back\\slash", ""] {
+            let mut metadata = Metadata::default();
+            metadata.set("key", text(value));
+            assert_eq!(Metadata::parse(&metadata.to_yaml()).get("key"), Some(&text(value)), "{:?}", value);
+        }
+        let mut metadata = Metadata::default();
+        metadata.set("key", text("plain words"));
+        assert_eq!(metadata.to_yaml(), "key: plain words");
+    }
+
+    #[test]
+    fn split_finds_the_front_matter() {
+        assert_eq!(split("---\na: 1\n---\nBody"), (Some("a: 1"), "Body"));
+        assert_eq!(split("---\r\na: 1\r\n---\r\nBody"), (Some("a: 1"), "Body"));
+        assert_eq!(split("---\n---\nBody"), (Some(""), "Body"));
+        // No closing fence, or a fence not at the very start, is no front matter
+        assert_eq!(split("---\na: 1\nBody"), (None, "---\na: 1\nBody"));
+        assert_eq!(split("\n---\na: 1\n---\n"), (None, "\n---\na: 1\n---\n"));
+        assert_eq!(body("---\na: 1\n---\nBody"), "Body");
+    }
+
+    #[test]
+    fn join_leaves_out_empty_front_matter() {
+        assert_eq!(join(&Metadata::default(), "Body"), "Body");
+        let metadata = Metadata::parse("a: 1");
+        assert_eq!(join(&metadata, "Body"), "---\na: 1\n---\nBody");
+        let (yaml, body) = split("---\na: 1\n---\nBody");
+        assert_eq!(join(&Metadata::parse(yaml.unwrap()), body), "---\na: 1\n---\nBody");
+    }
+
+    #[test]
+    fn items_of_a_value() {
+        assert_eq!(Value::List(vec!["a".into()]).items(), vec!["a".to_string()]);
+        assert_eq!(text("one").items(), vec!["one".to_string()]);
+        assert!(text("").items().is_empty());
+        assert!(Value::Number(1.0).items().is_empty());
+    }
+}
@@ -0,0 +1,744 @@
+//! Full-text search: an inverted index of the words in every note's title
+//! and text as last saved, and a small query language over it.
+//!
+//! Words in a query must all match, in any order. Besides plain words a
+//! query can hold:
+//! - `"quoted phrases"`, whose words must follow each other;
+//! - `OR` between alternatives, and `AND`, which is implied;
+//! - `NOT word` or `-word` to leave notes out, and parentheses to group;
+//! - `tag:name`, `title:word`, `path:folder` to match one part of a note;
+//! - `modified:2024-03` or `date:>=2024-03-01`, comparing the day a note was
+//!   last modified, or the `date` in its front matter, with `<`, `<=`, `>`,
+//!   `>=` or, by default, `=` to as much of a date as is given.
+//!
+//! A word also matches longer words it starts, and, when nothing else
+//! matches, words a typo or two away.
+//!
+//! [`hits`] finds where the words a query looks for are in a note.
+
+use crate::backlinks::Snippet;
+use crate::date::format_date;
+use crate::metadata::Value;
+use crate::store::{Note, NoteId};
+use std::cell::RefCell;
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::iter;
+use std::ops::{Bound, Range};
+use std::path::Path;
+
+/// How much more a word in the title counts than one in the text.
+const TITLE_BOOST: f32 = 3.0;
+/// How much a longer word that starts with the query word counts.
+const PREFIX_WEIGHT: f32 = 0.6;
+/// How much a word a typo or two away counts.
+const FUZZY_WEIGHT: f32 = 0.4;
+/// Most longer words a word stands for.
+const MAX_EXPANSIONS: usize = 50;
+/// BM25 parameters: how fast repeating a word stops adding to the score,
+/// and how much a note's length discounts it.
+const K1: f32 = 1.2;
+const B: f32 = 0.75;
+
+/// The words of every note, with where in the note each one is.
+#[derive(Default)]
+pub struct SearchIndex {
+    text: Postings,
+    title: Postings,
+    revision: u64,
+}
+
+#[derive(Default)]
+struct Postings {
+    /// Word to the notes that have it and its positions in each.
+    words: BTreeMap<String, HashMap<NoteId, Vec<u32>>>,
+    /// Distinct words of each note, to take it out again, and how many
+    /// words it has in all.
+    notes: HashMap<NoteId, (Vec<String>, u32)>,
+    total_words: u64,
+    /// Words found a typo or two away from a query word, since searching
+    /// the whole vocabulary again on every frame would not be instant.
+    typos: RefCell<HashMap<String, Vec<String>>>,
+}
+
+impl SearchIndex {
+    /// Indexes the note's title and content, replacing what was indexed
+    /// for it before.
+    pub fn update(&mut self, id: NoteId, title: &str, content: &str) {
+        self.text.insert(id, content);
+        self.title.insert(id, title);
+        self.revision += 1;
+    }
+
+    pub fn remove(&mut self, id: NoteId) {
+        self.text.remove(id);
+        self.title.remove(id);
+        self.revision += 1;
+    }
+
+    /// Changes whenever a note is indexed or removed, so that results kept
+    /// from an earlier search can tell they are out of date.
+    pub fn revision(&self) -> u64 {
+        self.revision
+    }
+
+    /// The indexed words the words of `query` stand for, to pass to
+    /// [`hits`].
+    pub fn words(&self, query: &Query) -> HashSet<String> {
+        let words = query.words();
+        let expanded = words.iter().flat_map(|word| self.text.expand(word).into_iter().chain(self.title.expand(word)));
+        expanded.map(|(word, _)| word).collect()
+    }
+
+    /// The notes among `notes` that match `query`, with their scores; the
+    /// higher, the better the match. `dir` is the notes directory, which
+    /// `path:` is relative to.
+    pub fn search(&self, query: &Query, notes: &[Note], dir: &Path) -> HashMap<NoteId, f32> {
+        Search { index: self, notes, dir }.run(query)
+    }
+}
+
+impl Postings {
+    fn insert(&mut self, id: NoteId, text: &str) {
+        self.remove(id);
+        self.typos.get_mut().clear();
+        let words = tokenize(text);
+        let mut positions: HashMap<&str, Vec<u32>> = HashMap::new();
+        for (i, word) in words.iter().enumerate() {
+            positions.entry(word).or_default().push(i as u32);
+        }
+        let distinct: Vec<String> = positions.keys().map(|word| word.to_string()).collect();
+        for (word, positions) in positions {
+            self.words.entry(word.to_string()).or_default().insert(id, positions);
+        }
+        self.total_words += words.len() as u64;
+        self.notes.insert(id, (distinct, words.len() as u32));
+    }
+
+    fn remove(&mut self, id: NoteId) {
+        let Some((words, length)) = self.notes.remove(&id) else {
+            return;
+        };
+        self.total_words -= u64::from(length);
+        self.typos.get_mut().clear();
+        for word in words {
+            if let Some(notes) = self.words.get_mut(&word) {
+                notes.remove(&id);
+                if notes.is_empty() {
+                    self.words.remove(&word);
+                }
+            }
+        }
+    }
+
+    /// The indexed words `word` stands for, with how much each counts.
+    fn expand(&self, word: &str) -> Vec<(String, f32)> {
+        let mut found: Vec<(String, f32)> = vec![];
+        if let Some((exact, _)) = self.words.get_key_value(word) {
+            found.push((exact.clone(), 1.0));
+        }
+        if word.chars().count() >= 2 {
+            let longer = self.words.range::<str, _>((Bound::Included(word), Bound::Unbounded)).map(|(w, _)| w);
+            let longer = longer.take_while(|w| w.starts_with(word)).filter(|w| *w != word);
+            found.extend(longer.take(MAX_EXPANSIONS).map(|w| (w.clone(), PREFIX_WEIGHT)));
+        }
+        if found.is_empty() {
+            let typos = self.typos.borrow_mut().entry(word.to_string()).or_insert_with(|| self.near(word)).clone();
+            found.extend(typos.into_iter().map(|w| (w, FUZZY_WEIGHT)));
+        }
+        found
+    }
+
+    // Words a typo or two away from `word`
+    fn near(&self, word: &str) -> Vec<String> {
+        let length = word.chars().count();
+        let max_typos = match length {
+            0..=3 => return vec![],
+            4..=7 => 1,
+            _ => 2,
+        };
+        let close = self.words.keys().filter(|w| w.chars().count().abs_diff(length) <= max_typos);
+        let close = close.filter(|w| within_typos(word, w, max_typos));
+        close.take(MAX_EXPANSIONS).cloned().collect()
+    }
+
+    /// BM25 relevance of each note for a word found `frequency` times in it.
+    fn score(&self, note_count: usize, matches: impl Iterator<Item = (NoteId, usize)> + Clone) -> Vec<(NoteId, f32)> {
+        let with_word = matches.clone().count() as f32;
+        let idf = (1.0 + (note_count as f32 - with_word + 0.5) / (with_word + 0.5)).ln();
+        let average = self.total_words as f32 / self.notes.len().max(1) as f32;
+        matches
+            .map(|(id, frequency)| {
+                let length = self.notes.get(&id).map_or(0.0, |(_, length)| *length as f32);
+                let tf = frequency as f32;
+                let norm = K1 * (1.0 - B + B * length / average.max(1.0));
+                (id, idf * tf * (K1 + 1.0) / (tf + norm))
+            })
+            .collect()
+    }
+
+    /// Notes where `words` follow each other, with how often they do.
+    fn phrase(&self, words: &[String]) -> Vec<(NoteId, usize)> {
+        let Some(first) = words.first().and_then(|word| self.words.get(word)) else {
+            return vec![];
+        };
+        let rest: Option<Vec<&HashMap<NoteId, Vec<u32>>>> = words[1..].iter().map(|word| self.words.get(word)).collect();
+        let Some(rest) = rest else {
+            return vec![];
+        };
+        first
+            .iter()
+            .filter_map(|(id, starts)| {
+                let count = starts
+                    .iter()
+                    .filter(|&&start| {
+                        rest.iter().enumerate().all(|(i, notes)| {
+                            notes.get(id).is_some_and(|positions| positions.binary_search(&(start + i as u32 + 1)).is_ok())
+                        })
+                    })
+                    .count();
+                (count > 0).then_some((*id, count))
+            })
+            .collect()
+    }
+}
+
+/// A parsed query.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Query {
+    /// Every note; what an empty query means.
+    All,
+    Word(String),
+    Phrase(Vec<String>),
+    /// A word or phrase in the title only.
+    Title(Box<Query>),
+    Tag(String),
+    /// Part of the note's path relative to the notes directory.
+    Path(String),
+    Date { field: DateField, op: DateOp, date: String },
+    And(Vec<Query>),
+    Or(Vec<Query>),
+    Not(Box<Query>),
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum DateField {
+    /// When the note's file was last written.
+    Modified,
+    /// The `date` property of the front matter.
+    Property,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum DateOp {
+    Before,
+    UpTo,
+    On,
+    From,
+    After,
+}
+
+impl Query {
+    pub fn parse(text: &str) -> Self {
+        let tokens = lex(text);
+        let mut parser = Parser { tokens: &tokens, pos: 0 };
+        let mut query = parser.or();
+        // A stray `)` ends the expression early; read on past it
+        while parser.pos < tokens.len() {
+            parser.pos += 1;
+            query = and(vec![query, parser.or()]);
+        }
+        query
+    }
+
+    /// The words the query looks for, to highlight in what it finds;
+    /// excluded words are left out.
+    pub fn words(&self) -> Vec<String> {
+        match self {
+            Query::Word(word) => vec![word.clone()],
+            Query::Phrase(words) => words.clone(),
+            Query::Title(query) => query.words(),
+            Query::And(queries) | Query::Or(queries) => queries.iter().flat_map(Query::words).collect(),
+            _ => vec![],
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+enum Token {
+    Word(String),
+    Phrase(String),
+    Field(String, String),
+    Open,
+    Close,
+    And,
+    Or,
+    Not,
+}
+
+fn lex(text: &str) -> Vec<Token> {
+    let mut tokens = vec![];
+    let mut chars = text.chars().peekable();
+    while let Some(&c) = chars.peek() {
+        match c {
+            _ if c.is_whitespace() => {
+                chars.next();
+            }
+            '(' => {
+                chars.next();
+                tokens.push(Token::Open);
+            }
+            ')' => {
+                chars.next();
+                tokens.push(Token::Close);
+            }
+            '"' => {
+                chars.next();
+                let phrase: String = chars.by_ref().take_while(|&c| c != '"').collect();
+                tokens.push(Token::Phrase(phrase));
+            }
+            '-' => {
+                chars.next();
+                if chars.peek().is_some_and(|c| !c.is_whitespace()) {
+                    tokens.push(Token::Not);
+                }
+            }
+            _ => {
+                let mut word = String::new();
+                while let Some(&c) = chars.peek() {
+                    if c.is_whitespace() || c == '(' || c == ')' || (c == '"' && !word.ends_with(':')) {
+                        break;
+                    }
+                    chars.next();
+                    if c == '"' {
+                        // `title:"a phrase"`
+                        word.extend(chars.by_ref().take_while(|&c| c != '"'));
+                        break;
+                    }
+                    word.push(c);
+                }
+                tokens.push(match word.as_str() {
+                    "AND" => Token::And,
+                    "OR" => Token::Or,
+                    "NOT" => Token::Not,
+                    _ => match word.split_once(':') {
+                        Some((field, value)) if is_field(field) && !value.is_empty() => {
+                            Token::Field(field.to_lowercase(), value.to_string())
+                        }
+                        _ => Token::Word(word),
+                    },
+                });
+            }
+        }
+    }
+    tokens
+}
+
+fn is_field(name: &str) -> bool {
+    matches!(name.to_lowercase().as_str(), "tag" | "title" | "path" | "modified" | "date")
+}
+
+struct Parser<'a> {
+    tokens: &'a [Token],
+    pos: usize,
+}
+
+impl Parser<'_> {
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.pos)
+    }
+
+    // alternatives := terms ("OR" terms)*
+    fn or(&mut self) -> Query {
+        let mut alternatives = vec![self.and()];
+        while self.peek() == Some(&Token::Or) {
+            self.pos += 1;
+            alternatives.push(self.and());
+        }
+        // An empty alternative, as in `a OR` while typing, would match everything
+        alternatives.retain(|alternative| *alternative != Query::All);
+        match alternatives.len() {
+            0 => Query::All,
+            1 => alternatives.remove(0),
+            _ => Query::Or(alternatives),
+        }
+    }
+
+    // terms := ("AND"? unary)*
+    fn and(&mut self) -> Query {
+        let mut terms = vec![];
+        loop {
+            match self.peek() {
+                None | Some(Token::Close) | Some(Token::Or) => break,
+                Some(Token::And) => self.pos += 1,
+                _ => terms.push(self.unary()),
+            }
+        }
+        and(terms)
+    }
+
+    fn unary(&mut self) -> Query {
+        match self.peek() {
+            Some(Token::Not) => {
+                self.pos += 1;
+                match self.peek() {
+                    None | Some(Token::Close) | Some(Token::Or) => Query::All,
+                    _ => Query::Not(Box::new(self.unary())),
+                }
+            }
+            _ => self.primary(),
+        }
+    }
+
+    fn primary(&mut self) -> Query {
+        let token = self.tokens[self.pos].clone();
+        self.pos += 1;
+        match token {
+            Token::Open => {
+                let query = self.or();
+                if self.peek() == Some(&Token::Close) {
+                    self.pos += 1;
+                }
+                query
+            }
+            Token::Word(text) | Token::Phrase(text) => text_query(&text),
+            Token::Field(field, value) => field_query(&field, &value),
+            // Operators where a term belongs are ignored
+            Token::Close | Token::And | Token::Or | Token::Not => Query::All,
+        }
+    }
+}
+
+fn and(mut terms: Vec<Query>) -> Query {
+    terms.retain(|term| *term != Query::All);
+    match terms.len() {
+        0 => Query::All,
+        1 => terms.remove(0),
+        _ => Query::And(terms),
+    }
+}
+
+// A word, or a phrase if it breaks into several, as in `well-known`
+fn text_query(text: &str) -> Query {
+    let mut words = tokenize(text);
+    match words.len() {
+        0 => Query::All,
+        1 => Query::Word(words.remove(0)),
+        _ => Query::Phrase(words),
+    }
+}
+
+fn field_query(field: &str, value: &str) -> Query {
+    match field {
+        "tag" => Query::Tag(value.trim_start_matches('#').to_lowercase()),
+        "title" => match text_query(value) {
+            Query::All => Query::All,
+            query => Query::Title(Box::new(query)),
+        },
+        "path" => Query::Path(value.to_lowercase()),
+        _ => {
+            let field = if field == "modified" { DateField::Modified } else { DateField::Property };
+            let ops = [(">=", DateOp::From), ("<=", DateOp::UpTo), (">", DateOp::After), ("<", DateOp::Before), ("=", DateOp::On)];
+            let (op, date) = ops
+                .into_iter()
+                .find_map(|(prefix, op)| value.strip_prefix(prefix).map(|date| (op, date)))
+                .unwrap_or((DateOp::On, value));
+            Query::Date { field, op, date: date.to_string() }
+        }
+    }
+}
+
+struct Search<'a> {
+    index: &'a SearchIndex,
+    notes: &'a [Note],
+    dir: &'a Path,
+}
+
+impl Search<'_> {
+    fn run(&self, query: &Query) -> HashMap<NoteId, f32> {
+        match query {
+            Query::All => self.notes.iter().map(|note| (note.id, 0.0)).collect(),
+            Query::Word(word) => {
+                let mut scores = self.word(&self.index.text, word, 1.0);
+                for (id, score) in self.word(&self.index.title, word, TITLE_BOOST) {
+                    *scores.entry(id).or_default() += score;
+                }
+                scores
+            }
+            Query::Phrase(words) => {
+                let mut scores = self.phrase(&self.index.text, words, 1.0);
+                for (id, score) in self.phrase(&self.index.title, words, TITLE_BOOST) {
+                    *scores.entry(id).or_default() += score;
+                }
+                scores
+            }
+            Query::Title(query) => match query.as_ref() {
+                Query::Word(word) => self.word(&self.index.title, word, TITLE_BOOST),
+                Query::Phrase(words) => self.phrase(&self.index.title, words, TITLE_BOOST),
+                query => self.run(query),
+            },
+            Query::Tag(tag) => self.filter(|note| note.has_tag(tag)),
+            Query::Path(part) => self.filter(|note| {
+                let path = note.path.strip_prefix(self.dir).unwrap_or(&note.path);
+                path.to_string_lossy().to_lowercase().replace('\\', "/").contains(part.as_str())
+            }),
+            Query::Date { field, op, date } => self.filter(|note| {
+                let value = match field {
+                    DateField::Modified => note.disk_modified.map(format_date),
+                    DateField::Property => match note.metadata.get("date") {
+                        Some(Value::Date(value)) => Some(value.clone()),
+                        _ => None,
+                    },
+                };
+                value.is_some_and(|value| compare_date(&value, *op, date))
+            }),
+            Query::And(queries) => {
+                let mut queries = queries.iter();
+                let mut scores = queries.next().map(|query| self.run(query)).unwrap_or_default();
+                for query in queries {
+                    let other = self.run(query);
+                    scores.retain(|id, _| other.contains_key(id));
+                    for (id, score) in scores.iter_mut() {
+                        *score += other[id];
+                    }
+                }
+                scores
+            }
+            Query::Or(queries) => {
+                let mut scores: HashMap<NoteId, f32> = HashMap::new();
+                for query in queries {
+                    for (id, score) in self.run(query) {
+                        *scores.entry(id).or_default() += score;
+                    }
+                }
+                scores
+            }
+            Query::Not(query) => {
+                let excluded: HashSet<NoteId> = self.run(query).into_keys().collect();
+                self.notes.iter().filter(|note| !excluded.contains(&note.id)).map(|note| (note.id, 0.0)).collect()
+            }
+        }
+    }
+
+    fn filter(&self, keep: impl Fn(&Note) -> bool) -> HashMap<NoteId, f32> {
+        self.notes.iter().filter(|note| keep(note)).map(|note| (note.id, 0.0)).collect()
+    }
+
+    fn word(&self, postings: &Postings, word: &str, boost: f32) -> HashMap<NoteId, f32> {
+        let mut scores: HashMap<NoteId, f32> = HashMap::new();
+        for (found, weight) in postings.expand(word) {
+            let matches = postings.words[&found].iter().map(|(id, positions)| (*id, positions.len()));
+            for (id, score) in postings.score(self.notes.len(), matches) {
+                // The best of the words it stands for, not their sum
+                let entry = scores.entry(id).or_default();
+                *entry = entry.max(score * weight * boost);
+            }
+        }
+        scores
+    }
+
+    fn phrase(&self, postings: &Postings, words: &[String], boost: f32) -> HashMap<NoteId, f32> {
+        let matches = postings.phrase(words);
+        let scores = postings.score(self.notes.len(), matches.iter().copied());
+        scores.into_iter().map(|(id, score)| (id, score * words.len() as f32 * boost)).collect()
+    }
+}
+
+// Compares as much of `value` as `date` gives, so `2024-03` is all of March
+fn compare_date(value: &str, op: DateOp, date: &str) -> bool {
+    let value = value.get(..date.len()).unwrap_or(value);
+    match op {
+        DateOp::Before => value < date,
+        DateOp::UpTo => value <= date,
+        DateOp::On => value == date,
+        DateOp::From => value >= date,
+        DateOp::After => value > date,
+    }
+}
+
+/// A note a search found, and where in its body the words searched for
+/// are; a note found by tag, path or date alone has none.
+#[derive(Clone, Debug)]
+pub struct SearchResult {
+    pub id: NoteId,
+    pub hits: Vec<Hit>,
+}
+
+/// Where one of the words searched for is in a note.
+#[derive(Clone, Debug)]
+pub struct Hit {
+    /// Byte range of the word in the text searched.
+    pub range: Range<usize>,
+    pub snippet: Snippet,
+}
+
+/// Every occurrence in `text` of the indexed `words`, from
+/// [`SearchIndex::words`].
+pub fn hits(text: &str, words: &HashSet<String>) -> Vec<Hit> {
+    spans(text)
+        .filter(|span| words.contains(&text[span.clone()].to_lowercase()))
+        .map(|range| Hit { snippet: Snippet::around(text, range.clone()), range })
+        .collect()
+}
+
+// The words of `text` in lower case: runs of letters and digits
+fn tokenize(text: &str) -> Vec<String> {
+    spans(text).map(|span| text[span].to_lowercase()).collect()
+}
+
+fn spans(text: &str) -> impl Iterator<Item = Range<usize>> + '_ {
+    let mut start = None;
+    text.char_indices().chain(iter::once((text.len(), ' '))).filter_map(move |(i, c)| {
+        match (c.is_alphanumeric(), start) {
+            (true, None) => start = Some(i),
+            (false, Some(from)) => {
+                start = None;
+                return Some(from..i);
+            }
+            _ => {}
+        }
+        None
+    })
+}
+
+// Whether turning `a` into `b` takes at most `max` single-character edits
+fn within_typos(a: &str, b: &str, max: usize) -> bool {
+    let a: Vec<char> = a.chars().collect();
+    let b: Vec<char> = b.chars().collect();
+    let mut previous: Vec<usize> = (0..=b.len()).collect();
+    for i in 1..=a.len() {
+        let mut current = vec![i; b.len() + 1];
+        for j in 1..=b.len() {
+            let substitution = previous[j - 1] + usize::from(a[i - 1] != b[j - 1]);
+            current[j] = substitution.min(previous[j] + 1).min(current[j - 1] + 1);
+        }
+        // Every path through the rest costs at least this row's minimum
+        if current.iter().min().is_some_and(|&min| min > max) {
+            return false;
+        }
+        previous = current;
+    }
+    previous[b.len()] <= max
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::store::tests::store_with as store;
+    use crate::store::SortOrder;
+
+    fn word(word: &str) -> Query {
+        Query::Word(word.to_string())
+    }
+
+    #[test]
+    fn parse_words_and_phrases() {
+        assert_eq!(Query::parse(""), Query::All);
+        assert_eq!(Query::parse("Rust"), word("rust"));
+        assert_eq!(Query::parse("a b"), Query::And(vec![word("a"), word("b")]));
+        assert_eq!(Query::parse("a AND b"), Query::parse("a b"));
+        assert_eq!(Query::parse("\"big red\""), Query::Phrase(vec!["big".to_string(), "red".to_string()]));
+        assert_eq!(Query::parse("well-known"), Query::Phrase(vec!["well".to_string(), "known".to_string()]));
+    }
+
+    #[test]
+    fn parse_operators() {
+        assert_eq!(Query::parse("a OR b c"), Query::Or(vec![word("a"), Query::And(vec![word("b"), word("c")])]));
+        assert_eq!(Query::parse("a -b"), Query::And(vec![word("a"), Query::Not(Box::new(word("b")))]));
+        assert_eq!(Query::parse("NOT b"), Query::Not(Box::new(word("b"))));
+        assert_eq!(
+            Query::parse("(a OR b) c"),
+            Query::And(vec![Query::Or(vec![word("a"), word("b")]), word("c")])
+        );
+        // A lone dash or a dangling operator is no term
+        assert_eq!(Query::parse("a - b"), Query::And(vec![word("a"), word("b")]));
+        assert_eq!(Query::parse("a OR"), word("a"));
+        assert_eq!(Query::parse("a)b"), Query::And(vec![word("a"), word("b")]));
+    }
+
+    #[test]
+    fn parse_fields() {
+        assert_eq!(Query::parse("tag:#Work"), Query::Tag("work".to_string()));
+        assert_eq!(Query::parse("title:\"to do\""), Query::Title(Box::new(Query::parse("\"to do\""))));
+        assert_eq!(Query::parse("path:Journal/"), Query::Path("journal/".to_string()));
+        assert_eq!(
+            Query::parse("date:>=2024-03"),
+            Query::Date { field: DateField::Property, op: DateOp::From, date: "2024-03".to_string() }
+        );
+        assert_eq!(
+            Query::parse("modified:2024"),
+            Query::Date { field: DateField::Modified, op: DateOp::On, date: "2024".to_string() }
+        );
+        // Not a field, so an ordinary word
+        assert_eq!(Query::parse("http:x"), Query::Phrase(vec!["http".to_string(), "x".to_string()]));
+    }
+
+    #[test]
+    fn titles_rank_above_text_and_repeats_above_one_mention() {
+        let (store, ids) = store(&[
+            ("Once", "mentions garden once among many other words here"),
+            ("Garden", "nothing else"),
+            ("Often", "garden garden garden"),
+        ]);
+        assert_eq!(store.query("garden", SortOrder::Title), vec![ids[1], ids[2], ids[0]]);
+    }
+
+    #[test]
+    fn phrases_need_their_words_in_order() {
+        let (store, ids) = store(&[("A", "the big red barn"), ("B", "red and big"), ("C", "big red")]);
+        let mut found = store.query("\"big red\"", SortOrder::Title);
+        found.sort();
+        assert_eq!(found, vec![ids[0], ids[2]]);
+    }
+
+    #[test]
+    fn exclusions_and_alternatives() {
+        let (store, ids) = store(&[("A", "apples pears"), ("B", "apples"), ("C", "plums")]);
+        assert_eq!(store.query("apples -pears", SortOrder::Title), vec![ids[1]]);
+        let mut found = store.query("pears OR plums", SortOrder::Title);
+        found.sort();
+        assert_eq!(found, vec![ids[0], ids[2]]);
+    }
+
+    #[test]
+    fn prefixes_match_and_typos_only_when_nothing_else_does() {
+        let (store, ids) = store(&[("A", "programming"), ("B", "program")]);
+        assert_eq!(store.query("progr", SortOrder::Title).len(), 2);
+        // The exact word beats the longer one it starts
+        assert_eq!(store.query("program", SortOrder::Title)[0], ids[1]);
+        assert_eq!(store.query("programing", SortOrder::Title), vec![ids[0]]);
+    }
+
+    #[test]
+    fn typos_allowed_grow_with_the_word() {
+        assert!(within_typos("cat", "cat", 0));
+        assert!(!within_typos("cat", "cut", 0));
+        assert!(within_typos("house", "horse", 1));
+        assert!(!within_typos("house", "horses", 1));
+        assert!(within_typos("keyboard", "kyeboadr", 4));
+        assert!(within_typos("elephant", "elefhent", 2));
+
+        let (store, _) = store(&[("A", "cat house elephant")]);
+        // Up to three letters a word must match exactly
+        assert!(store.query("cut", SortOrder::Title).is_empty());
+        assert_eq!(store.query("horse", SortOrder::Title).len(), 1);
+        assert!(store.query("hirsa", SortOrder::Title).is_empty());
+        assert_eq!(store.query("elefhent", SortOrder::Title).len(), 1);
+        assert!(store.query("alefhent", SortOrder::Title).is_empty());
+    }
+
+    #[test]
+    fn new_notes_are_found_before_they_are_saved() {
+        let (mut store, _) = store(&[]);
+        let id = store.create("Quarterly Report").unwrap();
+        assert_eq!(store.query("quarterly", SortOrder::Title), vec![id]);
+    }
+
+    #[test]
+    fn hits_are_the_expanded_words_in_the_text() {
+        let (store, _) = store(&[("A", "Walking and walked")]);
+        let words = store.search_index().words(&Query::parse("walk"));
+        let text = "I walked; Walking helps.";
+        let ranges: Vec<_> = hits(text, &words).into_iter().map(|hit| hit.range).collect();
+        assert_eq!(ranges, vec![2..8, 10..17]);
+    }
+}
@@ -0,0 +1,43 @@
+//! What was open when the app last closed, kept in eframe's storage so the
+//! next start picks up where the user left off.
+
+use rusty_notes::NoteId;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::PathBuf;
+
+pub const SESSION_KEY: &str = "session";
+
+/// Where the user was in a note's editor.
+#[derive(Clone, Copy, Default, Serialize, Deserialize)]
+pub struct TabView {
+    /// Character offset of the cursor.
+    pub cursor: Option<usize>,
+    pub scroll: f32,
+}
+
+/// What the central panel shows.
+#[derive(Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+pub enum View {
+    #[default]
+    Edit,
+    Preview,
+    Graph,
+    /// Every match of the sidebar search.
+    Search,
+}
+
+#[derive(Clone, Default, Serialize, Deserialize)]
+pub struct VaultSession {
+    pub tabs: Vec<(NoteId, TabView)>,
+    pub active: Option<NoteId>,
+}
+
+#[derive(Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Session {
+    pub view: View,
+    pub sidebar_width: Option<f32>,
+    /// Open tabs of every vault, so switching back to a vault reopens them too.
+    pub vaults: BTreeMap<PathBuf, VaultSession>,
+}
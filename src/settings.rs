@@ -0,0 +1,142 @@
+//! User preferences, kept as TOML in the configuration directory.
+
+use crate::backend::write_atomic;
+use crate::config;
+use crate::error::SettingsError;
+use crate::history::Retention;
+use crate::store::SortOrder;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+const SETTINGS_FILE: &str = "settings.toml";
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Theme {
+    #[default]
+    Dark,
+    Light,
+}
+
+/// Shortcuts written like `Ctrl+Shift+N`. `Ctrl` means Cmd on macOS.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Keybindings {
+    pub new_note: String,
+    pub save: String,
+    pub toggle_preview: String,
+    pub toggle_graph: String,
+    pub find: String,
+    pub replace: String,
+    pub close_tab: String,
+}
+
+impl Default for Keybindings {
+    fn default() -> Self {
+        Self {
+            new_note: "Ctrl+N".to_string(),
+            save: "Ctrl+S".to_string(),
+            toggle_preview: "Ctrl+P".to_string(),
+            toggle_graph: "Ctrl+G".to_string(),
+            find: "Ctrl+F".to_string(),
+            replace: "Ctrl+H".to_string(),
+            close_tab: "Ctrl+W".to_string(),
+        }
+    }
+}
+
+/// Every field has a default, so a settings file only needs the values
+/// the user changed, and settings added later load from older files.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Settings {
+    pub theme: Theme,
+    pub autosave_interval_secs: u64,
+    /// Save every note when the window is closed instead of asking.
+    pub save_on_close: bool,
+    /// Vault opened at start when none is given on the command line;
+    /// without one the most recently used vault is opened.
+    pub default_vault: Option<PathBuf>,
+    pub editor_font_size: f32,
+    pub interface_font_size: f32,
+    pub sort_order: SortOrder,
+    /// Saved versions kept of each note.
+    pub history: Retention,
+    /// Days a deleted note stays in the trash; 0 keeps it until purged.
+    pub trash_retention_days: u64,
+    pub keybindings: Keybindings,
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Self {
+            theme: Theme::Dark,
+            autosave_interval_secs: 30,
+            save_on_close: false,
+            default_vault: None,
+            editor_font_size: 12.0,
+            interface_font_size: 12.5,
+            sort_order: SortOrder::Title,
+            history: Retention::default(),
+            trash_retention_days: 30,
+            keybindings: Keybindings::default(),
+        }
+    }
+}
+
+impl Settings {
+    /// Where the settings live, if the platform has a configuration directory.
+    pub fn path() -> Option<PathBuf> {
+        config::config_dir().map(|dir| dir.join(SETTINGS_FILE))
+    }
+
+    /// Reads the settings file; a missing file gives the defaults.
+    pub fn load_from(path: &Path) -> Result<Self, SettingsError> {
+        let text = match fs::read_to_string(path) {
+            Ok(text) => text,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(Self::default()),
+            Err(source) => return Err(SettingsError::Read { path: path.to_path_buf(), source }),
+        };
+        toml::from_str(&text).map_err(|source| SettingsError::Parse { path: path.to_path_buf(), source })
+    }
+
+    pub fn save_to(&self, path: &Path) -> Result<(), SettingsError> {
+        let text = toml::to_string_pretty(self).expect("settings always serialize");
+        let write = || {
+            if let Some(parent) = path.parent() {
+                fs::create_dir_all(parent)?;
+            }
+            write_atomic(path, text.as_bytes())
+        };
+        write().map_err(|source| SettingsError::Write { path: path.to_path_buf(), source })
+    }
+
+    pub fn autosave_interval(&self) -> Duration {
+        Duration::from_secs(self.autosave_interval_secs)
+    }
+
+    /// How long deleted notes stay in the trash, or `None` for no limit.
+    pub fn trash_retention(&self) -> Option<Duration> {
+        // A huge number of days from a hand-edited file means keeping them forever
+        let secs = self.trash_retention_days.saturating_mul(24 * 60 * 60);
+        (self.trash_retention_days > 0).then(|| Duration::from_secs(secs))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn trash_retention_in_days() {
+        let mut settings = Settings { trash_retention_days: 2, ..Settings::default() };
+        assert_eq!(settings.trash_retention(), Some(Duration::from_secs(2 * 24 * 60 * 60)));
+        settings.trash_retention_days = 0;
+        assert_eq!(settings.trash_retention(), None);
+        settings.trash_retention_days = u64::MAX;
+        assert_eq!(settings.trash_retention(), Some(Duration::from_secs(u64::MAX)));
+    }
+}
@@ -0,0 +1,1522 @@
+use crate::backend::{Backend, FsBackend, MemoryBackend};
+use crate::backlinks::{Backlink, LinkIndex, Mention, Snippet};
+use crate::error::{Result, StoreError};
+use crate::find::{self, Finder, NoteChanges};
+use crate::history::{self, Retention, Version};
+use crate::links;
+use crate::trash::{self, TrashEntry, TRASH_DIR};
+use crate::markdown;
+use crate::metadata::{self, Metadata, Value};
+use crate::search::{self, Query, SearchIndex, SearchResult};
+use crate::tags;
+use crate::title::{self, slugify};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
+use std::fmt;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant, SystemTime};
+
+/// File in the notes directory recording which id belongs to which note file.
+pub const ID_INDEX_FILE: &str = ".ids";
+
+/// Hidden folder in the notes directory holding copies of unsaved edits,
+/// named like the note files they belong to.
+pub const SWAP_DIR: &str = ".swap";
+
+/// Identifies a note for as long as it lives in its directory, across
+/// renames, re-sorts and restarts.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct NoteId(pub u64);
+
+impl fmt::Display for NoteId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+pub struct Note {
+    pub id: NoteId,
+    pub title: String,
+    /// The whole file, front matter included.
+    pub content: String,
+    /// The properties in the front matter of `content`.
+    pub metadata: Metadata,
+    /// The `#tags` and front matter tags of `content`, in lower case.
+    pub tags: BTreeSet<String>,
+    pub path: PathBuf,
+    pub unsaved_changes: bool,
+    pub last_saved: Instant,
+    /// Modification time of the file when it was last read or written.
+    pub disk_modified: Option<SystemTime>,
+    /// The file content as last read or written: the common base when the
+    /// file and the editor both changed.
+    pub disk_content: String,
+    /// Why the file could not be read. Such a note is never saved, since
+    /// that would replace the file with whatever the editor holds.
+    pub load_error: Option<String>,
+    /// When the edits not yet copied to the swap folder began.
+    pub unjournaled_since: Option<Instant>,
+    /// How often the content was changed, for [`NoteStore::revision`].
+    edits: u64,
+}
+
+/// Order of the notes listed in the sidebar.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SortOrder {
+    #[default]
+    Title,
+    TitleDescending,
+    /// Most recently modified first.
+    Modified,
+}
+
+pub enum SaveOutcome {
+    /// The note had no unsaved changes.
+    Unchanged,
+    Saved,
+    /// The file changed on disk since it was loaded, so nothing was written.
+    /// Resolve with [`NoteStore::overwrite`], [`NoteStore::reload`] or
+    /// [`NoteStore::save_as_copy`].
+    Conflict,
+}
+
+/// An unsaved version of a note found in the swap folder, left behind by a
+/// crash or by edits discarded on close.
+pub struct Recovery {
+    pub id: NoteId,
+    pub content: String,
+    /// When the copy was written.
+    pub saved_at: SystemTime,
+}
+
+#[derive(Default)]
+pub struct AutosaveReport {
+    /// Notes skipped because their file changed on disk since they were loaded.
+    pub conflicts: Vec<NoteId>,
+    /// Notes whose write failed or was refused; they stay dirty and are
+    /// retried next interval.
+    pub failed: Vec<(NoteId, StoreError)>,
+}
+
+/// Something another program did to the notes directory, as found by
+/// [`NoteStore::sync_with_disk`].
+pub enum DiskChange {
+    Added(NoteId),
+    /// The file changed and the note had no local edits, so it was reloaded.
+    Reloaded(NoteId),
+    /// The file changed or vanished while the note had unsaved edits; the
+    /// local content was kept.
+    Diverged(NoteId),
+    Renamed(NoteId),
+    Removed(Box<Note>),
+}
+
+impl Note {
+    fn new(id: NoteId, content: String, path: PathBuf) -> Self {
+        let mut note = Self {
+            id,
+            title: String::new(),
+            metadata: Metadata::default(),
+            tags: BTreeSet::new(),
+            path,
+            unsaved_changes: false,
+            last_saved: Instant::now(),
+            disk_modified: None,
+            disk_content: content.clone(),
+            load_error: None,
+            unjournaled_since: None,
+            edits: 0,
+            content,
+        };
+        note.refresh_metadata();
+        note
+    }
+
+    /// Replaces the content and marks the note dirty if it changed.
+    pub fn set_content(&mut self, content: String) {
+        if self.content != content {
+            self.content = content;
+            self.edits += 1;
+            self.unsaved_changes = true;
+            self.unjournaled_since.get_or_insert_with(Instant::now);
+            self.refresh_metadata();
+        }
+    }
+
+    /// The content after the front matter, which is what the editor shows.
+    pub fn body(&self) -> &str {
+        metadata::body(&self.content)
+    }
+
+    /// Replaces the content after the front matter.
+    pub fn set_body(&mut self, body: String) {
+        let front_matter = &self.content[..self.content.len() - self.body().len()];
+        self.set_content(format!("{}{}", front_matter, body));
+    }
+
+    /// Sets a property in the front matter, adding the front matter if needed.
+    pub fn set_property(&mut self, key: &str, value: Value) {
+        let mut properties = self.metadata.clone();
+        properties.set(key, value);
+        self.set_content(metadata::join(&properties, self.body()));
+    }
+
+    pub fn remove_property(&mut self, key: &str) {
+        let mut properties = self.metadata.clone();
+        if properties.remove(key).is_some() {
+            self.set_content(metadata::join(&properties, self.body()));
+        }
+    }
+
+    /// Whether the note has `tag`, or a tag nested below it.
+    pub fn has_tag(&self, tag: &str) -> bool {
+        tags::matches(&self.tags, tag)
+    }
+
+    // The front matter, the tags, and the title written in the note or else
+    // its file name
+    fn refresh_metadata(&mut self) {
+        let (front_matter, _) = metadata::split(&self.content);
+        self.metadata = front_matter.map(Metadata::parse).unwrap_or_default();
+        self.tags = tags::extract(&self.content);
+        self.title = title::from_content(&self.content).unwrap_or_else(|| title::from_path(&self.path));
+    }
+
+    /// Words and characters after the front matter.
+    pub fn count_words_and_chars(&self) -> (usize, usize) {
+        let body = self.body();
+        (body.split_whitespace().count(), body.chars().count())
+    }
+}
+
+/// The notes of one directory and its folders, kept sorted by title.
+pub struct NoteStore {
+    dir: PathBuf,
+    backend: Box<dyn Backend>,
+    notes: Vec<Note>,
+    /// Every folder below `dir`, relative to it.
+    folders: BTreeSet<PathBuf>,
+    /// The wiki links of every note as last saved.
+    links: LinkIndex,
+    /// The words of every note as last saved.
+    search: SearchIndex,
+    /// Moves, and the edits of notes since removed, neither of which the
+    /// search index or the notes count.
+    edits: u64,
+    next_id: u64,
+    retention: Retention,
+}
+
+impl NoteStore {
+    /// Opens `dir` on the real filesystem, creating it if needed.
+    pub fn open(dir: impl Into<PathBuf>) -> Result<Self> {
+        Self::with_backend(dir, Box::new(FsBackend))
+    }
+
+    /// An empty store that never touches disk.
+    pub fn in_memory() -> Self {
+        Self::with_backend("notes", Box::new(MemoryBackend::new()))
+            .expect("memory backend cannot fail to list")
+    }
+
+    pub fn with_backend(dir: impl Into<PathBuf>, mut backend: Box<dyn Backend>) -> Result<Self> {
+        let dir = dir.into();
+        backend
+            .create_dir_all(&dir)
+            .map_err(|source| StoreError::CreateDir { dir: dir.clone(), source })?;
+        let (known_ids, next_free) = read_id_index(backend.as_ref(), &dir);
+        let mut next_id = known_ids.values().map(|id| id.0 + 1).max().unwrap_or(1).max(next_free);
+        let mut notes = vec![];
+
+        let (files, folders) = scan(backend.as_ref(), &dir)?;
+        for path in files {
+            if is_note_file(&path) {
+                let (content, load_error) = match backend.read(&path) {
+                    Ok(content) => (content, None),
+                    Err(e) => (String::new(), Some(e.to_string())),
+                };
+                let relative = path.strip_prefix(&dir).unwrap_or(&path).to_string_lossy();
+                let id = known_ids
+                    .get(relative.as_ref())
+                    .copied()
+                    .unwrap_or_else(|| {
+                        next_id += 1;
+                        NoteId(next_id - 1)
+                    });
+                let mut note = Note::new(id, content, path);
+                note.disk_modified = backend.modified(&note.path).ok();
+                note.load_error = load_error;
+                notes.push(note);
+            }
+        }
+
+        let mut links = LinkIndex::default();
+        let mut search = SearchIndex::default();
+        for note in &notes {
+            links.update(note.id, &note.disk_content);
+            search.update(note.id, &note.title, &note.disk_content);
+        }
+        let retention = Retention::default();
+        let mut store = Self { dir, backend, notes, folders, links, search, edits: 0, next_id, retention };
+        store.sort();
+        store.write_id_index();
+        Ok(store)
+    }
+
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    pub fn notes(&self) -> &[Note] {
+        &self.notes
+    }
+
+    pub fn len(&self) -> usize {
+        self.notes.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.notes.is_empty()
+    }
+
+    pub fn get(&self, id: NoteId) -> Option<&Note> {
+        self.notes.iter().find(|note| note.id == id)
+    }
+
+    pub fn get_mut(&mut self, id: NoteId) -> Option<&mut Note> {
+        self.notes.iter_mut().find(|note| note.id == id)
+    }
+
+    pub fn contains(&self, id: NoteId) -> bool {
+        self.get(id).is_some()
+    }
+
+    pub fn find_by_path(&self, path: &Path) -> Option<NoteId> {
+        self.notes.iter().find(|note| note.path == path).map(|note| note.id)
+    }
+
+    /// The note a wiki link to `title` leads to.
+    pub fn find_by_title(&self, title: &str) -> Option<NoteId> {
+        self.notes.iter().find(|note| links::targets(title, &note.title)).map(|note| note.id)
+    }
+
+    /// The wiki links of every note as last saved.
+    pub fn links(&self) -> &LinkIndex {
+        &self.links
+    }
+
+    /// The notes linking to this one as they were last saved, with the
+    /// lines the links are on, in note order.
+    pub fn backlinks(&self, id: NoteId) -> Vec<Backlink> {
+        let Some(note) = self.get(id) else {
+            return vec![];
+        };
+        let sources: BTreeSet<NoteId> = self.links.linking_to(&note.title).filter(|&source| source != id).collect();
+        self.notes
+            .iter()
+            .filter(|source| sources.contains(&source.id))
+            .map(|source| {
+                // Lines are counted in the body, as the editor shows it
+                let body = metadata::body(&source.disk_content);
+                let snippets = links::find(body)
+                    .into_iter()
+                    .filter(|link| links::targets(&link.target, &note.title))
+                    .map(|link| Snippet::around(body, link.range))
+                    .collect();
+                Backlink { source: source.id, snippets }
+            })
+            .collect()
+    }
+
+    /// Where other notes write this note's title without linking to it.
+    pub fn unlinked_mentions(&self, id: NoteId) -> Vec<Mention> {
+        let Some(note) = self.get(id) else {
+            return vec![];
+        };
+        let mut mentions = vec![];
+        for source in self.notes.iter().filter(|source| source.id != id && source.load_error.is_none()) {
+            // Ranges are into the whole content, front matter included
+            let body_start = source.content.len() - source.body().len();
+            for range in links::mentions(source.body(), &note.title) {
+                let snippet = Snippet::around(source.body(), range.clone());
+                let range = body_start + range.start..body_start + range.end;
+                mentions.push(Mention { source: source.id, range, snippet });
+            }
+        }
+        mentions
+    }
+
+    /// Turns a mention into a link to the note it mentions, leaving the
+    /// note it is in unsaved. Mentions whose text has changed since they
+    /// were found are left alone.
+    pub fn link_mention(&mut self, mention: &Mention, title: &str) -> Result<()> {
+        let note = self.get_mut(mention.source).ok_or(StoreError::NoSuchNote(mention.source))?;
+        let Some(text) = note.content.get(mention.range.clone()) else {
+            return Ok(());
+        };
+        if !links::targets(text, title) {
+            return Ok(());
+        }
+        let link = if text == title { format!("[[{}]]", title) } else { format!("[[{}|{}]]", title, text) };
+        let mut content = note.content.clone();
+        content.replace_range(mention.range.clone(), &link);
+        note.set_content(content);
+        Ok(())
+    }
+
+    /// What replacing every match of `finder` with `replacement` would
+    /// change in each note's body, for the notes it finds anything in.
+    /// Notes that could not be loaded are left out, as they are never saved.
+    pub fn replacements(&self, finder: &Finder, replacement: &str) -> Vec<NoteChanges> {
+        self.notes
+            .iter()
+            .filter(|note| note.load_error.is_none())
+            .filter_map(|note| {
+                let changes = finder.changes(note.body(), replacement);
+                let body = note.body().to_string();
+                (!changes.is_empty()).then_some(NoteChanges { id: note.id, body, changes })
+            })
+            .collect()
+    }
+
+    /// Makes the changes to the note's body, leaving the note unsaved.
+    /// Returns false, changing nothing, if the body is no longer the one
+    /// the changes were worked out from.
+    pub fn apply_changes(&mut self, changes: &NoteChanges) -> Result<bool> {
+        let note = self.get_mut(changes.id).ok_or(StoreError::NoSuchNote(changes.id))?;
+        if note.body() != changes.body {
+            return Ok(false);
+        }
+        note.set_body(find::apply(&changes.body, &changes.changes));
+        Ok(true)
+    }
+
+    /// `path` relative to the notes directory.
+    pub fn relative_path<'a>(&self, path: &'a Path) -> &'a Path {
+        path.strip_prefix(&self.dir).unwrap_or(path)
+    }
+
+    /// Folder of the note relative to the notes directory; empty at the top.
+    pub fn folder_of(&self, note: &Note) -> PathBuf {
+        self.relative_path(&note.path).parent().map(Path::to_path_buf).unwrap_or_default()
+    }
+
+    /// Every folder below the notes directory, relative to it, parents
+    /// before their children.
+    pub fn folders(&self) -> &BTreeSet<PathBuf> {
+        &self.folders
+    }
+
+    /// Notes matching `query`, in the [`search`](crate::search) query
+    /// language, best matches first and otherwise in the given order.
+    pub fn query(&self, query: &str, order: SortOrder) -> Vec<NoteId> {
+        let scores = self.search.search(&Query::parse(query), &self.notes, &self.dir);
+        let mut matches: Vec<&Note> = self.notes.iter().filter(|note| scores.contains_key(&note.id)).collect();
+        match order {
+            SortOrder::Title => {}
+            SortOrder::TitleDescending => matches.reverse(),
+            // Notes never written yet count as the newest
+            SortOrder::Modified => {
+                matches.sort_by_key(|note| std::cmp::Reverse(note.disk_modified.unwrap_or(SystemTime::now())))
+            }
+        }
+        matches.sort_by(|a, b| scores[&b.id].total_cmp(&scores[&a.id]));
+        matches.into_iter().map(|note| note.id).collect()
+    }
+
+    /// The notes [`query`](Self::query) finds, with where the words
+    /// searched for are in each note's body as it is being edited.
+    pub fn search(&self, query: &str, order: SortOrder) -> Vec<SearchResult> {
+        let words = self.search.words(&Query::parse(query));
+        self.query(query, order)
+            .into_iter()
+            .filter_map(|id| self.get(id))
+            .map(|note| SearchResult { id: note.id, hits: search::hits(note.body(), &words) })
+            .collect()
+    }
+
+    /// The words of every note as last saved.
+    pub fn search_index(&self) -> &SearchIndex {
+        &self.search
+    }
+
+    /// Changes whenever a note is edited, saved, reloaded, added, removed,
+    /// renamed or moved, so that what was worked out from the notes can
+    /// tell it is out of date.
+    pub fn revision(&self) -> u64 {
+        self.search.revision() + self.edits + self.notes.iter().map(|note| note.edits).sum::<u64>()
+    }
+
+    /// How many notes have each tag. A note with a nested tag such as
+    /// `project/alpha` also counts towards `project`.
+    pub fn tag_counts(&self) -> BTreeMap<String, usize> {
+        let mut counts = BTreeMap::new();
+        for note in &self.notes {
+            let tags: BTreeSet<&str> = note.tags.iter().flat_map(|tag| tags::with_parents(tag)).collect();
+            for tag in tags {
+                *counts.entry(tag.to_string()).or_insert(0) += 1;
+            }
+        }
+        counts
+    }
+
+    /// Title used for the next "New Note".
+    pub fn untitled_title(&self) -> String {
+        (self.notes.len() + 1..)
+            .map(|n| format!("Note_{}", n))
+            .find(|title| !self.notes.iter().any(|note| &note.title == title))
+            .expect("some number is free")
+    }
+
+    /// Creates an empty note at the top of the notes directory and returns its id.
+    pub fn create(&mut self, title: &str) -> Result<NoteId> {
+        self.create_in(Path::new(""), title)
+    }
+
+    /// Creates an empty note in `folder`, relative to the notes directory,
+    /// and returns its id. A file already named after the title is left
+    /// alone; the note gets a numbered file name instead.
+    pub fn create_in(&mut self, folder: &Path, title: &str) -> Result<NoteId> {
+        let path = self.unique_note_path(folder, &slugify(title), None);
+        let content = title::set_in_content("", title, &title::from_path(&path));
+        self.backend
+            .write(&path, &content)
+            .map_err(|source| StoreError::Write { path: path.clone(), source })?;
+        let id = self.allocate_id();
+        let mut note = Note::new(id, content, path);
+        note.disk_modified = self.backend.modified(&note.path).ok();
+        self.search.update(id, &note.title, &note.disk_content);
+        self.notes.push(note);
+        self.sort();
+        self.write_id_index();
+        Ok(id)
+    }
+
+    /// Changes the note's title and renames its file to match, numbering
+    /// the file name if another note already has it. Where the note keeps
+    /// its title, it is updated, which leaves the note unsaved. Links to the
+    /// old title in other notes are pointed at the new one, which leaves
+    /// those notes unsaved too.
+    pub fn rename(&mut self, id: NoteId, new_title: &str) -> Result<()> {
+        let idx = self.index_of(id).ok_or(StoreError::NoSuchNote(id))?;
+        let new_title = new_title.trim();
+        if new_title.is_empty() || self.notes[idx].title == new_title {
+            return Ok(());
+        }
+        let old_title = self.notes[idx].title.clone();
+        let old_path = self.notes[idx].path.clone();
+        let folder = self.folder_of(&self.notes[idx]);
+        let new_path = self.unique_note_path(&folder, &slugify(new_title), Some(&old_path));
+
+        if new_path != old_path {
+            self.backend.rename(&old_path, &new_path).map_err(|source| StoreError::Rename {
+                from: old_path.clone(),
+                to: new_path.clone(),
+                source,
+            })?;
+            self.move_swap_file(&old_path, &new_path);
+        }
+        let note = &mut self.notes[idx];
+        note.path = new_path;
+        note.disk_modified = self.backend.modified(&note.path).ok();
+        let content = title::set_in_content(&note.content, new_title, &title::from_path(&note.path));
+        note.set_content(content);
+        note.refresh_metadata();
+        self.search.update(id, &note.title, &note.disk_content);
+
+        // Links stay with the old title while another note still has it
+        if !self.notes.iter().any(|note| note.id != id && links::targets(&old_title, &note.title)) {
+            for note in &mut self.notes {
+                if let Some(content) = links::retarget(&note.content, &old_title, new_title) {
+                    note.set_content(content);
+                }
+            }
+        }
+
+        self.sort();
+        self.write_id_index();
+        Ok(())
+    }
+
+    /// Moves the note's file into `folder`, relative to the notes directory.
+    pub fn move_note(&mut self, id: NoteId, folder: &Path) -> Result<()> {
+        let idx = self.index_of(id).ok_or(StoreError::NoSuchNote(id))?;
+        let old_path = self.notes[idx].path.clone();
+        let new_path = self.dir.join(folder).join(old_path.file_name().unwrap_or_default());
+        if new_path == old_path {
+            return Ok(());
+        }
+        if self.backend.exists(&new_path) {
+            return Err(StoreError::AlreadyExists(new_path));
+        }
+        self.create_folder_dirs(folder)?;
+        self.backend.rename(&old_path, &new_path).map_err(|source| StoreError::Rename {
+            from: old_path.clone(),
+            to: new_path.clone(),
+            source,
+        })?;
+        let note = &mut self.notes[idx];
+        note.path = new_path.clone();
+        note.disk_modified = self.backend.modified(&note.path).ok();
+        self.edits += 1;
+        self.move_swap_file(&old_path, &new_path);
+        self.write_id_index();
+        Ok(())
+    }
+
+    /// Creates a folder named `name` inside `parent` and returns its path
+    /// relative to the notes directory.
+    pub fn create_folder(&mut self, parent: &Path, name: &str) -> Result<PathBuf> {
+        let folder = parent.join(slugify(name));
+        if self.backend.exists(&self.dir.join(&folder)) {
+            return Err(StoreError::AlreadyExists(self.dir.join(&folder)));
+        }
+        self.create_folder_dirs(&folder)?;
+        Ok(folder)
+    }
+
+    /// Renames a folder, taking the notes and folders inside it along, and
+    /// returns its new path relative to the notes directory.
+    pub fn rename_folder(&mut self, folder: &Path, new_name: &str) -> Result<PathBuf> {
+        let renamed = folder.with_file_name(slugify(new_name));
+        if renamed == folder || new_name.is_empty() {
+            return Ok(folder.to_path_buf());
+        }
+        let (from, to) = (self.dir.join(folder), self.dir.join(&renamed));
+        if self.backend.exists(&to) {
+            return Err(StoreError::AlreadyExists(to));
+        }
+        self.backend.rename(&from, &to).map_err(|source| StoreError::Rename {
+            from: from.clone(),
+            to: to.clone(),
+            source,
+        })?;
+
+        for note in self.notes.iter_mut().filter(|note| note.path.starts_with(&from)) {
+            note.path = rebase(&note.path, &from, &to);
+        }
+        self.edits += 1;
+        self.folders = self
+            .folders
+            .iter()
+            .map(|path| if path.starts_with(folder) { rebase(path, folder, &renamed) } else { path.clone() })
+            .collect();
+        // Best effort: unsaved edits keep their copies under the new name
+        let swap_dir = self.dir.join(SWAP_DIR);
+        let (old_swap, new_swap) = (swap_dir.join(folder), swap_dir.join(&renamed));
+        if self.backend.exists(&old_swap) {
+            if let Some(parent) = new_swap.parent() {
+                let _ = self.backend.create_dir_all(parent);
+            }
+            let _ = self.backend.rename(&old_swap, &new_swap);
+        }
+        self.write_id_index();
+        Ok(renamed)
+    }
+
+    /// Moves every note inside the folder to the trash, then removes the
+    /// folder. Other files inside keep it from being removed.
+    pub fn delete_folder(&mut self, folder: &Path) -> Result<()> {
+        let root = self.dir.join(folder);
+        let inside: Vec<NoteId> =
+            self.notes.iter().filter(|note| note.path.starts_with(&root)).map(|note| note.id).collect();
+        for id in inside {
+            self.delete(id)?;
+        }
+        // Children before their parents
+        let mut folders: Vec<PathBuf> = self.folders.iter().filter(|path| path.starts_with(folder)).cloned().collect();
+        folders.sort_by_key(|path| std::cmp::Reverse(path.components().count()));
+        for path in folders {
+            let dir = self.dir.join(&path);
+            match self.backend.remove_dir(&dir) {
+                Err(source) if source.kind() != io::ErrorKind::NotFound => {
+                    return Err(StoreError::Delete { path: dir, source });
+                }
+                _ => {}
+            }
+            self.folders.remove(&path);
+        }
+        Ok(())
+    }
+
+    /// Moves the note's file to the trash folder and removes the note from
+    /// the store. [`NoteStore::restore_from_trash`] brings it back.
+    pub fn delete(&mut self, id: NoteId) -> Result<Note> {
+        let idx = self.index_of(id).ok_or(StoreError::NoSuchNote(id))?;
+        let trash_dir = self.dir.join(TRASH_DIR);
+        self.backend
+            .create_dir_all(&trash_dir)
+            .map_err(|source| StoreError::CreateDir { dir: trash_dir.clone(), source })?;
+
+        let note = &self.notes[idx];
+        let file = format!("{}-{}", note.id, note.path.file_name().unwrap_or_default().to_string_lossy());
+        let trashed = trash_dir.join(&file);
+        match self.backend.rename(&note.path, &trashed) {
+            Ok(()) => {}
+            // A file that is already gone still leaves the editor content to keep
+            Err(e) if e.kind() == io::ErrorKind::NotFound => {}
+            Err(source) => {
+                return Err(StoreError::Rename { from: note.path.clone(), to: trashed, source });
+            }
+        }
+        // What is in the editor is what the user expects to get back
+        if (note.unsaved_changes || !self.backend.exists(&trashed)) && note.load_error.is_none() {
+            self.backend
+                .write(&trashed, &note.content)
+                .map_err(|source| StoreError::Write { path: trashed.clone(), source })?;
+        }
+
+        let mut entries = trash::read_index(self.backend.as_ref(), &self.dir);
+        entries.push(TrashEntry {
+            file,
+            id,
+            title: note.title.clone(),
+            original: note.path.strip_prefix(&self.dir).unwrap_or(&note.path).to_path_buf(),
+            deleted_at: SystemTime::now(),
+        });
+        if let Err(source) = trash::write_index(self.backend.as_mut(), &self.dir, &entries) {
+            // Without its entry the note could not be restored, so put it back
+            let _ = self.backend.rename(&trashed, &self.notes[idx].path);
+            return Err(StoreError::Write { path: trash::index_path(&self.dir), source });
+        }
+        let note = self.notes.remove(idx);
+        self.edits += note.edits;
+        self.links.remove(id);
+        self.search.remove(id);
+        self.remove_swap_file(&note.path);
+        self.write_id_index();
+        Ok(note)
+    }
+
+    /// Notes in the trash, most recently deleted first.
+    pub fn trash(&self) -> Vec<TrashEntry> {
+        let trash_dir = self.dir.join(TRASH_DIR);
+        let mut entries: Vec<TrashEntry> = trash::read_index(self.backend.as_ref(), &self.dir)
+            .into_iter()
+            .filter(|entry| self.backend.exists(&trash_dir.join(&entry.file)))
+            .map(|mut entry| {
+                let content = self.backend.read(&trash_dir.join(&entry.file)).unwrap_or_default();
+                if let Some(title) = title::from_content(&content) {
+                    entry.title = title;
+                }
+                entry
+            })
+            .collect();
+        entries.reverse();
+        entries
+    }
+
+    /// Moves a note out of the trash to where it was deleted from, under a
+    /// numbered name if that place has been taken since.
+    pub fn restore_from_trash(&mut self, file: &str) -> Result<NoteId> {
+        let mut entries = trash::read_index(self.backend.as_ref(), &self.dir);
+        let pos = entries
+            .iter()
+            .position(|entry| entry.file == file)
+            .ok_or_else(|| StoreError::NotInTrash(file.to_string()))?;
+        let entry = entries.remove(pos);
+
+        let folder = entry.original.parent().unwrap_or(Path::new("")).to_path_buf();
+        let path = self.unique_note_path(&folder, &title::from_path(&entry.original), None);
+        self.create_folder_dirs(&folder)?;
+        let trashed = self.dir.join(TRASH_DIR).join(file);
+        self.backend.rename(&trashed, &path).map_err(|source| StoreError::Rename {
+            from: trashed.clone(),
+            to: path.clone(),
+            source,
+        })?;
+        // Best effort: an entry whose file is gone is not listed
+        let _ = trash::write_index(self.backend.as_mut(), &self.dir, &entries);
+
+        let content = self
+            .backend
+            .read(&path)
+            .map_err(|source| StoreError::Read { path: path.clone(), source })?;
+        // Keeping the id keeps the note's history
+        let id = if self.contains(entry.id) { self.allocate_id() } else { entry.id };
+        let mut note = Note::new(id, content, path);
+        note.disk_modified = self.backend.modified(&note.path).ok();
+        self.links.update(id, &note.disk_content);
+        self.search.update(id, &note.title, &note.disk_content);
+        self.notes.push(note);
+        self.sort();
+        self.write_id_index();
+        Ok(id)
+    }
+
+    /// Deletes a note in the trash for good, along with its history.
+    pub fn purge(&mut self, file: &str) -> Result<()> {
+        let mut entries = trash::read_index(self.backend.as_ref(), &self.dir);
+        let pos = entries
+            .iter()
+            .position(|entry| entry.file == file)
+            .ok_or_else(|| StoreError::NotInTrash(file.to_string()))?;
+        let trashed = self.dir.join(TRASH_DIR).join(file);
+        match self.backend.remove(&trashed) {
+            Err(source) if source.kind() != io::ErrorKind::NotFound => {
+                return Err(StoreError::Delete { path: trashed, source });
+            }
+            _ => {}
+        }
+        let entry = entries.remove(pos);
+        // Best effort: leftovers are only clutter
+        let _ = history::remove_all(self.backend.as_mut(), &history::note_dir(&self.dir, entry.id));
+        trash::write_index(self.backend.as_mut(), &self.dir, &entries)
+            .map_err(|source| StoreError::Write { path: trash::index_path(&self.dir), source })
+    }
+
+    /// Purges the notes deleted more than `age` ago and returns how many.
+    pub fn purge_older_than(&mut self, age: Duration) -> Result<usize> {
+        let expired: Vec<String> = trash::read_index(self.backend.as_ref(), &self.dir)
+            .into_iter()
+            .filter(|entry| entry.deleted_at.elapsed().is_ok_and(|since| since > age))
+            .map(|entry| entry.file)
+            .collect();
+        for file in &expired {
+            self.purge(file)?;
+        }
+        Ok(expired.len())
+    }
+
+    /// Writes the note if it has unsaved changes, unless its file was
+    /// changed by someone else since it was loaded.
+    pub fn save(&mut self, id: NoteId) -> Result<SaveOutcome> {
+        let idx = self.index_of(id).ok_or(StoreError::NoSuchNote(id))?;
+        if !self.notes[idx].unsaved_changes {
+            return Ok(SaveOutcome::Unchanged);
+        }
+        if self.notes[idx].load_error.is_some() {
+            return Err(StoreError::NotLoaded(id));
+        }
+        if self.diverged_from_disk(idx) {
+            return Ok(SaveOutcome::Conflict);
+        }
+        self.write_note(idx)?;
+        Ok(SaveOutcome::Saved)
+    }
+
+    /// Writes the note even if its file changed on disk ("keep mine").
+    pub fn overwrite(&mut self, id: NoteId) -> Result<()> {
+        let idx = self.index_of(id).ok_or(StoreError::NoSuchNote(id))?;
+        if self.notes[idx].load_error.is_some() {
+            return Err(StoreError::NotLoaded(id));
+        }
+        self.write_note(idx)
+    }
+
+    /// Drops local edits and loads the file as it is now ("keep theirs").
+    /// Also retries a note whose first load failed.
+    pub fn reload(&mut self, id: NoteId) -> Result<()> {
+        let idx = self.index_of(id).ok_or(StoreError::NoSuchNote(id))?;
+        let note = &mut self.notes[idx];
+        let content = self
+            .backend
+            .read(&note.path)
+            .map_err(|source| StoreError::Read { path: note.path.clone(), source })?;
+        note.disk_modified = self.backend.modified(&note.path).ok();
+        note.content = content.clone();
+        note.disk_content = content;
+        note.refresh_metadata();
+        note.unsaved_changes = false;
+        note.load_error = None;
+        self.links.update(id, &note.disk_content);
+        self.search.update(id, &note.title, &note.disk_content);
+        Ok(())
+    }
+
+    /// Current content of the note's file, which may differ from both the
+    /// editor and what was loaded.
+    pub fn read_disk(&self, id: NoteId) -> Result<String> {
+        let note = self.get(id).ok_or(StoreError::NoSuchNote(id))?;
+        self.backend
+            .read(&note.path)
+            .map_err(|source| StoreError::Read { path: note.path.clone(), source })
+    }
+
+    /// Moves the note's local edits into a new note and reloads the
+    /// original from disk. Returns the copy's id.
+    pub fn save_as_copy(&mut self, id: NoteId) -> Result<NoteId> {
+        let note = self.get(id).ok_or(StoreError::NoSuchNote(id))?;
+        let content = note.content.clone();
+        let folder = self.folder_of(note);
+        let title = format!("{} (conflict copy)", note.title);
+
+        let copy = self.create_in(&folder, &title)?;
+        if let Some(note) = self.get_mut(copy) {
+            let content = title::set_in_content(&content, &title, &title::from_path(&note.path));
+            note.set_content(content);
+        }
+        self.overwrite(copy)?;
+        self.reload(id)?;
+        Ok(copy)
+    }
+
+    /// Renders the note to HTML next to its Markdown file and returns the
+    /// exported file's path.
+    pub fn export_html(&mut self, id: NoteId) -> Result<PathBuf> {
+        let note = self.get(id).ok_or(StoreError::NoSuchNote(id))?;
+        let html = markdown::to_html_page(&note.title, &markdown::parse(note.body()));
+        let path = note.path.with_extension("html");
+        self.backend
+            .write(&path, &html)
+            .map_err(|source| StoreError::Write { path: path.clone(), source })?;
+        Ok(path)
+    }
+
+    /// How many saved versions of each note to keep from now on.
+    pub fn set_retention(&mut self, retention: Retention) {
+        self.retention = retention;
+    }
+
+    /// Saved versions of a note, newest first.
+    pub fn history(&self, id: NoteId) -> Result<Vec<Version>> {
+        let dir = history::note_dir(&self.dir, id);
+        history::versions(self.backend.as_ref(), &dir).map_err(|source| StoreError::List { dir, source })
+    }
+
+    pub fn read_version(&self, version: &Version) -> Result<String> {
+        self.backend
+            .read(&version.path)
+            .map_err(|source| StoreError::Read { path: version.path.clone(), source })
+    }
+
+    /// Copies the content of every note with unsaved changes into the swap
+    /// folder, so the edits outlive an exit without saving. Saving a note
+    /// removes its copy. Returns how many notes were copied.
+    pub fn write_swap_files(&mut self) -> Result<usize> {
+        let dirty: Vec<usize> = (0..self.notes.len()).filter(|&i| self.notes[i].unsaved_changes).collect();
+        for &idx in &dirty {
+            self.write_swap(idx)?;
+        }
+        Ok(dirty.len())
+    }
+
+    /// Copies notes whose edits are at least `delay` old into the swap
+    /// folder, so a crash loses no more than `delay` of typing. Returns how
+    /// many notes were copied.
+    pub fn write_journal(&mut self, delay: Duration) -> Result<usize> {
+        let due: Vec<usize> = (0..self.notes.len())
+            .filter(|&i| self.notes[i].unsaved_changes)
+            .filter(|&i| self.notes[i].unjournaled_since.is_some_and(|since| since.elapsed() >= delay))
+            .collect();
+        for &idx in &due {
+            if let Err(e) = self.write_swap(idx) {
+                // Try again after another delay rather than every frame
+                self.notes[idx].unjournaled_since = Some(Instant::now());
+                return Err(e);
+            }
+        }
+        Ok(due.len())
+    }
+
+    /// Swap copies that are newer than their note's file and differ from
+    /// it, typically left by a crash. Stale copies are removed.
+    pub fn recoverable(&mut self) -> Vec<Recovery> {
+        let mut found = vec![];
+        // Copies of notes that are gone are left for the user to find
+        for note in &self.notes {
+            let path = swap_path(&self.dir, &note.path);
+            if !self.backend.exists(&path) {
+                continue;
+            }
+            let (Ok(saved_at), Ok(content)) = (self.backend.modified(&path), self.backend.read(&path)) else {
+                continue;
+            };
+            let newer = note.disk_modified.is_none_or(|modified| saved_at > modified);
+            if newer && content != note.content {
+                found.push(Recovery { id: note.id, content, saved_at });
+            } else {
+                let _ = self.backend.remove(&path);
+            }
+        }
+        found
+    }
+
+    /// Removes the swap copy of a note, once its recovery was declined.
+    pub fn discard_swap(&mut self, id: NoteId) {
+        if let Some(path) = self.get(id).map(|note| note.path.clone()) {
+            self.remove_swap_file(&path);
+        }
+    }
+
+    /// Saves every dirty note that was last saved at least `interval` ago.
+    pub fn autosave(&mut self, interval: Duration) -> AutosaveReport {
+        let now = Instant::now();
+        let mut report = AutosaveReport::default();
+        for idx in 0..self.notes.len() {
+            let note = &self.notes[idx];
+            if !note.unsaved_changes || now.duration_since(note.last_saved) < interval {
+                continue;
+            }
+            if note.load_error.is_some() {
+                let id = note.id;
+                self.notes[idx].last_saved = now;
+                report.failed.push((id, StoreError::NotLoaded(id)));
+            } else if self.diverged_from_disk(idx) {
+                // Try again after another interval rather than every frame
+                self.notes[idx].last_saved = now;
+                report.conflicts.push(self.notes[idx].id);
+            } else if let Err(e) = self.write_note(idx) {
+                self.notes[idx].last_saved = now;
+                report.failed.push((self.notes[idx].id, e));
+            }
+        }
+        report
+    }
+
+    /// Picks up notes that other programs created, changed, renamed or
+    /// deleted since the store last looked. Notes with unsaved edits are
+    /// never overwritten or dropped.
+    pub fn sync_with_disk(&mut self) -> Result<Vec<DiskChange>> {
+        let (files, folders) = scan(self.backend.as_ref(), &self.dir)?;
+        self.folders = folders;
+        let on_disk: HashSet<PathBuf> = files.into_iter().filter(|path| is_note_file(path)).collect();
+        let mut changes = vec![];
+
+        let mut vanished: Vec<usize> = (0..self.notes.len())
+            .filter(|&i| self.notes[i].disk_modified.is_some() && !on_disk.contains(&self.notes[i].path))
+            .collect();
+        let mut appeared: Vec<&PathBuf> = on_disk
+            .iter()
+            .filter(|path| !self.notes.iter().any(|note| &note.path == *path))
+            .collect();
+        appeared.sort();
+
+        for path in appeared {
+            let Ok(content) = self.backend.read(path) else {
+                continue;
+            };
+            let modified = self.backend.modified(path).ok();
+            // A vanished note whose content shows up under a new name was renamed
+            if let Some(pos) = vanished.iter().position(|&i| self.notes[i].disk_content == content) {
+                let note = &mut self.notes[vanished.remove(pos)];
+                note.path = path.clone();
+                note.disk_modified = modified;
+                note.refresh_metadata();
+                self.search.update(note.id, &note.title, &note.disk_content);
+                changes.push(DiskChange::Renamed(note.id));
+            } else {
+                let id = self.allocate_id();
+                let mut note = Note::new(id, content, path.clone());
+                note.disk_modified = modified;
+                self.links.update(id, &note.disk_content);
+                self.search.update(id, &note.title, &note.disk_content);
+                self.notes.push(note);
+                changes.push(DiskChange::Added(id));
+            }
+        }
+
+        vanished.sort_unstable_by(|a, b| b.cmp(a));
+        for i in vanished {
+            if self.notes[i].unsaved_changes {
+                // Saving will write the file again
+                self.notes[i].disk_modified = None;
+                changes.push(DiskChange::Diverged(self.notes[i].id));
+            } else {
+                self.links.remove(self.notes[i].id);
+                self.search.remove(self.notes[i].id);
+                let note = self.notes.remove(i);
+                self.edits += note.edits;
+                changes.push(DiskChange::Removed(Box::new(note)));
+            }
+        }
+
+        for note in self.notes.iter_mut() {
+            let Ok(modified) = self.backend.modified(&note.path) else {
+                continue;
+            };
+            if note.disk_modified == Some(modified) && note.load_error.is_none() {
+                continue;
+            }
+            let Ok(content) = self.backend.read(&note.path) else {
+                continue;
+            };
+            note.disk_modified = Some(modified);
+            if note.load_error.take().is_some() {
+                if note.unsaved_changes {
+                    // What was typed in place of the unreadable file stays;
+                    // saving finds the file differs from the empty content
+                    // the edits started from
+                    changes.push(DiskChange::Diverged(note.id));
+                    continue;
+                }
+                note.content = content.clone();
+                note.disk_content = content;
+                note.refresh_metadata();
+                note.unsaved_changes = false;
+                self.links.update(note.id, &note.disk_content);
+                self.search.update(note.id, &note.title, &note.disk_content);
+                changes.push(DiskChange::Reloaded(note.id));
+                continue;
+            }
+            if content == note.disk_content {
+                continue;
+            }
+            if note.unsaved_changes {
+                changes.push(DiskChange::Diverged(note.id));
+            } else {
+                note.content = content.clone();
+                note.disk_content = content;
+                note.refresh_metadata();
+                self.links.update(note.id, &note.disk_content);
+                self.search.update(note.id, &note.title, &note.disk_content);
+                changes.push(DiskChange::Reloaded(note.id));
+            }
+        }
+
+        if !changes.is_empty() {
+            self.sort();
+            self.write_id_index();
+        }
+        Ok(changes)
+    }
+
+    /// Whether the note's file was changed by someone else since the note
+    /// was last read or written. Only the content can tell: syncing takes
+    /// in a newer modification time without the change itself when the
+    /// note has unsaved edits.
+    fn diverged_from_disk(&self, idx: usize) -> bool {
+        let note = &self.notes[idx];
+        // A missing file is simply written again
+        self.backend
+            .read(&note.path)
+            .is_ok_and(|content| content != note.disk_content)
+    }
+
+    fn write_note(&mut self, idx: usize) -> Result<()> {
+        // The first save of a note also keeps what the file held before
+        let history_dir = history::note_dir(&self.dir, self.notes[idx].id);
+        let has_history = history::versions(self.backend.as_ref(), &history_dir).is_ok_and(|v| !v.is_empty());
+        let note = &mut self.notes[idx];
+        let original = (!has_history && !note.disk_content.is_empty())
+            .then(|| (note.disk_content.clone(), note.disk_modified.unwrap_or(SystemTime::now())));
+        self.backend
+            .write(&note.path, &note.content)
+            .map_err(|source| StoreError::Write { path: note.path.clone(), source })?;
+        note.unsaved_changes = false;
+        note.last_saved = Instant::now();
+        note.disk_content = note.content.clone();
+        note.disk_modified = self.backend.modified(&note.path).ok();
+        note.unjournaled_since = None;
+        self.links.update(note.id, &note.disk_content);
+        self.search.update(note.id, &note.title, &note.disk_content);
+        let path = note.path.clone();
+        self.remove_swap_file(&path);
+        self.record_version(idx, original);
+        Ok(())
+    }
+
+    // Best effort: the note is saved even if its history cannot be written
+    fn record_version(&mut self, idx: usize, original: Option<(String, SystemTime)>) {
+        let note = &self.notes[idx];
+        let dir = history::note_dir(&self.dir, note.id);
+        let backend = self.backend.as_mut();
+        if let Some((content, modified)) = original {
+            let _ = history::snapshot(backend, &dir, &content, modified);
+        }
+        let _ = history::snapshot(backend, &dir, &note.content, SystemTime::now());
+        let _ = history::prune(backend, &dir, self.retention);
+    }
+
+    fn write_swap(&mut self, idx: usize) -> Result<()> {
+        let swap_dir = self.dir.join(SWAP_DIR);
+        self.backend
+            .create_dir_all(&swap_dir)
+            .map_err(|source| StoreError::CreateDir { dir: swap_dir.clone(), source })?;
+        let note = &mut self.notes[idx];
+        let path = swap_path(&self.dir, &note.path);
+        if let Some(parent) = path.parent() {
+            self.backend
+                .create_dir_all(parent)
+                .map_err(|source| StoreError::CreateDir { dir: parent.to_path_buf(), source })?;
+        }
+        self.backend
+            .write(&path, &note.content)
+            .map_err(|source| StoreError::Write { path: path.clone(), source })?;
+        note.unjournaled_since = None;
+        Ok(())
+    }
+
+    // Best effort: a stale copy is only clutter, the note itself is safe
+    fn remove_swap_file(&mut self, note_path: &Path) {
+        let path = swap_path(&self.dir, note_path);
+        if self.backend.exists(&path) {
+            let _ = self.backend.remove(&path);
+        }
+    }
+
+    // Best effort: unsaved edits keep their copy under the note's new path
+    fn move_swap_file(&mut self, old_path: &Path, new_path: &Path) {
+        let (old_swap, new_swap) = (swap_path(&self.dir, old_path), swap_path(&self.dir, new_path));
+        if self.backend.exists(&old_swap) {
+            if let Some(parent) = new_swap.parent() {
+                let _ = self.backend.create_dir_all(parent);
+            }
+            let _ = self.backend.rename(&old_swap, &new_swap);
+        }
+    }
+
+    // Creates `folder` and its parents, relative to the notes directory
+    fn create_folder_dirs(&mut self, folder: &Path) -> Result<()> {
+        let dir = self.dir.join(folder);
+        self.backend
+            .create_dir_all(&dir)
+            .map_err(|source| StoreError::CreateDir { dir, source })?;
+        for ancestor in folder.ancestors().filter(|path| !path.as_os_str().is_empty()) {
+            self.folders.insert(ancestor.to_path_buf());
+        }
+        Ok(())
+    }
+
+    // `stem.md` in `folder`, or `stem 2.md` and so on if that is taken by
+    // anything but `own_path`
+    fn unique_note_path(&self, folder: &Path, stem: &str, own_path: Option<&Path>) -> PathBuf {
+        let dir = self.dir.join(folder);
+        let mut path = dir.join(format!("{}.md", stem));
+        let mut n = 1;
+        while self.backend.exists(&path) && Some(path.as_path()) != own_path {
+            n += 1;
+            path = dir.join(format!("{} {}.md", stem, n));
+        }
+        path
+    }
+
+    fn index_of(&self, id: NoteId) -> Option<usize> {
+        self.notes.iter().position(|note| note.id == id)
+    }
+
+    fn sort(&mut self) {
+        self.notes.sort_by_key(|note| note.title.to_lowercase());
+    }
+
+    fn allocate_id(&mut self) -> NoteId {
+        self.next_id += 1;
+        NoteId(self.next_id - 1)
+    }
+
+    fn write_id_index(&mut self) {
+        let mut entries: Vec<_> = self
+            .notes
+            .iter()
+            .map(|note| (note.id, note.path.strip_prefix(&self.dir).unwrap_or(&note.path).to_string_lossy()))
+            .collect();
+        entries.sort();
+        // Ids of deleted notes are never handed out again, so their history
+        // cannot end up attached to a new note
+        let mut contents = format!("next\t{}\n", self.next_id);
+        for (id, name) in &entries {
+            contents.push_str(&format!("{}\t{}\n", id, name));
+        }
+        // Best effort: without the index the next open hands out fresh ids,
+        // which forgets restored tabs and detaches notes from their history.
+        let _ = self.backend.write(&self.dir.join(ID_INDEX_FILE), &contents);
+    }
+}
+
+/// Reads the id index: a map from each path, relative to the notes
+/// directory, to its note's id, and the lowest id never handed out.
+fn read_id_index(backend: &dyn Backend, dir: &Path) -> (HashMap<String, NoteId>, u64) {
+    let contents = backend.read(&dir.join(ID_INDEX_FILE)).unwrap_or_default();
+    let mut ids = HashMap::new();
+    let mut next_free = 1;
+    for line in contents.lines() {
+        let Some((key, value)) = line.split_once('\t') else {
+            continue;
+        };
+        if key == "next" {
+            next_free = value.parse().unwrap_or(next_free);
+        } else if let Ok(id) = key.parse() {
+            ids.insert(value.to_string(), NoteId(id));
+        }
+    }
+    (ids, next_free)
+}
+
+fn swap_path(dir: &Path, note_path: &Path) -> PathBuf {
+    dir.join(SWAP_DIR).join(note_path.strip_prefix(dir).unwrap_or(note_path))
+}
+
+/// `path`, which is inside `from`, moved along with `from` to `to`.
+fn rebase(path: &Path, from: &Path, to: &Path) -> PathBuf {
+    match path.strip_prefix(from) {
+        Ok(rest) if !rest.as_os_str().is_empty() => to.join(rest),
+        _ => to.to_path_buf(),
+    }
+}
+
+/// Files anywhere below `dir` and the folders holding them, relative to
+/// `dir`. Hidden folders such as the trash are left out.
+fn scan(backend: &dyn Backend, dir: &Path) -> Result<(Vec<PathBuf>, BTreeSet<PathBuf>)> {
+    let mut files = vec![];
+    let mut folders = BTreeSet::new();
+    let mut pending = vec![dir.to_path_buf()];
+    while let Some(current) = pending.pop() {
+        let list_error = |source| StoreError::List { dir: current.clone(), source };
+        files.extend(backend.list(&current).map_err(list_error)?);
+        for sub in backend.list_dirs(&current).map_err(list_error)? {
+            if sub.file_name().is_some_and(|name| name.to_string_lossy().starts_with('.')) {
+                continue;
+            }
+            folders.insert(sub.strip_prefix(dir).unwrap_or(&sub).to_path_buf());
+            pending.push(sub);
+        }
+    }
+    Ok((files, folders))
+}
+
+fn is_note_file(path: &Path) -> bool {
+    path.extension().is_some_and(|e| e == "md")
+}
+
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+
+    // A store on files a test can still reach through the returned handle
+    fn store() -> (NoteStore, MemoryBackend) {
+        let backend = MemoryBackend::new();
+        let store = NoteStore::with_backend("notes", Box::new(backend.clone())).unwrap();
+        (store, backend)
+    }
+
+    /// A store with a saved note for each `(title, body)`, and their ids
+    /// in the order given.
+    pub(crate) fn store_with(notes: &[(&str, &str)]) -> (NoteStore, Vec<NoteId>) {
+        let (mut store, _) = store();
+        let ids = notes
+            .iter()
+            .map(|(title, body)| {
+                let id = store.create(title).unwrap();
+                store.get_mut(id).unwrap().set_body(body.to_string());
+                store.save(id).unwrap();
+                id
+            })
+            .collect();
+        (store, ids)
+    }
+
+    fn edit(store: &mut NoteStore, id: NoteId, content: &str) {
+        store.get_mut(id).unwrap().set_content(content.to_string());
+    }
+
+    #[test]
+    fn create_writes_the_file() {
+        let (mut store, backend) = store();
+        let id = store.create("Shopping List").unwrap();
+        let note = store.get(id).unwrap();
+        assert_eq!(note.title, "Shopping List");
+        assert_eq!(note.path, Path::new("notes/Shopping List.md"));
+        assert!(!note.unsaved_changes);
+        assert_eq!(backend.read(&note.path).unwrap(), note.content);
+    }
+
+    #[test]
+    fn create_numbers_a_taken_file_name() {
+        let (mut store, _) = store();
+        let first = store.create("Idea").unwrap();
+        let second = store.create("Idea").unwrap();
+        assert_ne!(store.get(first).unwrap().path, store.get(second).unwrap().path);
+    }
+
+    #[test]
+    fn save_writes_only_dirty_notes() {
+        let (mut store, backend) = store();
+        let id = store.create("Plan").unwrap();
+        assert!(matches!(store.save(id).unwrap(), SaveOutcome::Unchanged));
+
+        edit(&mut store, id, "# Plan\nStep one");
+        assert!(store.get(id).unwrap().unsaved_changes);
+        assert!(matches!(store.save(id).unwrap(), SaveOutcome::Saved));
+        let note = store.get(id).unwrap();
+        assert!(!note.unsaved_changes);
+        assert_eq!(backend.read(&note.path).unwrap(), "# Plan\nStep one");
+    }
+
+    #[test]
+    fn save_refuses_to_overwrite_an_outside_change() {
+        let (mut store, backend) = store();
+        let id = store.create("Plan").unwrap();
+        let path = store.get(id).unwrap().path.clone();
+        backend.insert(&path, "# Plan\nTheirs");
+        edit(&mut store, id, "# Plan\nOurs");
+        assert!(matches!(store.save(id).unwrap(), SaveOutcome::Conflict));
+        assert_eq!(backend.read(&path).unwrap(), "# Plan\nTheirs");
+
+        store.overwrite(id).unwrap();
+        assert_eq!(backend.read(&path).unwrap(), "# Plan\nOurs");
+    }
+
+    #[test]
+    fn rename_moves_the_file_and_updates_the_title() {
+        let (mut store, backend) = store();
+        let id = store.create("Draft").unwrap();
+        let old_path = store.get(id).unwrap().path.clone();
+        store.rename(id, "Final").unwrap();
+
+        let note = store.get(id).unwrap();
+        assert_eq!(note.title, "Final");
+        assert_eq!(note.path, Path::new("notes/Final.md"));
+        assert!(!backend.exists(&old_path));
+        assert!(backend.exists(&note.path));
+    }
+
+    #[test]
+    fn delete_moves_the_note_to_the_trash_and_back() {
+        let (mut store, backend) = store();
+        let id = store.create("Old").unwrap();
+        let path = store.get(id).unwrap().path.clone();
+        store.delete(id).unwrap();
+        assert!(store.get(id).is_none());
+        assert!(!backend.exists(&path));
+
+        let trash = store.trash();
+        assert_eq!(trash.len(), 1);
+        assert_eq!(trash[0].title, "Old");
+        let restored = store.restore_from_trash(&trash[0].file).unwrap();
+        assert_eq!(restored, id);
+        assert_eq!(store.get(id).unwrap().path, path);
+        assert!(store.trash().is_empty());
+    }
+
+    #[test]
+    fn reload_drops_local_edits() {
+        let (mut store, backend) = store();
+        let id = store.create("Log").unwrap();
+        let path = store.get(id).unwrap().path.clone();
+        edit(&mut store, id, "# Log\nmine");
+        backend.insert(&path, "# Log\ntheirs");
+        store.reload(id).unwrap();
+
+        let note = store.get(id).unwrap();
+        assert_eq!(note.content, "# Log\ntheirs");
+        assert!(!note.unsaved_changes);
+    }
+
+    #[test]
+    fn ids_survive_reopening() {
+        let (mut store, backend) = store();
+        let a = store.create("Alpha").unwrap();
+        let b = store.create("Beta").unwrap();
+        store.rename(b, "Gamma").unwrap();
+        store.save(b).unwrap();
+
+        let reopened = NoteStore::with_backend("notes", Box::new(backend)).unwrap();
+        assert_eq!(reopened.find_by_path(Path::new("notes/Alpha.md")), Some(a));
+        assert_eq!(reopened.find_by_path(Path::new("notes/Gamma.md")), Some(b));
+    }
+
+    #[test]
+    fn sync_picks_up_outside_changes() {
+        let (mut store, mut backend) = store();
+        let changed = store.create("Changed").unwrap();
+        let removed = store.create("Removed").unwrap();
+        let changed_path = store.get(changed).unwrap().path.clone();
+        let removed_path = store.get(removed).unwrap().path.clone();
+
+        backend.insert("notes/added.md", "# Added");
+        backend.insert(&changed_path, "# Changed\nnew text");
+        backend.remove(&removed_path).unwrap();
+        let changes = store.sync_with_disk().unwrap();
+
+        let added = store.find_by_path(Path::new("notes/added.md")).unwrap();
+        assert!(changes.iter().any(|change| matches!(change, DiskChange::Added(id) if *id == added)));
+        assert!(changes.iter().any(|change| matches!(change, DiskChange::Reloaded(id) if *id == changed)));
+        assert!(changes.iter().any(|change| matches!(change, DiskChange::Removed(note) if note.id == removed)));
+        assert_eq!(store.get(changed).unwrap().content, "# Changed\nnew text");
+        assert!(store.get(removed).is_none());
+        assert!(store.sync_with_disk().unwrap().is_empty());
+    }
+
+    #[test]
+    fn sync_keeps_unsaved_edits() {
+        let (mut store, backend) = store();
+        let id = store.create("Busy").unwrap();
+        let path = store.get(id).unwrap().path.clone();
+        edit(&mut store, id, "# Busy\nmine");
+        backend.insert(&path, "# Busy\ntheirs");
+
+        let changes = store.sync_with_disk().unwrap();
+        assert!(matches!(changes.as_slice(), [DiskChange::Diverged(changed)] if *changed == id));
+        assert_eq!(store.get(id).unwrap().content, "# Busy\nmine");
+    }
+
+    #[test]
+    fn save_after_sync_still_refuses_to_overwrite_an_outside_change() {
+        let (mut store, backend) = store();
+        let id = store.create("Busy").unwrap();
+        let path = store.get(id).unwrap().path.clone();
+        edit(&mut store, id, "# Busy\nmine");
+        backend.insert(&path, "# Busy\ntheirs");
+
+        store.sync_with_disk().unwrap();
+        assert!(matches!(store.save(id).unwrap(), SaveOutcome::Conflict));
+        assert_eq!(backend.read(&path).unwrap(), "# Busy\ntheirs");
+        // Syncing again finds nothing new
+        assert!(store.sync_with_disk().unwrap().is_empty());
+    }
+
+    #[test]
+    fn sync_keeps_edits_typed_into_a_note_that_failed_to_load() {
+        let (mut store, backend) = store();
+        let id = store.create("Locked").unwrap();
+        let path = store.get(id).unwrap().path.clone();
+        let note = store.get_mut(id).unwrap();
+        note.load_error = Some("permission denied".to_string());
+        note.disk_content = String::new();
+        edit(&mut store, id, "typed");
+        backend.insert(&path, "# Locked\nreadable now");
+
+        let changes = store.sync_with_disk().unwrap();
+        assert!(matches!(changes.as_slice(), [DiskChange::Diverged(changed)] if *changed == id));
+        assert_eq!(store.get(id).unwrap().content, "typed");
+        assert!(matches!(store.save(id).unwrap(), SaveOutcome::Conflict));
+    }
+
+    #[test]
+    fn sync_follows_a_rename() {
+        let (mut store, mut backend) = store();
+        let id = store.create("Moved").unwrap();
+        let path = store.get(id).unwrap().path.clone();
+        backend.rename(&path, Path::new("notes/elsewhere.md")).unwrap();
+
+        let changes = store.sync_with_disk().unwrap();
+        assert!(matches!(changes.as_slice(), [DiskChange::Renamed(renamed)] if *renamed == id));
+        assert_eq!(store.get(id).unwrap().path, Path::new("notes/elsewhere.md"));
+    }
+
+    #[test]
+    fn revision_counts_edits_saves_and_moves() {
+        let (mut store, _) = store();
+        let id = store.create("Note").unwrap();
+        let mut last = store.revision();
+        let mut changed = |store: &NoteStore| {
+            let changed = store.revision() != last;
+            last = store.revision();
+            changed
+        };
+        edit(&mut store, id, "# Note\ntext");
+        assert!(changed(&store));
+        store.save(id).unwrap();
+        assert!(changed(&store));
+        store.move_note(id, Path::new("folder")).unwrap();
+        assert!(changed(&store));
+        store.search("text", SortOrder::Title);
+        assert!(!changed(&store));
+        // Looking at a note to change, without changing it, is no change
+        store.get_mut(id).unwrap();
+        edit(&mut store, id, "# Note\ntext");
+        assert!(!changed(&store));
+
+        // The edits of a deleted note still count, so the revision never repeats
+        let other = store.create("Other").unwrap();
+        edit(&mut store, other, "# Other\none");
+        edit(&mut store, other, "# Other\ntwo");
+        let before = store.revision();
+        store.delete(other).unwrap();
+        assert!(store.revision() > before);
+    }
+}
@@ -0,0 +1,131 @@
+//! Tags: `#tag` anywhere in a note's text, and the `tags` property of its
+//! front matter. A `/` nests tags, so `#project/alpha` is also tagged
+//! `project`. Tags are compared in lower case.
+
+use crate::metadata::{self, Metadata};
+use std::collections::BTreeSet;
+
+/// Every tag of a note, without the `#`, in lower case.
+pub fn extract(content: &str) -> BTreeSet<String> {
+    let (front_matter, body) = metadata::split(content);
+    let mut tags = BTreeSet::new();
+    if let Some(value) = front_matter.map(Metadata::parse).as_ref().and_then(|m| m.get("tags")) {
+        // `tags: a, b` and `tags: a b` are common besides proper lists
+        for item in value.items() {
+            for tag in item.split([',', ' ']) {
+                if let Some(tag) = normalize(tag.trim_start_matches('#')) {
+                    tags.insert(tag);
+                }
+            }
+        }
+    }
+
+    let mut fence: Option<&str> = None;
+    for line in body.lines() {
+        let trimmed = line.trim_start();
+        if let Some(open) = fence {
+            if trimmed.starts_with(open) {
+                fence = None;
+            }
+            continue;
+        }
+        if let Some(marker) = ["```", "~~~"].into_iter().find(|marker| trimmed.starts_with(marker)) {
+            fence = Some(marker);
+            continue;
+        }
+        tags.extend(inline_tags(line));
+    }
+    tags
+}
+
+/// Whether `tags` has `tag` or a tag nested below it.
+pub fn matches(tags: &BTreeSet<String>, tag: &str) -> bool {
+    let tag = tag.to_lowercase();
+    tags.iter().any(|t| *t == tag || t.strip_prefix(&tag).is_some_and(|rest| rest.starts_with('/')))
+}
+
+/// `tag` and the tags it is nested in, innermost last: `a`, `a/b`, `a/b/c`.
+pub fn with_parents(tag: &str) -> impl Iterator<Item = &str> {
+    tag.match_indices('/').map(move |(i, _)| &tag[..i]).chain(std::iter::once(tag))
+}
+
+// `#tag`s of one line, outside code spans
+fn inline_tags(line: &str) -> Vec<String> {
+    let mut tags = vec![];
+    let mut in_code = false;
+    let mut previous = ' ';
+    for (i, c) in line.char_indices() {
+        if c == '`' {
+            in_code = !in_code;
+        } else if c == '#' && !in_code && (previous.is_whitespace() || "([{,;".contains(previous)) {
+            let rest = &line[i + 1..];
+            let end = rest.find(|c: char| !is_tag_char(c)).unwrap_or(rest.len());
+            if let Some(tag) = normalize(&rest[..end]) {
+                tags.push(tag);
+            }
+        }
+        previous = c;
+    }
+    tags
+}
+
+fn is_tag_char(c: char) -> bool {
+    c.is_alphanumeric() || matches!(c, '_' | '-' | '/')
+}
+
+// Lower case without stray slashes; a tag needs more than digits, so that
+// `#1` in "issue #1" is not one
+fn normalize(tag: &str) -> Option<String> {
+    let tag = tag.trim_matches('/');
+    let valid = !tag.is_empty()
+        && tag.chars().all(is_tag_char)
+        && !tag.contains("//")
+        && tag.chars().any(|c| !c.is_ascii_digit() && c != '/');
+    valid.then(|| tag.to_lowercase())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn tags(content: &str) -> Vec<String> {
+        extract(content).into_iter().collect()
+    }
+
+    #[test]
+    fn inline_tags_in_the_text() {
+        assert_eq!(tags("Plan #Work and (#home), #a/b/"), vec!["a/b", "home", "work"]);
+        // Headings, anchors inside words, numbers and code are not tags
+        assert!(tags("# Heading\nissue #1, page#anchor, `#code`").is_empty());
+        assert_eq!(tags("#2024-review"), vec!["2024-review"]);
+    }
+
+    #[test]
+    fn fenced_code_has_no_tags() {
+        assert_eq!(tags("```\n#not\n```\n#yes\n~~~\n#nor\n"), vec!["yes"]);
+    }
+
+    #[test]
+    fn front_matter_tags() {
+        assert_eq!(tags("---\ntags: [One, '#two']\n---\n#three"), vec!["one", "three", "two"]);
+        assert_eq!(tags("---\ntags: a, b c\n---\n"), vec!["a", "b", "c"]);
+        assert_eq!(tags("---\ntags:\n  - x/y\n---\n"), vec!["x/y"]);
+        assert!(tags("---\ntags: 42\n---\n").is_empty());
+    }
+
+    #[test]
+    fn nested_tags_match_their_parents() {
+        let tags = extract("#project/alpha #home");
+        assert!(matches(&tags, "project"));
+        assert!(matches(&tags, "Project/Alpha"));
+        assert!(matches(&tags, "home"));
+        assert!(!matches(&tags, "proj"));
+        assert!(!matches(&tags, "project/alpha/x"));
+    }
+
+    #[test]
+    fn parents_come_first() {
+        assert_eq!(with_parents("a/b/c").collect::<Vec<_>>(), vec!["a", "a/b", "a/b/c"]);
+        assert_eq!(with_parents("a").collect::<Vec<_>>(), vec!["a"]);
+    }
+}
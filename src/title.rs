@@ -0,0 +1,123 @@
+//! Note titles. A note's title is whatever the user typed; its file name is
+//! only a file-system-safe version of it. When the two differ, the title is
+//! kept in the note itself: a `title` property in the front matter, or a
+//! level 1 heading on the first line.
+
+use crate::metadata::{self, Metadata, Value};
+use std::path::Path;
+
+/// Longest file name, in characters, made from a title.
+const MAX_STEM_CHARS: usize = 120;
+
+/// The title written in the note, if any.
+pub fn from_content(content: &str) -> Option<String> {
+    let (front_matter, body) = metadata::split(content);
+    if let Some(Value::Text(title)) = front_matter.map(Metadata::parse).as_ref().and_then(|m| m.get("title")) {
+        if !title.is_empty() {
+            return Some(title.clone());
+        }
+    }
+    body.lines().find(|line| !line.trim().is_empty()).and_then(heading_text)
+}
+
+/// The title of a note that does not name one: its file name.
+pub fn from_path(path: &Path) -> String {
+    path.file_stem().unwrap_or_default().to_string_lossy().into_owned()
+}
+
+/// File name, without extension, for a title: the title minus what file
+/// systems reject or hide.
+pub fn slugify(title: &str) -> String {
+    let safe: String = title
+        .chars()
+        .map(|c| if c.is_control() || r#"/\:*?"<>|"#.contains(c) { '-' } else { c })
+        .take(MAX_STEM_CHARS)
+        .collect();
+    let safe = safe.trim().trim_start_matches('.').trim_start().trim_end_matches(['.', ' ']);
+    if safe.is_empty() {
+        "Untitled".to_string()
+    } else {
+        safe.to_string()
+    }
+}
+
+/// `content` with its title changed to `title`, for a note whose file name
+/// is `stem`. The title is changed where the note keeps it; a note without
+/// one only gets one if `stem` does not already say it.
+pub fn set_in_content(content: &str, title: &str, stem: &str) -> String {
+    let (front_matter, body) = metadata::split(content);
+    let mut properties = front_matter.map(Metadata::parse).unwrap_or_default();
+    if matches!(properties.get("title"), Some(Value::Text(_))) {
+        properties.set("title", Value::Text(title.to_string()));
+        return metadata::join(&properties, body);
+    }
+    let first_line = body.lines().find(|line| !line.trim().is_empty());
+    if let Some(line) = first_line.filter(|line| heading_text(line).is_some()) {
+        let front = &content[..content.len() - body.len()];
+        return format!("{}{}", front, body.replacen(line, &format!("# {}", title), 1));
+    }
+    if title == stem {
+        return content.to_string();
+    }
+    properties.set("title", Value::Text(title.to_string()));
+    metadata::join(&properties, body)
+}
+
+/// The text of a level 1 ATX heading such as `# Title`.
+fn heading_text(line: &str) -> Option<String> {
+    let line = line.trim_end();
+    let indent = line.len() - line.trim_start_matches(' ').len();
+    let text = line[indent..].strip_prefix("# ").filter(|_| indent <= 3)?;
+    // An optional closing sequence of #s
+    let text = match text.trim_end_matches('#') {
+        stripped if stripped.ends_with(' ') => stripped,
+        _ => text,
+    };
+    let text = text.trim();
+    (!text.is_empty()).then(|| text.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn slugify_keeps_the_title_readable() {
+        assert_eq!(slugify("Shopping List"), "Shopping List");
+        assert_eq!(slugify("Café ☕"), "Café ☕");
+        assert_eq!(slugify("a/b: c?"), "a-b- c-");
+        assert_eq!(slugify("tab\there"), "tab-here");
+        assert_eq!(slugify("  .hidden. "), "hidden");
+        assert_eq!(slugify(". x"), "x");
+        assert_eq!(slugify(""), "Untitled");
+        assert_eq!(slugify("..."), "Untitled");
+        assert_eq!(slugify(&"é".repeat(200)).chars().count(), MAX_STEM_CHARS);
+    }
+
+    #[test]
+    fn title_from_heading_or_front_matter() {
+        assert_eq!(from_content("\n# Title #\ntext").as_deref(), Some("Title"));
+        assert_eq!(from_content("# C#").as_deref(), Some("C#"));
+        assert_eq!(from_content("---\ntitle: \"A: B\"\n---\n# Heading").as_deref(), Some("A: B"));
+        assert_eq!(from_content("---\ntitle: \"\"\n---\n# Heading").as_deref(), Some("Heading"));
+        // Only a level 1 heading on the first line counts
+        assert_eq!(from_content("text\n# Later"), None);
+        assert_eq!(from_content("## Sub"), None);
+        assert_eq!(from_content("    # Code"), None);
+        assert_eq!(from_path(Path::new("notes/My Note.md")), "My Note");
+    }
+
+    #[test]
+    fn set_title_where_the_note_keeps_it() {
+        assert_eq!(set_in_content("# Old\nbody", "New", "Old"), "# New\nbody");
+        assert_eq!(set_in_content("---\ntitle: Old\n---\nbody", "New", "Old"), "---\ntitle: New\n---\nbody");
+        assert_eq!(set_in_content("---\ntags: [a]\n---\n# Old\n", "New", "x"), "---\ntags: [a]\n---\n# New\n");
+    }
+
+    #[test]
+    fn set_title_only_when_the_file_name_does_not_say_it() {
+        assert_eq!(set_in_content("body", "Name", "Name"), "body");
+        assert_eq!(set_in_content("body", "a/b", "a-b"), "---\ntitle: a/b\n---\nbody");
+        assert_eq!(from_content(&set_in_content("", "Why?", "Why-")).as_deref(), Some("Why?"));
+    }
+}
@@ -0,0 +1,55 @@
+//! Deleted notes, kept in a hidden `.trash` folder of the notes directory
+//! until they are restored or purged. `.trash/.index` has a line per
+//! trashed note: its file in the trash, its id, when it was deleted (in
+//! seconds since the Unix epoch) and where it lived, relative to the notes
+//! directory.
+
+use crate::backend::Backend;
+use crate::store::NoteId;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+pub const TRASH_DIR: &str = ".trash";
+const INDEX_FILE: &str = ".index";
+
+#[derive(Clone, Debug)]
+pub struct TrashEntry {
+    /// Name of the file inside the trash folder.
+    pub file: String,
+    pub id: NoteId,
+    pub title: String,
+    /// Where the note lived, relative to the notes directory.
+    pub original: PathBuf,
+    pub deleted_at: SystemTime,
+}
+
+pub(crate) fn index_path(notes_dir: &Path) -> PathBuf {
+    notes_dir.join(TRASH_DIR).join(INDEX_FILE)
+}
+
+/// Entries of the trash index, oldest first. A missing index is an empty trash.
+pub(crate) fn read_index(backend: &dyn Backend, notes_dir: &Path) -> Vec<TrashEntry> {
+    let contents = backend.read(&index_path(notes_dir)).unwrap_or_default();
+    contents
+        .lines()
+        .filter_map(|line| {
+            let mut fields = line.splitn(4, '\t');
+            let file = fields.next()?.to_string();
+            let id = NoteId(fields.next()?.parse().ok()?);
+            let deleted_at = UNIX_EPOCH + Duration::from_secs(fields.next()?.parse().ok()?);
+            let original = PathBuf::from(fields.next()?);
+            let title = original.file_stem()?.to_string_lossy().into_owned();
+            Some(TrashEntry { file, id, title, original, deleted_at })
+        })
+        .collect()
+}
+
+pub(crate) fn write_index(backend: &mut dyn Backend, notes_dir: &Path, entries: &[TrashEntry]) -> io::Result<()> {
+    let mut contents = String::new();
+    for entry in entries {
+        let secs = entry.deleted_at.duration_since(UNIX_EPOCH).unwrap_or_default().as_secs();
+        contents.push_str(&format!("{}\t{}\t{}\t{}\n", entry.file, entry.id, secs, entry.original.display()));
+    }
+    backend.write(&index_path(notes_dir), &contents)
+}
@@ -0,0 +1,109 @@
+use eframe::egui::text::LayoutJob;
+use eframe::egui::{CollapsingHeader, Label, ScrollArea, Sense, TextFormat, TextStyle, Ui};
+use rusty_notes::{Backlink, Mention, NoteId, NoteStore, Snippet};
+
+/// Below the editor: the notes linking to the current one, and the notes
+/// that mention its title without a link. What was found is kept until
+/// another note is shown or the notes change, since looking through every
+/// note each frame would not keep up with a large vault.
+#[derive(Default)]
+pub struct BacklinksPanel {
+    backlinks: Option<(CacheKey, Vec<Backlink>)>,
+    mentions: Option<(CacheKey, Vec<Mention>)>,
+}
+
+// The note shown and the store revision
+type CacheKey = (NoteId, u64);
+
+pub enum BacklinkAction {
+    Open(NoteId),
+    /// Turn these mentions into links, in the order given.
+    Link(Vec<Mention>),
+}
+
+impl BacklinksPanel {
+    pub fn show(&mut self, ui: &mut Ui, store: &NoteStore, id: NoteId) -> Option<BacklinkAction> {
+        let mut action = None;
+        let key = (id, store.revision());
+        let (_, backlinks) = match self.backlinks.take() {
+            Some(cached) if cached.0 == key => self.backlinks.insert(cached),
+            _ => self.backlinks.insert((key, store.backlinks(id))),
+        };
+
+        CollapsingHeader::new(format!("Backlinks ({})", backlinks.len()))
+            .id_source("backlinks")
+            .show(ui, |ui| {
+                ScrollArea::vertical().id_source("backlinks_list").max_height(160.0).show(ui, |ui| {
+                    if backlinks.is_empty() {
+                        ui.weak("No other note links here.");
+                    }
+                    for backlink in backlinks.iter() {
+                        if ui.link(title(store, backlink.source)).clicked() {
+                            action = Some(BacklinkAction::Open(backlink.source));
+                        }
+                        for snippet in &backlink.snippets {
+                            if show_snippet(ui, snippet) {
+                                action = Some(BacklinkAction::Open(backlink.source));
+                            }
+                        }
+                    }
+                });
+            });
+
+        // Searching every note for the title is only worth it when looked at
+        CollapsingHeader::new("Unlinked mentions").id_source("unlinked_mentions").show(ui, |ui| {
+            let (_, mentions) = match self.mentions.take() {
+                Some(cached) if cached.0 == key => self.mentions.insert(cached),
+                _ => self.mentions.insert((key, store.unlinked_mentions(id))),
+            };
+            if mentions.is_empty() {
+                ui.weak("The title is not mentioned anywhere else.");
+                return;
+            }
+            if ui.button("Link all").on_hover_text("Turn every mention into a link").clicked() {
+                // From the end, so that earlier offsets stay valid
+                action = Some(BacklinkAction::Link(mentions.iter().rev().cloned().collect()));
+            }
+            ScrollArea::vertical().id_source("mentions_list").max_height(160.0).show(ui, |ui| {
+                let mut previous = None;
+                for mention in mentions.iter() {
+                    if previous != Some(mention.source) {
+                        if ui.link(title(store, mention.source)).clicked() {
+                            action = Some(BacklinkAction::Open(mention.source));
+                        }
+                        previous = Some(mention.source);
+                    }
+                    ui.horizontal(|ui| {
+                        if ui.small_button("Link").on_hover_text("Turn this mention into a link").clicked() {
+                            action = Some(BacklinkAction::Link(vec![mention.clone()]));
+                        }
+                        if show_snippet(ui, &mention.snippet) {
+                            action = Some(BacklinkAction::Open(mention.source));
+                        }
+                    });
+                }
+            });
+        });
+        action
+    }
+}
+
+fn title(store: &NoteStore, id: NoteId) -> String {
+    store.get(id).map(|note| note.title.clone()).unwrap_or_default()
+}
+
+/// A line number and the text around a link, the link itself emphasized;
+/// returns whether it was clicked.
+pub fn show_snippet(ui: &mut Ui, snippet: &Snippet) -> bool {
+    let font_id = TextStyle::Body.resolve(ui.style());
+    let plain = TextFormat { font_id: font_id.clone(), color: ui.visuals().text_color(), ..Default::default() };
+    let strong = TextFormat { font_id, color: ui.visuals().strong_text_color(), ..Default::default() };
+    let mut job = LayoutJob::default();
+    job.append(&format!("{}: ", snippet.line), 0.0, TextFormat { color: ui.visuals().weak_text_color(), ..plain.clone() });
+    job.append(&snippet.text[..snippet.highlight.start], 0.0, plain.clone());
+    job.append(&snippet.text[snippet.highlight.clone()], 0.0, strong);
+    job.append(&snippet.text[snippet.highlight.end..], 0.0, plain);
+    ui.add(Label::new(job).sense(Sense::click()))
+        .on_hover_text("Open note")
+        .clicked()
+}
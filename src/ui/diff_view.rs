@@ -0,0 +1,118 @@
+use eframe::egui::{Color32, Grid, Label, RichText, Ui};
+use rusty_notes::diff::{self, DiffOp};
+
+// Unchanged lines shown around each change
+const CONTEXT: usize = 2;
+
+/// The lines of two texts and how to get from the old one to the new one,
+/// worked out once and kept for as long as the texts are shown, since
+/// diffing them every frame would make long notes slow to scroll.
+pub struct TextDiff {
+    old_lines: Vec<String>,
+    new_lines: Vec<String>,
+    ops: Vec<DiffOp>,
+}
+
+impl TextDiff {
+    pub fn new(old: &str, new: &str) -> Self {
+        let (old_lines, new_lines) = (diff::lines(old), diff::lines(new));
+        let ops = diff::diff_lines(&old_lines, &new_lines);
+        let owned = |lines: Vec<&str>| lines.into_iter().map(str::to_string).collect();
+        Self { old_lines: owned(old_lines), new_lines: owned(new_lines), ops }
+    }
+}
+
+/// Shows the lines the new text adds to or removes from the old one, with
+/// a little unchanged context around each change.
+pub fn show_unified(ui: &mut Ui, diff: &TextDiff) {
+    let TextDiff { old_lines, new_lines, ops } = diff;
+    let changed: Vec<bool> = ops.iter().map(|op| !matches!(op, DiffOp::Equal(..))).collect();
+    let near_change = |i: usize| changed[i.saturating_sub(CONTEXT)..(i + CONTEXT + 1).min(changed.len())].contains(&true);
+
+    let (removed, added) = colors(ui);
+    let mut skipped = false;
+    for (i, op) in ops.iter().enumerate() {
+        let (prefix, line, color) = match *op {
+            DiffOp::Equal(..) if !near_change(i) => {
+                skipped = true;
+                continue;
+            }
+            DiffOp::Equal(a, _) => ("  ", &old_lines[a], None),
+            DiffOp::Delete(a) => ("- ", &old_lines[a], Some(removed)),
+            DiffOp::Insert(b) => ("+ ", &new_lines[b], Some(added)),
+        };
+        if std::mem::take(&mut skipped) {
+            ui.label(RichText::new("  …").monospace().weak());
+        }
+        let text = RichText::new(format!("{}{}", prefix, line.trim_end_matches('\n'))).monospace();
+        ui.label(match color {
+            Some(color) => text.color(color),
+            None => text.weak(),
+        });
+    }
+    if skipped {
+        ui.label(RichText::new("  …").monospace().weak());
+    }
+    if !changed.contains(&true) {
+        ui.label(RichText::new("No differences").italics());
+    }
+}
+
+/// Shows the old and the new text in two columns, with changed lines
+/// lined up against each other.
+pub fn show_side_by_side(ui: &mut Ui, diff: &TextDiff) {
+    let TextDiff { old_lines, new_lines, ops } = diff;
+
+    // Each row pairs a line of `old` with a line of `new`; runs of removed
+    // and added lines share rows
+    let mut rows: Vec<Row> = vec![];
+    let (mut deleted, mut inserted) = (vec![], vec![]);
+    for &op in ops {
+        match op {
+            DiffOp::Equal(a, _) => {
+                pair_up(&mut rows, &mut deleted, &mut inserted);
+                rows.push((Some(&old_lines[a]), Some(&old_lines[a]), false));
+            }
+            DiffOp::Delete(a) => deleted.push(&old_lines[a]),
+            DiffOp::Insert(b) => inserted.push(&new_lines[b]),
+        }
+    }
+    pair_up(&mut rows, &mut deleted, &mut inserted);
+
+    let (removed, added) = colors(ui);
+    let column_width = (ui.available_width() - ui.spacing().item_spacing.x * 2.0) / 2.0;
+    Grid::new("side_by_side_diff")
+        .num_columns(2)
+        .min_col_width(column_width)
+        .max_col_width(column_width)
+        .show(ui, |ui| {
+            for (left, right, changed) in rows {
+                for (line, color) in [(left, removed), (right, added)] {
+                    let text = RichText::new(line.unwrap_or("").trim_end_matches('\n')).monospace();
+                    let text = if changed { text.color(color) } else { text.weak() };
+                    ui.add(Label::new(text).wrap(true));
+                }
+                ui.end_row();
+            }
+        });
+}
+
+// A line of the old text, a line of the new text, and whether they differ
+type Row<'a> = (Option<&'a str>, Option<&'a str>, bool);
+
+fn pair_up<'a>(rows: &mut Vec<Row<'a>>, deleted: &mut Vec<&'a str>, inserted: &mut Vec<&'a str>) {
+    for i in 0..deleted.len().max(inserted.len()) {
+        rows.push((deleted.get(i).copied(), inserted.get(i).copied(), true));
+    }
+    deleted.clear();
+    inserted.clear();
+}
+
+// Colours of removed and added lines
+fn colors(ui: &Ui) -> (Color32, Color32) {
+    if ui.visuals().dark_mode {
+        (Color32::from_rgb(240, 110, 110), Color32::from_rgb(110, 210, 110))
+    } else {
+        (Color32::from_rgb(180, 30, 30), Color32::from_rgb(20, 130, 20))
+    }
+}
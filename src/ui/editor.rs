@@ -0,0 +1,197 @@
+use eframe::egui::text::{CCursor, CCursorRange, LayoutJob, LayoutSection};
+use eframe::egui::text_edit::{TextEditOutput, TextEditState};
+use eframe::egui::{self, Id, Key, Modifiers, Order, Stroke, TextFormat, TextStyle, Ui};
+use rusty_notes::links;
+use std::collections::HashSet;
+use std::ops::Range;
+
+/// Most titles suggested at once while a link is typed.
+const MAX_SUGGESTIONS: usize = 8;
+
+/// The editor's text laid out in the monospace font, with wiki links drawn
+/// as links. `titles` holds every note title in lower case; links to other
+/// titles are dimmed.
+pub fn highlight(ui: &Ui, text: &str, titles: &HashSet<String>) -> LayoutJob {
+    let font_id = TextStyle::Monospace.resolve(ui.style());
+    let plain = TextFormat { font_id: font_id.clone(), color: ui.visuals().text_color(), ..Default::default() };
+    let mut job = LayoutJob::default();
+    let mut last = 0;
+    for link in links::find(text) {
+        job.append(&text[last..link.range.start], 0.0, plain.clone());
+        let color = if titles.contains(&link.target.to_lowercase()) {
+            ui.visuals().hyperlink_color
+        } else {
+            ui.visuals().weak_text_color()
+        };
+        let format = TextFormat { font_id: font_id.clone(), color, underline: Stroke::new(1.0, color), ..Default::default() };
+        job.append(&text[link.range.clone()], 0.0, format);
+        last = link.range.end;
+    }
+    job.append(&text[last..], 0.0, plain);
+    job
+}
+
+/// Puts a background behind the byte ranges `matches` of the job's text,
+/// which must be in order, and a stronger one behind match `current`.
+pub fn mark_matches(ui: &Ui, job: &mut LayoutJob, matches: &[Range<usize>], current: Option<usize>) {
+    if matches.is_empty() {
+        return;
+    }
+    let color = ui.visuals().warn_fg_color;
+    let mut sections = Vec::with_capacity(job.sections.len() + matches.len() * 2);
+    for section in std::mem::take(&mut job.sections) {
+        let mut pieces: Vec<(Range<usize>, Option<usize>)> = vec![];
+        let mut start = section.byte_range.start;
+        let first = matches.partition_point(|found| found.end <= start);
+        for (i, found) in matches.iter().enumerate().skip(first) {
+            if found.start >= section.byte_range.end {
+                break;
+            }
+            let marked = found.start.max(start)..found.end.min(section.byte_range.end);
+            if marked.start > start {
+                pieces.push((start..marked.start, None));
+            }
+            start = marked.end;
+            pieces.push((marked, Some(i)));
+        }
+        if start < section.byte_range.end {
+            pieces.push((start..section.byte_range.end, None));
+        }
+        for (n, (byte_range, found)) in pieces.into_iter().enumerate() {
+            let mut format = section.format.clone();
+            if let Some(i) = found {
+                format.background = color.gamma_multiply(if current == Some(i) { 0.7 } else { 0.3 });
+            }
+            let leading_space = if n == 0 { section.leading_space } else { 0.0 };
+            sections.push(LayoutSection { leading_space, byte_range, format });
+        }
+    }
+    job.sections = sections;
+}
+
+/// The target of the wiki link under the cursor, for Ctrl+click.
+pub fn link_at_cursor(text: &str, output: &TextEditOutput) -> Option<String> {
+    let cursor = byte_index(text, output.cursor_range?.primary.ccursor.index);
+    links::find(text).into_iter().find(|link| (link.range.start..=link.range.end).contains(&cursor)).map(|link| link.target)
+}
+
+/// Suggests note titles below the cursor while a `[[` link is typed.
+#[derive(Default)]
+pub struct LinkCompletion {
+    /// The titles suggested last frame.
+    suggestions: Vec<String>,
+    selected: usize,
+    /// Byte offset of the link being typed.
+    start: Option<usize>,
+    /// Start of the link Escape was pressed in, so it stays dismissed.
+    dismissed: Option<usize>,
+}
+
+impl LinkCompletion {
+    /// Takes the keys that move through the suggestions before the editor
+    /// sees them, so call it before drawing the editor. Returns the title
+    /// picked with Enter or Tab.
+    pub fn take_keys(&mut self, ui: &Ui) -> Option<String> {
+        if self.suggestions.is_empty() {
+            return None;
+        }
+        let count = self.suggestions.len();
+        let mut picked = None;
+        ui.input_mut(|i| {
+            if i.consume_key(Modifiers::NONE, Key::ArrowDown) {
+                self.selected = (self.selected + 1) % count;
+            }
+            if i.consume_key(Modifiers::NONE, Key::ArrowUp) {
+                self.selected = (self.selected + count - 1) % count;
+            }
+            if i.consume_key(Modifiers::NONE, Key::Enter) || i.consume_key(Modifiers::NONE, Key::Tab) {
+                picked = self.suggestions.get(self.selected).cloned();
+            }
+            if i.consume_key(Modifiers::NONE, Key::Escape) {
+                self.dismissed = self.start;
+            }
+        });
+        picked
+    }
+
+    /// Shows the titles matching the link being typed at the cursor, if
+    /// any, and returns the one clicked.
+    pub fn show<'t>(
+        &mut self,
+        ui: &Ui,
+        text: &str,
+        output: &TextEditOutput,
+        titles: impl Iterator<Item = &'t str>,
+    ) -> Option<String> {
+        let cursor = output.cursor_range.filter(|_| output.response.has_focus());
+        let before = cursor.map(|range| &text[..byte_index(text, range.primary.ccursor.index)]);
+        let Some((typed, cursor)) = before.and_then(|before| Some((links::partial(before)?, cursor?))) else {
+            self.suggestions.clear();
+            self.start = None;
+            self.dismissed = None;
+            return None;
+        };
+        self.start = before.map(|before| before.len() - typed.len());
+        if self.dismissed.is_some() && self.dismissed == self.start {
+            self.suggestions.clear();
+            return None;
+        }
+
+        let typed = typed.trim().to_lowercase();
+        let mut matches: Vec<&str> = titles.filter(|title| title.to_lowercase().contains(&typed)).collect();
+        // Titles starting with what was typed come first
+        matches.sort_by_key(|title| (!title.to_lowercase().starts_with(&typed), title.to_lowercase()));
+        matches.dedup();
+        matches.truncate(MAX_SUGGESTIONS);
+        let suggestions: Vec<String> = matches.into_iter().map(String::from).collect();
+        if suggestions != self.suggestions {
+            self.selected = 0;
+            self.suggestions = suggestions;
+        }
+        if self.suggestions.is_empty() {
+            return None;
+        }
+
+        let pos = output.galley_pos + output.galley.pos_from_cursor(&cursor.primary).left_bottom().to_vec2();
+        let mut picked = None;
+        egui::Area::new(Id::new("link_completion")).order(Order::Foreground).fixed_pos(pos).show(ui.ctx(), |ui| {
+            egui::Frame::popup(ui.style()).show(ui, |ui| {
+                for (i, title) in self.suggestions.iter().enumerate() {
+                    if ui.selectable_label(i == self.selected, title).clicked() {
+                        picked = Some(title.clone());
+                    }
+                }
+            });
+        });
+        picked
+    }
+}
+
+/// Finishes the link being typed at the editor's cursor with `title`, and
+/// puts the cursor after it. Returns whether `text` changed.
+pub fn complete_link(ui: &Ui, editor_id: Id, text: &mut String, title: &str) -> bool {
+    let Some(mut state) = TextEditState::load(ui.ctx(), editor_id) else {
+        return false;
+    };
+    let Some(range) = state.cursor.char_range() else {
+        return false;
+    };
+    let cursor = byte_index(text, range.primary.index);
+    let Some(typed) = links::partial(&text[..cursor]) else {
+        return false;
+    };
+    let start = cursor - typed.len();
+    // Keep a closing `]]` that is already there
+    let closing = if text[cursor..].starts_with("]]") { "" } else { "]]" };
+    text.replace_range(start..cursor, &format!("{}{}", title, closing));
+
+    let new_cursor = text[..start + title.len() + 2].chars().count();
+    state.cursor.set_char_range(Some(CCursorRange::one(CCursor::new(new_cursor))));
+    state.store(ui.ctx(), editor_id);
+    ui.ctx().memory_mut(|memory| memory.request_focus(editor_id));
+    true
+}
+
+fn byte_index(text: &str, char_index: usize) -> usize {
+    text.char_indices().nth(char_index).map_or(text.len(), |(i, _)| i)
+}
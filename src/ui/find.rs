@@ -0,0 +1,172 @@
+use eframe::egui::{Button, Key, TextEdit, Ui};
+use rusty_notes::find;
+use rusty_notes::{FindOptions, Finder};
+use std::ops::Range;
+
+/// Find and replace in the current note, shown above the editor. Enter
+/// moves to the next match and Shift+Enter to the previous one; Escape
+/// closes the bar and leaves the current match selected in the editor.
+#[derive(Default)]
+pub struct FindBar {
+    open: bool,
+    show_replace: bool,
+    query: String,
+    replacement: String,
+    options: FindOptions,
+    /// The query and options last compiled, and what came of it.
+    compiled: Option<(String, FindOptions, Result<Finder, String>)>,
+    /// Index of the current match.
+    current: usize,
+    /// After a replacement: byte offset of the text following it, where
+    /// the next match is looked for.
+    resume_at: Option<usize>,
+    /// Focus the query field next frame.
+    focus: bool,
+}
+
+pub enum FindAction {
+    /// Select a byte range of the note's body and scroll to it, focusing
+    /// the editor if `focus`.
+    Select { range: Range<usize>, focus: bool },
+    /// Replacing changed the text.
+    Replaced,
+    /// Preview replacing the matches in every note.
+    ReplaceInVault(Finder, String),
+}
+
+impl FindBar {
+    pub fn open(&mut self, replace: bool) {
+        self.open = true;
+        self.show_replace |= replace;
+        self.focus = true;
+    }
+
+    /// The matches in `text` to highlight, and which is the current one.
+    pub fn matches(&self, text: &str) -> (Vec<Range<usize>>, Option<usize>) {
+        match self.finder() {
+            Some(finder) => {
+                let matches = finder.find_all(text);
+                let current = (self.current < matches.len()).then_some(self.current);
+                (matches, current)
+            }
+            None => (vec![], None),
+        }
+    }
+
+    fn finder(&self) -> Option<&Finder> {
+        match &self.compiled {
+            Some((_, _, Ok(finder))) if self.open && !self.query.is_empty() => Some(finder),
+            _ => None,
+        }
+    }
+
+    fn is_compiled(&self) -> bool {
+        self.compiled.as_ref().is_some_and(|(query, options, _)| *query == self.query && *options == self.options)
+    }
+
+    /// Draws the bar, if open, for `text`, the note's body, which
+    /// replacing changes in place.
+    pub fn show(&mut self, ui: &mut Ui, text: &mut String) -> Option<FindAction> {
+        if !self.open {
+            return None;
+        }
+        if !self.is_compiled() {
+            let finder = Finder::new(&self.query, self.options).map_err(|e| e.to_string());
+            self.compiled = Some((self.query.clone(), self.options, finder));
+            // Start over at the first match of the new query
+            self.resume_at = Some(0);
+        }
+        let mut action = None;
+        let (matches, _) = self.matches(text);
+        if let Some(offset) = self.resume_at.take() {
+            self.current = matches.iter().position(|found| found.start >= offset).unwrap_or(0);
+            if let Some(range) = matches.get(self.current) {
+                action = Some(FindAction::Select { range: range.clone(), focus: false });
+            }
+        }
+        let mut step = None;
+        let mut close = false;
+
+        ui.horizontal(|ui| {
+            let response = ui.add(TextEdit::singleline(&mut self.query).hint_text("Find").desired_width(220.0));
+            if std::mem::take(&mut self.focus) {
+                response.request_focus();
+            }
+            if response.lost_focus() {
+                if ui.input(|i| i.key_pressed(Key::Enter)) {
+                    step = Some(!ui.input(|i| i.modifiers.shift));
+                    response.request_focus();
+                } else if ui.input(|i| i.key_pressed(Key::Escape)) {
+                    close = true;
+                }
+            }
+            ui.toggle_value(&mut self.options.match_case, "Aa").on_hover_text("Match case");
+            ui.toggle_value(&mut self.options.whole_word, "W").on_hover_text("Whole words only");
+            ui.toggle_value(&mut self.options.regex, ".*").on_hover_text("Regular expression");
+
+            match &self.compiled {
+                Some((_, _, Err(e))) if !self.query.is_empty() => {
+                    ui.colored_label(ui.visuals().error_fg_color, "Invalid pattern").on_hover_text(e);
+                }
+                _ if self.query.is_empty() => {}
+                _ if matches.is_empty() => {
+                    ui.weak("No matches");
+                }
+                _ => {
+                    ui.label(format!("{} of {}", self.current.min(matches.len() - 1) + 1, matches.len()));
+                }
+            }
+            if ui.small_button("⬆").on_hover_text("Previous match (Shift+Enter)").clicked() {
+                step = Some(false);
+            }
+            if ui.small_button("⬇").on_hover_text("Next match (Enter)").clicked() {
+                step = Some(true);
+            }
+            ui.toggle_value(&mut self.show_replace, "Replace");
+            if ui.small_button("✖").on_hover_text("Close (Escape)").clicked() {
+                close = true;
+            }
+        });
+
+        if !self.is_compiled() {
+            // The query changed; its matches are found next frame
+            ui.ctx().request_repaint();
+        }
+        let finder = self.finder().cloned();
+        if self.show_replace {
+            ui.horizontal(|ui| {
+                ui.add(TextEdit::singleline(&mut self.replacement).hint_text("Replace with").desired_width(220.0))
+                    .on_hover_text("With a regular expression, $1 or ${name} stand for what a group matched");
+                let Some(finder) = &finder else {
+                    return;
+                };
+                let found = !matches.is_empty();
+                if ui.add_enabled(found, Button::new("Replace")).clicked() {
+                    let range = matches[self.current.min(matches.len() - 1)].clone();
+                    let replacement = finder.replacement(text, range.clone(), &self.replacement);
+                    text.replace_range(range.clone(), &replacement);
+                    self.resume_at = Some(range.start + replacement.len());
+                    action = Some(FindAction::Replaced);
+                }
+                if ui.add_enabled(found, Button::new("Replace all")).clicked() {
+                    *text = find::apply(text, &finder.changes(text, &self.replacement));
+                    action = Some(FindAction::Replaced);
+                }
+                if ui.button("In all notes…").on_hover_text("Preview replacing in every note").clicked() {
+                    action = Some(FindAction::ReplaceInVault(finder.clone(), self.replacement.clone()));
+                }
+            });
+        }
+
+        if close {
+            self.open = false;
+            return matches.get(self.current).map(|range| FindAction::Select { range: range.clone(), focus: true });
+        }
+        if let (Some(forward), false) = (step, matches.is_empty()) {
+            let count = matches.len();
+            self.current = if forward { (self.current + 1) % count } else { (self.current + count - 1) % count };
+            action = Some(FindAction::Select { range: matches[self.current].clone(), focus: false });
+        }
+        action
+    }
+}
@@ -0,0 +1,219 @@
+use eframe::egui::{self, Align2, Color32, ComboBox, CursorIcon, FontId, Pos2, Rect, Sense, Shape, Stroke, Ui, Vec2};
+use rusty_notes::graph::{EdgeKind, ForceLayout, Graph};
+use rusty_notes::{NoteId, NoteStore};
+use std::path::PathBuf;
+
+const NODE_RADIUS: f32 = 5.0;
+/// Below this zoom only the current note and the hovered one are labelled.
+const LABEL_ZOOM: f32 = 0.8;
+/// Total node movement per step under which the layout counts as settled.
+const SETTLED: f32 = 0.5;
+
+/// Every note as a node, with edges for links and shared tags. Scrolling
+/// zooms, dragging the background pans, dragging a node moves it, and
+/// clicking a node opens its note.
+pub struct GraphView {
+    layout: ForceLayout,
+    /// Offset of the graph's origin from the middle of the view, in points.
+    pan: Vec2,
+    zoom: f32,
+    tag_filter: Option<String>,
+    folder_filter: Option<PathBuf>,
+    tag_edges: bool,
+    dragging: Option<NoteId>,
+    /// The graph last built, kept until the notes or the filters change,
+    /// with the store revision and filters it was built for.
+    graph: Option<(GraphKey, Graph)>,
+}
+
+type GraphKey = (u64, Option<String>, Option<PathBuf>, bool);
+
+impl Default for GraphView {
+    fn default() -> Self {
+        Self {
+            layout: ForceLayout::default(),
+            pan: Vec2::ZERO,
+            zoom: 1.0,
+            tag_filter: None,
+            folder_filter: None,
+            tag_edges: true,
+            dragging: None,
+            graph: None,
+        }
+    }
+}
+
+impl GraphView {
+    /// Draws the graph with the neighbourhood of `current` highlighted and
+    /// returns the note clicked.
+    pub fn show(&mut self, ui: &mut Ui, store: &NoteStore, current: Option<NoteId>) -> Option<NoteId> {
+        self.show_filters(ui, store);
+        let key = (store.revision(), self.tag_filter.clone(), self.folder_filter.clone(), self.tag_edges);
+        if self.graph.as_ref().is_none_or(|(built, _)| *built != key) {
+            let graph = Graph::build(
+                store,
+                |note| {
+                    self.tag_filter.as_ref().is_none_or(|tag| note.has_tag(tag))
+                        && self.folder_filter.as_ref().is_none_or(|folder| store.folder_of(note).starts_with(folder))
+                },
+                self.tag_edges,
+            );
+            self.graph = Some((key, graph));
+        }
+        // Taken out for the frame, since drawing it also changes the view
+        let (key, graph) = self.graph.take().expect("built above");
+        let clicked = self.draw(ui, store, current, &graph);
+        self.graph = Some((key, graph));
+        clicked
+    }
+
+    fn draw(&mut self, ui: &mut Ui, store: &NoteStore, current: Option<NoteId>, graph: &Graph) -> Option<NoteId> {
+        if self.layout.step(graph) > SETTLED || self.dragging.is_some() {
+            ui.ctx().request_repaint();
+        }
+
+        let (response, painter) = ui.allocate_painter(ui.available_size(), Sense::click_and_drag());
+        let rect = response.rect;
+        if graph.nodes.is_empty() {
+            painter.text(rect.center(), Align2::CENTER_CENTER, "No notes to show", FontId::proportional(14.0), ui.visuals().weak_text_color());
+            return None;
+        }
+        self.zoom_and_pan(ui, &response, graph);
+
+        let screen: Vec<Pos2> = graph
+            .nodes
+            .iter()
+            .map(|&id| self.to_screen(rect, self.layout.position(id).unwrap_or_default()))
+            .collect();
+        let degrees = graph.degrees();
+        let radius = |i: usize| (NODE_RADIUS + (degrees[i].min(16) as f32).sqrt() * 1.5) * self.zoom.sqrt();
+        let pointer = response.hover_pos();
+        let hovered = pointer.and_then(|pointer| {
+            (0..screen.len())
+                .filter(|&i| screen[i].distance(pointer) <= radius(i).max(4.0))
+                .min_by(|&a, &b| screen[a].distance(pointer).total_cmp(&screen[b].distance(pointer)))
+        });
+
+        let mut clicked = None;
+        if let Some(i) = hovered {
+            ui.ctx().set_cursor_icon(CursorIcon::PointingHand);
+            if response.clicked() {
+                clicked = Some(graph.nodes[i]);
+            }
+        }
+        if response.drag_started() {
+            self.dragging = hovered.map(|i| graph.nodes[i]);
+        }
+        if response.drag_stopped() {
+            self.dragging = None;
+        }
+
+        let current = current.and_then(|id| graph.nodes.iter().position(|&node| node == id));
+        let neighbours = current.map(|i| graph.neighbours(i)).unwrap_or_default();
+        let visuals = ui.visuals();
+        let highlight = visuals.selection.bg_fill;
+        let faded = |color: Color32| if current.is_some() { color.gamma_multiply(0.3) } else { color };
+
+        for edge in &graph.edges {
+            let touches_current = current.is_some_and(|i| edge.from == i || edge.to == i);
+            let stroke = if touches_current {
+                Stroke::new(2.0, highlight)
+            } else {
+                Stroke::new(1.0, faded(visuals.weak_text_color()))
+            };
+            let points = [screen[edge.from], screen[edge.to]];
+            match edge.kind {
+                EdgeKind::Link => {
+                    painter.line_segment(points, stroke);
+                }
+                EdgeKind::Tag => painter.extend(Shape::dashed_line(&points, stroke, 4.0, 4.0)),
+            }
+        }
+
+        for (i, &id) in graph.nodes.iter().enumerate() {
+            let near = current == Some(i) || neighbours.contains(&i);
+            let fill = if current == Some(i) {
+                highlight
+            } else if near {
+                visuals.strong_text_color()
+            } else {
+                faded(visuals.text_color())
+            };
+            painter.circle_filled(screen[i], radius(i), fill);
+
+            if near || hovered == Some(i) || self.zoom >= LABEL_ZOOM {
+                let title = store.get(id).map_or("", |note| note.title.as_str());
+                let color = if near || hovered == Some(i) { visuals.strong_text_color() } else { faded(visuals.text_color()) };
+                painter.text(
+                    screen[i] + Vec2::new(0.0, radius(i) + 2.0),
+                    Align2::CENTER_TOP,
+                    title,
+                    FontId::proportional(11.0),
+                    color,
+                );
+            }
+        }
+        clicked
+    }
+
+    fn show_filters(&mut self, ui: &mut Ui, store: &NoteStore) {
+        ui.horizontal(|ui| {
+            ui.label("Tag:");
+            let selected = self.tag_filter.as_ref().map_or("All".to_string(), |tag| format!("#{}", tag));
+            ComboBox::from_id_source("graph_tag").selected_text(selected).show_ui(ui, |ui| {
+                ui.selectable_value(&mut self.tag_filter, None, "All");
+                for tag in store.tag_counts().into_keys() {
+                    let text = format!("#{}", tag);
+                    ui.selectable_value(&mut self.tag_filter, Some(tag), text);
+                }
+            });
+
+            ui.label("Folder:");
+            let selected = self.folder_filter.as_ref().map_or("All".to_string(), |folder| folder.display().to_string());
+            ComboBox::from_id_source("graph_folder").selected_text(selected).show_ui(ui, |ui| {
+                ui.selectable_value(&mut self.folder_filter, None, "All");
+                for folder in store.folders() {
+                    let text = folder.display().to_string();
+                    ui.selectable_value(&mut self.folder_filter, Some(folder.clone()), text);
+                }
+            });
+
+            ui.checkbox(&mut self.tag_edges, "Shared tags")
+                .on_hover_text("Connect notes that have a tag in common");
+            if ui.button("Reset view").clicked() {
+                self.pan = Vec2::ZERO;
+                self.zoom = 1.0;
+            }
+        });
+    }
+
+    // Scrolling zooms around the pointer; dragging moves a node or the view
+    fn zoom_and_pan(&mut self, ui: &Ui, response: &egui::Response, graph: &Graph) {
+        let rect = response.rect;
+        if let (true, Some(pointer)) = (response.hovered(), response.hover_pos()) {
+            let (scroll, pinch) = ui.input(|i| (i.smooth_scroll_delta.y, i.zoom_delta()));
+            let factor = pinch * (scroll * 0.002).exp();
+            if factor != 1.0 {
+                let anchor = (pointer - rect.center() - self.pan) / self.zoom;
+                self.zoom = (self.zoom * factor).clamp(0.1, 5.0);
+                self.pan = pointer - rect.center() - anchor * self.zoom;
+            }
+        }
+        if !response.dragged() {
+            return;
+        }
+        match self.dragging.filter(|id| graph.nodes.contains(id)) {
+            Some(id) => {
+                if let Some(pointer) = response.interact_pointer_pos() {
+                    let position = (pointer - rect.center() - self.pan) / self.zoom;
+                    self.layout.set_position(id, [position.x, position.y]);
+                }
+            }
+            None => self.pan += response.drag_delta(),
+        }
+    }
+
+    fn to_screen(&self, rect: Rect, position: [f32; 2]) -> Pos2 {
+        rect.center() + self.pan + Vec2::new(position[0], position[1]) * self.zoom
+    }
+}
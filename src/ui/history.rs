@@ -0,0 +1,139 @@
+use crate::ui::diff_view::{self, TextDiff};
+use crate::ui::time::{format_age, format_datetime};
+use eframe::egui::{self, ComboBox, Context, RichText, ScrollArea};
+use rusty_notes::{NoteId, NoteStore, StoreError, Version};
+use std::collections::HashMap;
+
+/// Saved versions of a note, each comparable side by side with the note as
+/// it is now or with another version, and restorable in one click.
+pub struct HistoryView {
+    pub id: NoteId,
+    title: String,
+    current: String,
+    versions: Vec<Version>,
+    selected: usize,
+    // Version shown on the right; None is the note as it is now
+    compare_to: Option<usize>,
+    contents: HashMap<usize, Result<String, String>>,
+    // The versions last compared and how they differ
+    diff: Option<(Compared, Result<TextDiff, String>)>,
+}
+
+// The version selected and the one it is compared with
+type Compared = (usize, Option<usize>);
+
+pub enum HistoryOutcome {
+    Restore(String),
+    Close,
+}
+
+impl HistoryView {
+    pub fn new(store: &NoteStore, id: NoteId) -> Result<Self, StoreError> {
+        let note = store.get(id).ok_or(StoreError::NoSuchNote(id))?;
+        Ok(Self {
+            id,
+            title: note.title.clone(),
+            current: note.content.clone(),
+            versions: store.history(id)?,
+            selected: 0,
+            compare_to: None,
+            contents: HashMap::new(),
+            diff: None,
+        })
+    }
+
+    fn content(&mut self, store: &NoteStore, index: usize) -> Result<String, String> {
+        self.contents
+            .entry(index)
+            .or_insert_with(|| store.read_version(&self.versions[index]).map_err(|e| e.to_string()))
+            .clone()
+    }
+
+    fn label(&self, index: Option<usize>) -> String {
+        match index {
+            Some(index) => format_datetime(self.versions[index].saved_at),
+            None => "Current note".to_string(),
+        }
+    }
+
+    pub fn show(&mut self, ctx: &Context, store: &NoteStore) -> Option<HistoryOutcome> {
+        let mut outcome = None;
+        let mut open = true;
+
+        egui::Window::new(format!("History of \"{}\"", self.title))
+            .open(&mut open)
+            .default_size([900.0, 500.0])
+            .show(ctx, |ui| {
+                if self.versions.is_empty() {
+                    ui.label("No saved versions yet. A version is kept every time the note is saved.");
+                    return;
+                }
+                ui.horizontal_top(|ui| {
+                    ui.vertical(|ui| {
+                        ui.set_width(220.0);
+                        ui.label(RichText::new("Saved versions").strong());
+                        ScrollArea::vertical().id_source("history_versions").max_height(420.0).show(ui, |ui| {
+                            for (i, version) in self.versions.iter().enumerate() {
+                                let age = version.saved_at.elapsed().map_or(String::new(), |age| {
+                                    format!(" ({} ago)", format_age(age.as_secs()))
+                                });
+                                let text = format!("{}{}", format_datetime(version.saved_at), age);
+                                ui.selectable_value(&mut self.selected, i, text);
+                            }
+                        });
+                    });
+                    ui.separator();
+                    ui.vertical(|ui| {
+                        ui.horizontal(|ui| {
+                            ui.label(format!("{}  compared with", self.label(Some(self.selected))));
+                            let choices: Vec<(Option<usize>, String)> = std::iter::once(None)
+                                .chain((0..self.versions.len()).map(Some))
+                                .map(|index| (index, self.label(index)))
+                                .collect();
+                            ComboBox::from_id_source("history_compare_to")
+                                .selected_text(self.label(self.compare_to))
+                                .show_ui(ui, |ui| {
+                                    for (index, label) in choices {
+                                        ui.selectable_value(&mut self.compare_to, index, label);
+                                    }
+                                });
+                        });
+                        let key = (self.selected, self.compare_to);
+                        let (_, diff) = match self.diff.take() {
+                            Some(compared) if compared.0 == key => self.diff.insert(compared),
+                            _ => {
+                                let old = self.content(store, self.selected);
+                                let new = match self.compare_to {
+                                    Some(i) => self.content(store, i),
+                                    None => Ok(self.current.clone()),
+                                };
+                                let diff = old.and_then(|old| new.map(|new| TextDiff::new(&old, &new)));
+                                self.diff.insert((key, diff))
+                            }
+                        };
+                        match diff {
+                            Ok(diff) => {
+                                ScrollArea::vertical().id_source("history_diff").max_height(400.0).show(ui, |ui| {
+                                    diff_view::show_side_by_side(ui, diff);
+                                });
+                            }
+                            Err(e) => {
+                                ui.colored_label(ui.visuals().error_fg_color, e.as_str());
+                            }
+                        }
+                        ui.add_space(5.0);
+                        if let Some(Ok(old)) = self.contents.get(&self.selected) {
+                            if ui.button("Restore this version").clicked() {
+                                outcome = Some(HistoryOutcome::Restore(old.clone()));
+                            }
+                        }
+                    });
+                });
+            });
+
+        if !open {
+            outcome = Some(HistoryOutcome::Close);
+        }
+        outcome
+    }
+}
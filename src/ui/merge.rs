@@ -0,0 +1,89 @@
+use eframe::egui::{self, Context, RichText, ScrollArea, TextEdit, TextStyle};
+use rusty_notes::diff;
+use rusty_notes::NoteId;
+
+/// Side-by-side view of a note's local edits, the file on disk and their
+/// three-way merge, which the user can edit before applying it.
+pub struct MergeView {
+    pub id: NoteId,
+    title: String,
+    mine: String,
+    theirs: String,
+    merged: String,
+    conflicts: usize,
+}
+
+pub enum MergeOutcome {
+    Apply(String),
+    Cancel,
+}
+
+impl MergeView {
+    pub fn new(id: NoteId, title: &str, base: &str, mine: &str, theirs: &str) -> Self {
+        let merge = diff::merge3(base, mine, theirs);
+        Self {
+            id,
+            title: title.to_string(),
+            mine: mine.to_string(),
+            theirs: theirs.to_string(),
+            merged: merge.text,
+            conflicts: merge.conflicts,
+        }
+    }
+
+    pub fn show(&mut self, ctx: &Context) -> Option<MergeOutcome> {
+        let mut outcome = None;
+        let mut open = true;
+
+        egui::Window::new(format!("Merge \"{}\"", self.title))
+            .open(&mut open)
+            .default_size([900.0, 500.0])
+            .show(ctx, |ui| {
+                if self.conflicts > 0 {
+                    ui.label(format!(
+                        "{} conflicting region(s) are marked with <<<<<<< / ======= / >>>>>>>. Edit the merged text before applying.",
+                        self.conflicts
+                    ));
+                } else {
+                    ui.label("Both sets of changes merged cleanly.");
+                }
+                ui.add_space(5.0);
+
+                ui.columns(3, |columns| {
+                    let read_only = [(0, "Mine", &self.mine), (2, "Theirs (on disk)", &self.theirs)];
+                    for (column, heading, text) in read_only {
+                        let ui = &mut columns[column];
+                        ui.label(RichText::new(heading).strong());
+                        ScrollArea::vertical().id_source(heading).max_height(400.0).show(ui, |ui| {
+                            ui.add(TextEdit::multiline(&mut text.as_str()).font(TextStyle::Monospace));
+                        });
+                    }
+
+                    let ui = &mut columns[1];
+                    ui.label(RichText::new("Merged").strong());
+                    ScrollArea::vertical().id_source("merged").max_height(400.0).show(ui, |ui| {
+                        ui.add(TextEdit::multiline(&mut self.merged).font(TextStyle::Monospace));
+                    });
+                });
+
+                ui.add_space(5.0);
+                ui.horizontal(|ui| {
+                    if ui.button("Cancel").clicked() {
+                        outcome = Some(MergeOutcome::Cancel);
+                    }
+                    let unresolved = self.merged.lines().any(|l| l.starts_with("<<<<<<< ") || l.starts_with(">>>>>>> "));
+                    let apply = ui.add_enabled(!unresolved, egui::Button::new("Apply merge"));
+                    if unresolved {
+                        apply.on_disabled_hover_text("Remove the conflict markers first");
+                    } else if apply.clicked() {
+                        outcome = Some(MergeOutcome::Apply(self.merged.clone()));
+                    }
+                });
+            });
+
+        if !open {
+            outcome = Some(MergeOutcome::Cancel);
+        }
+        outcome
+    }
+}
@@ -0,0 +1,17 @@
+pub mod backlinks;
+pub mod diff_view;
+pub mod editor;
+pub mod find;
+pub mod graph;
+pub mod history;
+pub mod merge;
+pub mod note_tree;
+pub mod preview;
+pub mod properties;
+pub mod recovery;
+pub mod replace;
+pub mod search;
+pub mod settings;
+pub mod tag_pane;
+pub mod time;
+pub mod toasts;
@@ -0,0 +1,198 @@
+use eframe::egui::{self, CollapsingHeader, Context, Frame, Id, RichText, ScrollArea, Ui};
+use rusty_notes::{NoteId, NoteStore};
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+
+/// The sidebar's notes, inside a collapsible tree of their folders. Notes
+/// can be dragged onto a folder, or onto empty space for the top level.
+pub struct NoteTree<'a> {
+    store: &'a NoteStore,
+    /// Notes to list, in order; folders without any are hidden while searching.
+    notes: &'a [NoteId],
+    searching: bool,
+    open_tabs: &'a [NoteId],
+    current_tab: Option<NoteId>,
+}
+
+/// Folders are relative to the notes directory; the empty path is the top.
+pub enum TreeAction {
+    Open(NoteId),
+    Delete(NoteId),
+    Move(NoteId, PathBuf),
+    NewNote(PathBuf),
+    NewFolder(PathBuf),
+    RenameFolder(PathBuf),
+    DeleteFolder(PathBuf),
+}
+
+impl<'a> NoteTree<'a> {
+    pub fn new(store: &'a NoteStore, notes: &'a [NoteId], searching: bool) -> Self {
+        Self { store, notes, searching, open_tabs: &[], current_tab: None }
+    }
+
+    /// Open notes are shown in italics, the current one in bold.
+    pub fn highlight(mut self, open_tabs: &'a [NoteId], current_tab: Option<NoteId>) -> Self {
+        self.open_tabs = open_tabs;
+        self.current_tab = current_tab;
+        self
+    }
+
+    pub fn show(&self, ui: &mut Ui) -> Option<TreeAction> {
+        let mut by_folder: BTreeMap<PathBuf, Vec<NoteId>> = BTreeMap::new();
+        for note in self.notes.iter().filter_map(|&id| self.store.get(id)) {
+            by_folder.entry(self.store.folder_of(note)).or_default().push(note.id);
+        }
+
+        let mut action = None;
+        ScrollArea::vertical().show(ui, |ui| {
+            let (_, dropped) = ui.dnd_drop_zone::<NoteId, _>(Frame::none(), |ui| {
+                ui.set_min_width(ui.available_width());
+                self.show_folder(ui, Path::new(""), &by_folder, &mut action);
+                if self.notes.is_empty() {
+                    ui.label(if self.searching { "No notes match your search." } else { "No notes yet." });
+                }
+            });
+            if let Some(id) = dropped {
+                action = Some(TreeAction::Move(*id, PathBuf::new()));
+            }
+        });
+        action
+    }
+
+    fn show_folder(
+        &self,
+        ui: &mut Ui,
+        folder: &Path,
+        by_folder: &BTreeMap<PathBuf, Vec<NoteId>>,
+        action: &mut Option<TreeAction>,
+    ) {
+        let children = self.store.folders().iter().filter(|path| path.parent() == Some(folder));
+        for child in children {
+            if self.searching && !by_folder.keys().any(|path| path.starts_with(child)) {
+                continue;
+            }
+            let name = child.file_name().unwrap_or_default().to_string_lossy();
+            let response = CollapsingHeader::new(format!("📁 {}", name))
+                .id_source(("folder", child))
+                .open(self.searching.then_some(true))
+                .show(ui, |ui| self.show_folder(ui, child, by_folder, action));
+
+            let header = response.header_response;
+            if let Some(id) = header.dnd_release_payload::<NoteId>() {
+                *action = Some(TreeAction::Move(*id, child.clone()));
+            }
+            header.context_menu(|ui| {
+                let items = [
+                    ("📄 New note here", TreeAction::NewNote(child.clone())),
+                    ("📁 New folder…", TreeAction::NewFolder(child.clone())),
+                    ("✏ Rename…", TreeAction::RenameFolder(child.clone())),
+                    ("🗑 Delete folder…", TreeAction::DeleteFolder(child.clone())),
+                ];
+                for (text, item) in items {
+                    if ui.button(text).clicked() {
+                        *action = Some(item);
+                        ui.close_menu();
+                    }
+                }
+            });
+        }
+
+        for &id in by_folder.get(folder).into_iter().flatten() {
+            let Some(note) = self.store.get(id) else {
+                continue;
+            };
+            ui.dnd_drag_source(Id::new(("note_drag", id)), id, |ui| {
+                ui.horizontal(|ui| {
+                    let mut title_text = note.title.clone();
+                    if note.unsaved_changes {
+                        title_text.push('*');
+                    }
+                    let text = if self.current_tab == Some(id) {
+                        RichText::new(&title_text).strong()
+                    } else if self.open_tabs.contains(&id) {
+                        RichText::new(&title_text).italics()
+                    } else {
+                        RichText::new(&title_text)
+                    };
+
+                    if ui.button(text).on_hover_text("Open note; drag onto a folder to move it").clicked() {
+                        *action = Some(TreeAction::Open(id));
+                    }
+                    if ui.button("🗑").on_hover_text("Move to trash").clicked() {
+                        *action = Some(TreeAction::Delete(id));
+                    }
+                });
+            });
+        }
+    }
+}
+
+/// Asks for the name of a new folder, or the new name of one.
+pub struct FolderPrompt {
+    kind: PromptKind,
+    name: String,
+}
+
+enum PromptKind {
+    New(PathBuf),
+    Rename(PathBuf),
+}
+
+pub enum FolderOutcome {
+    /// Create a folder with this name inside the parent folder.
+    Create(PathBuf, String),
+    /// Give the folder this name.
+    Rename(PathBuf, String),
+    Cancel,
+}
+
+impl FolderPrompt {
+    /// A prompt for a new folder inside `parent`.
+    pub fn new_folder(parent: PathBuf) -> Self {
+        Self { kind: PromptKind::New(parent), name: String::new() }
+    }
+
+    pub fn rename(folder: PathBuf) -> Self {
+        let name = folder.file_name().unwrap_or_default().to_string_lossy().into_owned();
+        Self { kind: PromptKind::Rename(folder), name }
+    }
+
+    pub fn show(&mut self, ctx: &Context) -> Option<FolderOutcome> {
+        let title = match &self.kind {
+            PromptKind::New(_) => "New Folder",
+            PromptKind::Rename(_) => "Rename Folder",
+        };
+        let mut outcome = None;
+        let mut open = true;
+
+        egui::Window::new(title).open(&mut open).collapsible(false).resizable(false).show(ctx, |ui| {
+            if let PromptKind::New(parent) = &self.kind {
+                if !parent.as_os_str().is_empty() {
+                    ui.label(format!("Inside {}", parent.display()));
+                }
+            }
+            let edit = ui.text_edit_singleline(&mut self.name);
+            edit.request_focus();
+            let entered = edit.lost_focus() && ui.input(|i| i.key_pressed(egui::Key::Enter));
+            ui.add_space(5.0);
+            ui.horizontal(|ui| {
+                if ui.button("Cancel").clicked() {
+                    outcome = Some(FolderOutcome::Cancel);
+                }
+                let name = self.name.trim();
+                let confirm = ui.add_enabled(!name.is_empty(), egui::Button::new("OK"));
+                if !name.is_empty() && (confirm.clicked() || entered) {
+                    outcome = Some(match &self.kind {
+                        PromptKind::New(parent) => FolderOutcome::Create(parent.clone(), name.to_string()),
+                        PromptKind::Rename(folder) => FolderOutcome::Rename(folder.clone(), name.to_string()),
+                    });
+                }
+            });
+        });
+
+        if !open {
+            outcome = Some(FolderOutcome::Cancel);
+        }
+        outcome
+    }
+}
@@ -0,0 +1,239 @@
+use eframe::egui::{self, Color32, FontId, Frame, Grid, RichText, Stroke, Ui};
+use rusty_notes::markdown::{Block, Document, Inline, List, Table};
+use std::cell::RefCell;
+
+const HEADING_SIZES: [f32; 6] = [28.0, 24.0, 20.0, 18.0, 16.0, 14.0];
+const BULLETS: [&str; 3] = ["•", "◦", "▪"];
+
+/// Lays out a parsed Markdown document with native egui widgets.
+pub struct MarkdownPreview<'a> {
+    document: &'a Document,
+    /// Whether a wiki link's target note exists; links to missing notes are
+    /// drawn dimmed.
+    link_exists: Option<&'a dyn Fn(&str) -> bool>,
+    /// Target of the wiki link clicked while drawing.
+    clicked: RefCell<Option<String>>,
+}
+
+/// Text styling that inline formatting accumulates as it nests.
+#[derive(Clone, Copy, Default)]
+struct Style {
+    strong: bool,
+    italics: bool,
+    strikethrough: bool,
+    code: bool,
+    size: Option<f32>,
+}
+
+impl<'a> MarkdownPreview<'a> {
+    pub fn new(document: &'a Document) -> Self {
+        Self { document, link_exists: None, clicked: RefCell::new(None) }
+    }
+
+    pub fn link_exists(mut self, exists: &'a dyn Fn(&str) -> bool) -> Self {
+        self.link_exists = Some(exists);
+        self
+    }
+
+    /// Draws the document and returns the target of the wiki link clicked.
+    pub fn show(self, ui: &mut Ui) -> Option<String> {
+        self.blocks(ui, &self.document.blocks, 0);
+
+        if !self.document.footnotes.is_empty() {
+            ui.add_space(10.0);
+            ui.separator();
+            for (i, footnote) in self.document.footnotes.iter().enumerate() {
+                ui.horizontal_top(|ui| {
+                    ui.label(RichText::new(format!("{}.", i + 1)).small());
+                    ui.vertical(|ui| self.blocks(ui, &footnote.blocks, 0));
+                });
+            }
+        }
+        self.clicked.into_inner()
+    }
+
+    fn blocks(&self, ui: &mut Ui, blocks: &[Block], depth: usize) {
+        for block in blocks {
+            self.block(ui, block, depth);
+        }
+    }
+
+    fn block(&self, ui: &mut Ui, block: &Block, depth: usize) {
+        match block {
+            Block::Heading { level, content } => {
+                let size = HEADING_SIZES[(*level as usize).clamp(1, 6) - 1];
+                ui.add_space(size * 0.4);
+                let style = Style { strong: true, size: Some(size), ..Style::default() };
+                self.paragraph(ui, content, style);
+                ui.add_space(size * 0.2);
+            }
+            Block::Paragraph(content) => {
+                self.paragraph(ui, content, Style::default());
+                ui.add_space(6.0);
+            }
+            Block::CodeBlock { code, .. } => {
+                Frame::none()
+                    .fill(ui.visuals().code_bg_color)
+                    .inner_margin(6.0)
+                    .rounding(4.0)
+                    .show(ui, |ui| {
+                        ui.set_min_width(ui.available_width());
+                        ui.label(RichText::new(code.trim_end_matches('\n')).monospace());
+                    });
+                ui.add_space(6.0);
+            }
+            Block::BlockQuote(children) => {
+                let response = ui.horizontal_top(|ui| {
+                    ui.add_space(12.0);
+                    ui.vertical(|ui| self.blocks(ui, children, depth));
+                });
+                let rect = response.response.rect;
+                ui.painter().vline(
+                    rect.left() + 4.0,
+                    rect.y_range(),
+                    Stroke::new(3.0, ui.visuals().weak_text_color()),
+                );
+            }
+            Block::List(list) => self.list(ui, list, depth),
+            Block::Table(table) => {
+                self.table(ui, table);
+                ui.add_space(6.0);
+            }
+            Block::ThematicBreak => {
+                ui.separator();
+            }
+        }
+    }
+
+    fn list(&self, ui: &mut Ui, list: &List, depth: usize) {
+        for (i, item) in list.items.iter().enumerate() {
+            ui.horizontal_top(|ui| {
+                ui.add_space(8.0);
+                let marker = match list.start {
+                    Some(start) => format!("{}.", start + i as u64),
+                    None => BULLETS[depth % BULLETS.len()].to_string(),
+                };
+                ui.label(marker);
+                if let Some(mut checked) = item.task {
+                    ui.add_enabled(false, egui::Checkbox::without_text(&mut checked));
+                }
+                ui.vertical(|ui| {
+                    if list.tight {
+                        ui.spacing_mut().item_spacing.y = 2.0;
+                    }
+                    self.blocks(ui, &item.blocks, depth + 1);
+                });
+            });
+        }
+        if depth == 0 {
+            ui.add_space(6.0);
+        }
+    }
+
+    fn table(&self, ui: &mut Ui, table: &Table) {
+        // Grid ids must be unique per table within the preview
+        let id = ui.next_auto_id();
+        Grid::new(id).striped(true).spacing([12.0, 4.0]).show(ui, |ui| {
+            for cell in &table.header {
+                self.paragraph(ui, cell, Style { strong: true, ..Style::default() });
+            }
+            ui.end_row();
+            for row in &table.rows {
+                for cell in row {
+                    self.paragraph(ui, cell, Style::default());
+                }
+                ui.end_row();
+            }
+        });
+    }
+
+    /// Flows inline content left to right, wrapping at the panel edge.
+    fn paragraph(&self, ui: &mut Ui, inlines: &[Inline], style: Style) {
+        ui.horizontal_wrapped(|ui| {
+            ui.spacing_mut().item_spacing.x = 0.0;
+            self.inlines(ui, inlines, style);
+        });
+    }
+
+    fn inlines(&self, ui: &mut Ui, inlines: &[Inline], style: Style) {
+        for inline in inlines {
+            match inline {
+                Inline::Text(text) => {
+                    ui.label(rich_text(ui, text, style));
+                }
+                Inline::Code(code) => {
+                    ui.label(rich_text(ui, code, Style { code: true, ..style }));
+                }
+                Inline::Emphasis(children) => self.inlines(ui, children, Style { italics: true, ..style }),
+                Inline::Strong(children) => self.inlines(ui, children, Style { strong: true, ..style }),
+                Inline::Strikethrough(children) => {
+                    self.inlines(ui, children, Style { strikethrough: true, ..style })
+                }
+                Inline::Link { url, content, .. } => {
+                    let text = rusty_notes::markdown::plain_text(content);
+                    ui.hyperlink_to(rich_text(ui, &text, style), url).on_hover_text(url);
+                }
+                Inline::Image { url, alt, .. } => {
+                    ui.hyperlink_to(rich_text(ui, &format!("🖼 {}", alt), style), url)
+                        .on_hover_text(url);
+                }
+                Inline::WikiLink { target, alias } => {
+                    let label = alias.as_ref().unwrap_or(target);
+                    let exists = self.link_exists.is_none_or(|exists| exists(target));
+                    let mut text = rich_text(ui, label, style);
+                    if !exists {
+                        text = text.color(ui.visuals().weak_text_color());
+                    }
+                    let hover = if exists { format!("Open \"{}\"", target) } else { format!("Create \"{}\"", target) };
+                    if ui.link(text).on_hover_text(hover).clicked() {
+                        *self.clicked.borrow_mut() = Some(target.clone());
+                    }
+                }
+                Inline::FootnoteReference(label) => {
+                    let number = self
+                        .document
+                        .footnotes
+                        .iter()
+                        .position(|f| f.label.eq_ignore_ascii_case(label))
+                        .map_or(0, |i| i + 1);
+                    ui.label(RichText::new(format!("[{}]", number)).small_raised());
+                }
+                Inline::SoftBreak => {
+                    ui.label(rich_text(ui, " ", style));
+                }
+                Inline::HardBreak => ui.end_row(),
+            }
+        }
+    }
+}
+
+fn rich_text(ui: &Ui, text: &str, style: Style) -> RichText {
+    let mut rich = RichText::new(text);
+    if let Some(size) = style.size {
+        rich = rich.font(FontId::proportional(size));
+    }
+    if style.code {
+        rich = rich.code();
+    }
+    if style.strong {
+        rich = rich.strong();
+    }
+    if style.italics {
+        rich = rich.italics();
+    }
+    if style.strikethrough {
+        rich = rich.strikethrough();
+    }
+    if style.code && style.size.is_none() {
+        rich = rich.color(code_color(ui));
+    }
+    rich
+}
+
+fn code_color(ui: &Ui) -> Color32 {
+    if ui.visuals().dark_mode {
+        Color32::from_rgb(230, 180, 130)
+    } else {
+        Color32::from_rgb(160, 70, 30)
+    }
+}
@@ -0,0 +1,152 @@
+use eframe::egui::{self, CollapsingHeader, ComboBox, DragValue, Grid, TextEdit, TextStyle, Ui};
+use rusty_notes::{Note, NoteId, Value};
+use std::collections::HashMap;
+
+/// The front matter properties of the current note, above the editor.
+#[derive(Default)]
+pub struct PropertiesPanel {
+    id: Option<NoteId>,
+    /// What is being typed into fields that only take effect once they
+    /// parse, by property.
+    drafts: HashMap<String, String>,
+    new_key: String,
+    new_kind: Kind,
+}
+
+pub enum PropertyEdit {
+    Set(String, Value),
+    Remove(String),
+}
+
+#[derive(Clone, Copy, Default, PartialEq)]
+enum Kind {
+    #[default]
+    Text,
+    Number,
+    Bool,
+    Date,
+    List,
+}
+
+impl Kind {
+    const ALL: [(Kind, &'static str); 5] = [
+        (Kind::Text, "Text"),
+        (Kind::Number, "Number"),
+        (Kind::Bool, "Checkbox"),
+        (Kind::Date, "Date"),
+        (Kind::List, "List"),
+    ];
+
+    fn initial_value(self) -> Value {
+        match self {
+            Kind::Text => Value::Text(String::new()),
+            Kind::Number => Value::Number(0.0),
+            Kind::Bool => Value::Bool(false),
+            Kind::Date => Value::Date("1970-01-01".to_string()),
+            Kind::List => Value::List(vec![]),
+        }
+    }
+}
+
+impl PropertiesPanel {
+    /// Draws the properties of `note` and returns the edit made this frame.
+    pub fn show(&mut self, ui: &mut Ui, note: &Note) -> Option<PropertyEdit> {
+        if self.id != Some(note.id) {
+            *self = Self { id: Some(note.id), ..Self::default() };
+        }
+        let count = note.metadata.iter().count();
+        let mut edit = None;
+
+        CollapsingHeader::new(format!("Properties ({})", count))
+            .id_source(("properties", note.id))
+            .default_open(count > 0)
+            .show(ui, |ui| {
+                Grid::new(("properties_grid", note.id)).num_columns(3).spacing([10.0, 4.0]).show(ui, |ui| {
+                    for (key, value) in note.metadata.iter() {
+                        ui.label(key);
+                        if let Some(value) = self.show_value(ui, key, value) {
+                            edit = Some(PropertyEdit::Set(key.to_string(), value));
+                        }
+                        if ui.small_button("✖").on_hover_text("Remove property").clicked() {
+                            edit = Some(PropertyEdit::Remove(key.to_string()));
+                        }
+                        ui.end_row();
+                    }
+                });
+
+                ui.horizontal(|ui| {
+                    ui.add(TextEdit::singleline(&mut self.new_key).hint_text("New property").desired_width(120.0));
+                    let selected = Kind::ALL.iter().find(|(kind, _)| *kind == self.new_kind).map_or("", |(_, name)| *name);
+                    ComboBox::from_id_source(("property_kind", note.id)).selected_text(selected).show_ui(ui, |ui| {
+                        for (kind, name) in Kind::ALL {
+                            ui.selectable_value(&mut self.new_kind, kind, name);
+                        }
+                    });
+                    let key = self.new_key.trim();
+                    let taken = note.metadata.get(key).is_some();
+                    let valid = !key.is_empty() && !key.contains(':') && !key.starts_with(['#', '-']);
+                    let add = ui.add_enabled(valid && !taken, egui::Button::new("Add"));
+                    if taken {
+                        add.on_disabled_hover_text("The note already has this property");
+                    } else if add.clicked() {
+                        edit = Some(PropertyEdit::Set(key.to_string(), self.new_kind.initial_value()));
+                        self.new_key.clear();
+                    }
+                });
+            });
+        edit
+    }
+
+    // Typing goes to the draft until the field loses focus
+    fn keep_draft(&mut self, key: &str, draft: String, response: &egui::Response) {
+        if response.lost_focus() {
+            self.drafts.remove(key);
+        } else if response.changed() {
+            self.drafts.insert(key.to_string(), draft);
+        }
+    }
+
+    // The field for one property; returns its new value when it changes
+    fn show_value(&mut self, ui: &mut Ui, key: &str, value: &Value) -> Option<Value> {
+        match value {
+            Value::Text(text) => {
+                let mut text = text.clone();
+                ui.text_edit_singleline(&mut text).changed().then_some(Value::Text(text))
+            }
+            Value::Number(n) => {
+                let mut n = *n;
+                ui.add(DragValue::new(&mut n)).changed().then_some(Value::Number(n))
+            }
+            Value::Bool(b) => {
+                let mut b = *b;
+                ui.checkbox(&mut b, "").changed().then_some(Value::Bool(b))
+            }
+            Value::Date(date) => {
+                let mut draft = self.drafts.get(key).cloned().unwrap_or_else(|| date.clone());
+                let valid = matches!(Value::parse(&draft), Value::Date(_));
+                let mut field = TextEdit::singleline(&mut draft).hint_text("YYYY-MM-DD");
+                if !valid {
+                    field = field.text_color(ui.visuals().error_fg_color);
+                }
+                let response = ui.add(field);
+                self.keep_draft(key, draft.clone(), &response);
+                let parsed = Value::parse(&draft);
+                (response.changed() && matches!(parsed, Value::Date(_))).then_some(parsed)
+            }
+            Value::List(items) => {
+                let mut draft = self.drafts.get(key).cloned().unwrap_or_else(|| items.join(", "));
+                let response = ui.add(TextEdit::singleline(&mut draft).hint_text("Comma-separated"));
+                self.keep_draft(key, draft.clone(), &response);
+                let items = draft.split(',').map(str::trim).filter(|item| !item.is_empty()).map(String::from);
+                response.changed().then(|| Value::List(items.collect()))
+            }
+            Value::Raw(raw) => {
+                let mut raw = raw.clone();
+                let response = ui
+                    .add(TextEdit::multiline(&mut raw).font(TextStyle::Monospace).desired_rows(1))
+                    .on_hover_text("YAML, as written after the colon");
+                response.changed().then_some(Value::Raw(raw))
+            }
+        }
+    }
+}
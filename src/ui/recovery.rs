@@ -0,0 +1,97 @@
+use crate::ui::diff_view::{self, TextDiff};
+use crate::ui::time::format_age;
+use eframe::egui::{self, Context, RichText, ScrollArea};
+use rusty_notes::{NoteId, NoteStore, Recovery};
+use std::time::SystemTime;
+
+/// Lists notes with unsaved edits recovered from the swap folder, so the
+/// user can compare each with its file and restore or discard it.
+pub struct RecoveryView {
+    entries: Vec<Entry>,
+}
+
+struct Entry {
+    id: NoteId,
+    title: String,
+    saved: String,
+    recovered: String,
+    saved_at: SystemTime,
+    show_diff: bool,
+    // Worked out the first time the changes are shown
+    diff: Option<TextDiff>,
+}
+
+pub enum RecoveryAction {
+    /// Put the recovered text in the editor; it is saved like any edit.
+    Restore(NoteId, String),
+    Discard(NoteId),
+}
+
+impl RecoveryView {
+    pub fn new(store: &NoteStore, recoveries: Vec<Recovery>) -> Self {
+        let entries = recoveries
+            .into_iter()
+            .filter_map(|recovery| {
+                let note = store.get(recovery.id)?;
+                Some(Entry {
+                    id: recovery.id,
+                    title: note.title.clone(),
+                    saved: note.content.clone(),
+                    recovered: recovery.content,
+                    saved_at: recovery.saved_at,
+                    show_diff: false,
+                    diff: None,
+                })
+            })
+            .collect();
+        Self { entries }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Draws the window and returns the choices made this frame. Closing
+    /// the window keeps the remaining copies for the next start.
+    pub fn show(&mut self, ctx: &Context, open: &mut bool) -> Vec<RecoveryAction> {
+        let mut actions = vec![];
+
+        egui::Window::new("Recover Unsaved Edits")
+            .open(open)
+            .default_width(600.0)
+            .show(ctx, |ui| {
+                ui.label("These notes have edits that were never saved, probably because the app did not close normally.");
+                ui.add_space(5.0);
+                ScrollArea::vertical().max_height(500.0).show(ui, |ui| {
+                    self.entries.retain_mut(|entry| {
+                        let mut keep = true;
+                        ui.separator();
+                        ui.horizontal(|ui| {
+                            ui.label(RichText::new(&entry.title).strong());
+                            if let Ok(age) = entry.saved_at.elapsed() {
+                                ui.label(RichText::new(format!("edited {} ago", format_age(age.as_secs()))).weak());
+                            }
+                        });
+                        ui.horizontal(|ui| {
+                            if ui.button("Restore").clicked() {
+                                actions.push(RecoveryAction::Restore(entry.id, entry.recovered.clone()));
+                                keep = false;
+                            }
+                            if ui.button("Discard").clicked() {
+                                actions.push(RecoveryAction::Discard(entry.id));
+                                keep = false;
+                            }
+                            ui.toggle_value(&mut entry.show_diff, "Show changes");
+                        });
+                        if entry.show_diff {
+                            let diff = entry.diff.get_or_insert_with(|| TextDiff::new(&entry.saved, &entry.recovered));
+                            diff_view::show_unified(ui, diff);
+                        }
+                        keep
+                    });
+                });
+            });
+
+        actions
+    }
+}
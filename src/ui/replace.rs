@@ -0,0 +1,94 @@
+use eframe::egui::text::LayoutJob;
+use eframe::egui::{self, Button, Context, ScrollArea, Stroke, TextFormat, TextStyle, Ui};
+use rusty_notes::{Change, Finder, NoteChanges, NoteStore};
+
+/// Every change replacing a pattern in all notes would make, line by line,
+/// with a checkbox per note to leave it out.
+pub struct ReplaceView {
+    replacement: String,
+    /// The changes to each note, and whether to make them.
+    notes: Vec<(NoteChanges, bool)>,
+}
+
+pub enum ReplaceOutcome {
+    Apply(Vec<NoteChanges>),
+    Close,
+}
+
+impl ReplaceView {
+    pub fn new(store: &NoteStore, finder: &Finder, replacement: &str) -> Self {
+        let notes = store.replacements(finder, replacement).into_iter().map(|changes| (changes, true)).collect();
+        Self { replacement: replacement.to_string(), notes }
+    }
+
+    pub fn show(&mut self, ctx: &Context, store: &NoteStore) -> Option<ReplaceOutcome> {
+        let mut outcome = None;
+        let mut open = true;
+
+        egui::Window::new("Replace in all notes")
+            .open(&mut open)
+            .default_size([700.0, 500.0])
+            .show(ctx, |ui| {
+                if self.notes.is_empty() {
+                    ui.label("No note has a match.");
+                    return;
+                }
+                let count: usize = self.notes.iter().map(|(note, _)| note.changes.len()).sum();
+                let notes = self.notes.len();
+                ui.label(format!("{} matches in {} notes, to be replaced with \"{}\":", count, notes, self.replacement));
+                ui.add_space(5.0);
+                ScrollArea::vertical().id_source("replace_preview").max_height(400.0).show(ui, |ui| {
+                    for (note, include) in &mut self.notes {
+                        let title = store.get(note.id).map(|note| note.title.clone()).unwrap_or_default();
+                        ui.checkbox(include, format!("{} ({})", title, note.changes.len()));
+                        ui.indent(("replace_changes", note.id), |ui| {
+                            ui.add_enabled_ui(*include, |ui| {
+                                for change in &note.changes {
+                                    show_change(ui, change);
+                                }
+                            });
+                        });
+                        ui.add_space(4.0);
+                    }
+                });
+                ui.add_space(5.0);
+                let chosen = self.notes.iter().filter(|(_, include)| *include);
+                let chosen_count = chosen.clone().count();
+                ui.horizontal(|ui| {
+                    if ui.button("Cancel").clicked() {
+                        outcome = Some(ReplaceOutcome::Close);
+                    }
+                    let text = format!("Replace in {} notes", chosen_count);
+                    if ui.add_enabled(chosen_count > 0, Button::new(text)).clicked() {
+                        outcome = Some(ReplaceOutcome::Apply(chosen.map(|(note, _)| note.clone()).collect()));
+                    }
+                });
+            });
+
+        if !open {
+            outcome = Some(ReplaceOutcome::Close);
+        }
+        outcome
+    }
+}
+
+// The line with the match struck out and its replacement after it
+fn show_change(ui: &mut Ui, change: &Change) {
+    let snippet = &change.snippet;
+    let font_id = TextStyle::Body.resolve(ui.style());
+    let plain = TextFormat { font_id, color: ui.visuals().text_color(), ..Default::default() };
+    let error = ui.visuals().error_fg_color;
+    let removed = TextFormat { strikethrough: Stroke::new(1.0, error), color: error, ..plain.clone() };
+    let added = TextFormat {
+        color: ui.visuals().strong_text_color(),
+        background: ui.visuals().selection.bg_fill,
+        ..plain.clone()
+    };
+    let mut job = LayoutJob::default();
+    job.append(&format!("{}: ", snippet.line), 0.0, TextFormat { color: ui.visuals().weak_text_color(), ..plain.clone() });
+    job.append(&snippet.text[..snippet.highlight.start], 0.0, plain.clone());
+    job.append(&snippet.text[snippet.highlight.clone()], 0.0, removed);
+    job.append(&change.replacement, 0.0, added);
+    job.append(&snippet.text[snippet.highlight.end..], 0.0, plain);
+    ui.label(job);
+}
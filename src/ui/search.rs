@@ -0,0 +1,95 @@
+use crate::ui::backlinks::show_snippet;
+use eframe::egui::{ScrollArea, Ui};
+use rusty_notes::{NoteId, NoteStore, SearchResult, SortOrder};
+use std::collections::HashSet;
+use std::ops::Range;
+
+/// Occurrences listed per note until the rest are asked for.
+const HITS_SHOWN: usize = 5;
+
+/// Every note the sidebar search finds, each with the lines the words
+/// searched for are on. Results are kept until the search or the notes
+/// change, unsaved edits included, since finding the words in every note
+/// each frame would not keep up with a large vault.
+#[derive(Default)]
+pub struct SearchView {
+    key: Option<(String, SortOrder, Option<String>, u64)>,
+    results: Vec<SearchResult>,
+    /// Notes with all their occurrences listed.
+    expanded: HashSet<NoteId>,
+}
+
+pub enum SearchAction {
+    Open(NoteId),
+    /// Open the note with this byte range of its body selected.
+    Jump(NoteId, Range<usize>),
+}
+
+impl SearchView {
+    /// Lists the notes matching `query` that have `tag`, if given.
+    pub fn show(
+        &mut self,
+        ui: &mut Ui,
+        store: &NoteStore,
+        query: &str,
+        order: SortOrder,
+        tag: Option<&str>,
+    ) -> Option<SearchAction> {
+        let key = (query.to_string(), order, tag.map(str::to_string), store.revision());
+        if self.key.as_ref() != Some(&key) {
+            self.results = store.search(query, order);
+            if let Some(tag) = tag {
+                self.results.retain(|result| store.get(result.id).is_some_and(|note| note.has_tag(tag)));
+            }
+            self.key = Some(key);
+        }
+
+        let hit_count: usize = self.results.iter().map(|result| result.hits.len()).sum();
+        ui.horizontal(|ui| {
+            ui.heading("Search results");
+            ui.weak(format!("{} notes, {} matches", self.results.len(), hit_count));
+        });
+        ui.separator();
+        if query.trim().is_empty() && tag.is_none() {
+            ui.weak("Type in the search box to find notes.");
+            return None;
+        }
+        if self.results.is_empty() {
+            ui.weak("No notes match.");
+            return None;
+        }
+
+        let mut action = None;
+        ScrollArea::vertical().id_source("search_results").auto_shrink([false, false]).show(ui, |ui| {
+            for result in &self.results {
+                let title = store.get(result.id).map(|note| note.title.clone()).unwrap_or_default();
+                ui.horizontal(|ui| {
+                    if ui.link(title).on_hover_text("Open note").clicked() {
+                        action = Some(SearchAction::Open(result.id));
+                    }
+                    if !result.hits.is_empty() {
+                        ui.weak(format!("({})", result.hits.len()));
+                    }
+                });
+                ui.indent(("search_hits", result.id), |ui| {
+                    let expanded = self.expanded.contains(&result.id);
+                    let shown = if expanded { result.hits.len() } else { HITS_SHOWN };
+                    for hit in result.hits.iter().take(shown) {
+                        if show_snippet(ui, &hit.snippet) {
+                            action = Some(SearchAction::Jump(result.id, hit.range.clone()));
+                        }
+                    }
+                    if result.hits.len() > HITS_SHOWN {
+                        let more = result.hits.len() - HITS_SHOWN;
+                        let text = if expanded { "Show fewer".to_string() } else { format!("{} more", more) };
+                        if ui.small_button(text).clicked() && !self.expanded.remove(&result.id) {
+                            self.expanded.insert(result.id);
+                        }
+                    }
+                });
+                ui.add_space(4.0);
+            }
+        });
+        action
+    }
+}
@@ -0,0 +1,164 @@
+use eframe::egui::{self, ComboBox, Context, DragValue, Grid, Key, KeyboardShortcut, Modifiers, RichText, Slider};
+use rusty_notes::settings::{Settings, Theme};
+use rusty_notes::vault;
+use rusty_notes::SortOrder;
+
+/// Reads a shortcut written like `Ctrl+Shift+N`. `Ctrl` matches Cmd on macOS.
+pub fn parse_shortcut(text: &str) -> Option<KeyboardShortcut> {
+    let (modifier_names, key_name) = text.rsplit_once('+').unwrap_or(("", text));
+    let key = Key::from_name(key_name.trim())?;
+    let mut modifiers = Modifiers::NONE;
+    for name in modifier_names.split('+').map(str::trim).filter(|name| !name.is_empty()) {
+        modifiers = modifiers
+            | match name.to_lowercase().as_str() {
+                "ctrl" | "cmd" | "command" => Modifiers::COMMAND,
+                "shift" => Modifiers::SHIFT,
+                "alt" | "option" => Modifiers::ALT,
+                _ => return None,
+            };
+    }
+    Some(KeyboardShortcut::new(modifiers, key))
+}
+
+/// Editable copy of the settings; nothing changes until Save is clicked.
+pub struct SettingsWindow {
+    draft: Settings,
+    default_vault: String,
+}
+
+pub enum SettingsOutcome {
+    Save(Box<Settings>),
+    Cancel,
+}
+
+impl SettingsWindow {
+    pub fn new(settings: &Settings) -> Self {
+        let default_vault = settings
+            .default_vault
+            .as_ref()
+            .map(|dir| dir.display().to_string())
+            .unwrap_or_default();
+        Self { draft: settings.clone(), default_vault }
+    }
+
+    pub fn show(&mut self, ctx: &Context) -> Option<SettingsOutcome> {
+        let mut outcome = None;
+        let mut open = true;
+
+        egui::Window::new("Settings")
+            .open(&mut open)
+            .collapsible(false)
+            .resizable(false)
+            .show(ctx, |ui| {
+                let draft = &mut self.draft;
+                Grid::new("settings_grid").num_columns(2).spacing([20.0, 8.0]).show(ui, |ui| {
+                    ui.label("Theme");
+                    ui.horizontal(|ui| {
+                        ui.radio_value(&mut draft.theme, Theme::Dark, "Dark");
+                        ui.radio_value(&mut draft.theme, Theme::Light, "Light");
+                    });
+                    ui.end_row();
+
+                    ui.label("Autosave after");
+                    ui.add(DragValue::new(&mut draft.autosave_interval_secs).clamp_range(5..=3600).suffix(" s"));
+                    ui.end_row();
+
+                    ui.label("On close");
+                    ui.checkbox(&mut draft.save_on_close, "Save all notes without asking");
+                    ui.end_row();
+
+                    ui.label("Default vault");
+                    ui.text_edit_singleline(&mut self.default_vault)
+                        .on_hover_text("Leave empty to open the most recently used vault");
+                    ui.end_row();
+
+                    ui.label("Editor font size");
+                    ui.add(Slider::new(&mut draft.editor_font_size, 8.0..=32.0));
+                    ui.end_row();
+
+                    ui.label("Interface font size");
+                    ui.add(Slider::new(&mut draft.interface_font_size, 8.0..=32.0));
+                    ui.end_row();
+
+                    ui.label("Sort notes by");
+                    let orders = [
+                        (SortOrder::Title, "Title (A–Z)"),
+                        (SortOrder::TitleDescending, "Title (Z–A)"),
+                        (SortOrder::Modified, "Last modified"),
+                    ];
+                    let selected = orders.iter().find(|(order, _)| *order == draft.sort_order).map_or("", |(_, name)| *name);
+                    ComboBox::from_id_source("sort_order").selected_text(selected).show_ui(ui, |ui| {
+                        for (order, name) in orders {
+                            ui.selectable_value(&mut draft.sort_order, order, name);
+                        }
+                    });
+                    ui.end_row();
+
+                    ui.label("Version history");
+                    ui.horizontal(|ui| {
+                        ui.label("keep");
+                        ui.add(DragValue::new(&mut draft.history.max_versions).clamp_range(0..=10_000));
+                        ui.label("versions for");
+                        ui.add(DragValue::new(&mut draft.history.max_age_days).clamp_range(0..=3650).suffix(" days"));
+                    })
+                    .response
+                    .on_hover_text("0 means no limit. The latest version is always kept.");
+                    ui.end_row();
+
+                    ui.label("Empty trash after");
+                    ui.add(DragValue::new(&mut draft.trash_retention_days).clamp_range(0..=3650).suffix(" days"))
+                        .on_hover_text("0 keeps deleted notes until you purge them.");
+                    ui.end_row();
+                });
+
+                ui.add_space(10.0);
+                ui.label(RichText::new("Keyboard shortcuts").strong());
+                let mut all_valid = true;
+                Grid::new("keybindings_grid").num_columns(2).spacing([20.0, 8.0]).show(ui, |ui| {
+                    let bindings = &mut draft.keybindings;
+                    let rows = [
+                        ("New note", &mut bindings.new_note),
+                        ("Save", &mut bindings.save),
+                        ("Toggle preview", &mut bindings.toggle_preview),
+                        ("Toggle graph", &mut bindings.toggle_graph),
+                        ("Find in note", &mut bindings.find),
+                        ("Replace in note", &mut bindings.replace),
+                        ("Close tab", &mut bindings.close_tab),
+                    ];
+                    for (name, binding) in rows {
+                        ui.label(name);
+                        ui.horizontal(|ui| {
+                            ui.add(egui::TextEdit::singleline(binding).desired_width(120.0));
+                            if parse_shortcut(binding).is_none() {
+                                all_valid = false;
+                                ui.colored_label(ui.visuals().error_fg_color, "Not a shortcut, e.g. Ctrl+Shift+N");
+                            }
+                        });
+                        ui.end_row();
+                    }
+                });
+
+                ui.add_space(10.0);
+                ui.horizontal(|ui| {
+                    if ui.button("Cancel").clicked() {
+                        outcome = Some(SettingsOutcome::Cancel);
+                    }
+                    if ui.button("Reset to defaults").clicked() {
+                        *self = Self::new(&Settings::default());
+                    }
+                    let save = ui.add_enabled(all_valid, egui::Button::new("Save"));
+                    if save.clicked() {
+                        let mut settings = self.draft.clone();
+                        let default_vault = self.default_vault.trim();
+                        settings.default_vault = (!default_vault.is_empty()).then(|| vault::expand_home(default_vault));
+                        outcome = Some(SettingsOutcome::Save(Box::new(settings)));
+                    }
+                });
+            });
+
+        if !open {
+            outcome = Some(SettingsOutcome::Cancel);
+        }
+        outcome
+    }
+}
@@ -0,0 +1,59 @@
+use eframe::egui::{collapsing_header::CollapsingState, Id, ScrollArea, Ui};
+use std::collections::BTreeMap;
+
+/// Every tag in the vault with how many notes have it, nested tags inside
+/// their parents. Clicking a tag selects it; clicking it again clears it.
+pub struct TagPane<'a> {
+    counts: &'a BTreeMap<String, usize>,
+    selected: Option<&'a str>,
+}
+
+impl<'a> TagPane<'a> {
+    pub fn new(counts: &'a BTreeMap<String, usize>, selected: Option<&'a str>) -> Self {
+        Self { counts, selected }
+    }
+
+    /// Returns the tag clicked this frame.
+    pub fn show(&self, ui: &mut Ui) -> Option<String> {
+        let mut clicked = None;
+        ScrollArea::vertical().id_source("tag_pane").auto_shrink([false, true]).show(ui, |ui| {
+            if self.counts.is_empty() {
+                ui.weak("No tags yet. Add #tags to your notes.");
+            }
+            self.show_children(ui, None, &mut clicked);
+        });
+        clicked
+    }
+
+    fn show_children(&self, ui: &mut Ui, parent: Option<&str>, clicked: &mut Option<String>) {
+        let children = self.counts.iter().filter(|(tag, _)| match parent {
+            Some(parent) => tag.strip_prefix(parent).and_then(|rest| rest.strip_prefix('/')).is_some_and(|rest| !rest.contains('/')),
+            None => !tag.contains('/'),
+        });
+        for (tag, count) in children {
+            let name = tag.rsplit('/').next().unwrap_or(tag);
+            let text = format!("#{} ({})", name, count);
+            let selected = self.selected == Some(tag.as_str());
+            let prefix = format!("{}/", tag);
+            let has_children = self.counts.range(prefix.clone()..).next().is_some_and(|(t, _)| t.starts_with(&prefix));
+
+            if has_children {
+                // Open while the selection is inside, so that it stays visible
+                let inside = self.selected.is_some_and(|s| s.starts_with(&prefix));
+                let mut state = CollapsingState::load_with_default_open(ui.ctx(), Id::new(("tag", tag)), false);
+                if inside {
+                    state.set_open(true);
+                }
+                state
+                    .show_header(ui, |ui| {
+                        if ui.selectable_label(selected, text).clicked() {
+                            *clicked = Some(tag.clone());
+                        }
+                    })
+                    .body(|ui| self.show_children(ui, Some(tag), clicked));
+            } else if ui.selectable_label(selected, text).clicked() {
+                *clicked = Some(tag.clone());
+            }
+        }
+    }
+}
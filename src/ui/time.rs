@@ -0,0 +1,20 @@
+use rusty_notes::date::civil_from_days;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// Rough age such as "5 min" for a number of seconds.
+pub fn format_age(secs: u64) -> String {
+    match secs {
+        0..=59 => format!("{} s", secs),
+        60..=3599 => format!("{} min", secs / 60),
+        3600..=86399 => format!("{} h", secs / 3600),
+        _ => format!("{} days", secs / 86400),
+    }
+}
+
+/// Date and time to the minute, in UTC, e.g. "2024-03-09 14:05 UTC".
+pub fn format_datetime(time: SystemTime) -> String {
+    let secs = time.duration_since(UNIX_EPOCH).map_or(0, |since| since.as_secs());
+    let (year, month, day) = civil_from_days((secs / 86400) as i64);
+    let minutes = secs % 86400 / 60;
+    format!("{:04}-{:02}-{:02} {:02}:{:02} UTC", year, month, day, minutes / 60, minutes % 60)
+}
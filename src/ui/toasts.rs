@@ -0,0 +1,87 @@
+use eframe::egui::{self, Align2, Context, Frame, RichText};
+use std::time::{Duration, Instant};
+
+// Informational toasts go away on their own; errors stay until dismissed
+const INFO_LIFETIME: Duration = Duration::from_secs(4);
+
+/// Notifications stacked in the bottom-right corner. An error toast can
+/// carry an action of type `A` that its Retry button hands back.
+pub struct Toasts<A> {
+    toasts: Vec<Toast<A>>,
+}
+
+struct Toast<A> {
+    message: String,
+    error: bool,
+    retry: Option<A>,
+    shown_at: Instant,
+}
+
+impl<A> Default for Toasts<A> {
+    fn default() -> Self {
+        Self { toasts: vec![] }
+    }
+}
+
+impl<A: Clone> Toasts<A> {
+    pub fn info(&mut self, message: impl Into<String>) {
+        self.push(message.into(), false, None);
+    }
+
+    pub fn error(&mut self, message: impl Into<String>, retry: Option<A>) {
+        self.push(message.into(), true, retry);
+    }
+
+    // A failure that repeats (autosave, disk sync) refreshes its toast
+    // instead of stacking copies of it
+    fn push(&mut self, message: String, error: bool, retry: Option<A>) {
+        self.toasts.retain(|toast| toast.message != message);
+        self.toasts.push(Toast { message, error, retry, shown_at: Instant::now() });
+    }
+
+    /// Draws the toasts and returns the action of a clicked Retry button.
+    pub fn show(&mut self, ctx: &Context) -> Option<A> {
+        self.toasts.retain(|toast| toast.error || toast.shown_at.elapsed() < INFO_LIFETIME);
+        if self.toasts.is_empty() {
+            return None;
+        }
+        if self.toasts.iter().any(|toast| !toast.error) {
+            ctx.request_repaint_after(Duration::from_millis(250));
+        }
+
+        let mut retry = None;
+        let mut dismissed = None;
+        egui::Area::new(egui::Id::new("toasts"))
+            .anchor(Align2::RIGHT_BOTTOM, [-10.0, -30.0])
+            .order(egui::Order::Foreground)
+            .show(ctx, |ui| {
+                ui.set_max_width(360.0);
+                for (i, toast) in self.toasts.iter().enumerate() {
+                    Frame::popup(ui.style()).show(ui, |ui| {
+                        ui.horizontal(|ui| {
+                            if ui.small_button("✖").on_hover_text("Dismiss").clicked() {
+                                dismissed = Some(i);
+                            }
+                            if let Some(action) = &toast.retry {
+                                if ui.small_button("Retry").clicked() {
+                                    retry = Some(action.clone());
+                                    dismissed = Some(i);
+                                }
+                            }
+                            let text = RichText::new(&toast.message);
+                            if toast.error {
+                                ui.label(text.color(ui.visuals().error_fg_color));
+                            } else {
+                                ui.label(text);
+                            }
+                        });
+                    });
+                }
+            });
+
+        if let Some(i) = dismissed {
+            self.toasts.remove(i);
+        }
+        retry
+    }
+}
@@ -0,0 +1,100 @@
+//! Vaults are folders of notes. The app opens one at a time and remembers
+//! the ones used recently.
+
+use crate::backend::write_atomic;
+use crate::config;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+const RECENT_FILE: &str = "recent_vaults";
+const MAX_RECENT: usize = 10;
+
+/// Folder used when no vault was given and none was used before.
+pub fn default_vault() -> Option<PathBuf> {
+    config::home_dir().map(|home| home.join("Notes"))
+}
+
+/// Turns a path typed by the user into one to open, expanding a leading `~`.
+pub fn expand_home(input: &str) -> PathBuf {
+    let input = input.trim();
+    let rest = input.strip_prefix("~/").or_else(|| input.strip_prefix("~\\"));
+    match (input == "~", rest, config::home_dir()) {
+        (true, _, Some(home)) => home,
+        (_, Some(rest), Some(home)) => home.join(rest),
+        _ => PathBuf::from(input),
+    }
+}
+
+/// Short name of a vault for menus: its folder name.
+pub fn vault_name(dir: &Path) -> String {
+    dir.file_name()
+        .map(|name| name.to_string_lossy().into_owned())
+        .unwrap_or_else(|| dir.display().to_string())
+}
+
+/// Recently opened vaults, most recent first, kept one path per line in
+/// the configuration directory.
+#[derive(Default)]
+pub struct RecentVaults {
+    file: Option<PathBuf>,
+    vaults: Vec<PathBuf>,
+}
+
+impl RecentVaults {
+    /// Loads the list from the configuration directory. A missing or
+    /// unreadable file gives an empty list.
+    pub fn load() -> Self {
+        match config::config_dir() {
+            Some(dir) => Self::load_from(dir.join(RECENT_FILE)),
+            None => Self::default(),
+        }
+    }
+
+    pub fn load_from(file: PathBuf) -> Self {
+        let vaults = fs::read_to_string(&file)
+            .unwrap_or_default()
+            .lines()
+            .filter(|line| !line.is_empty())
+            .map(PathBuf::from)
+            .take(MAX_RECENT)
+            .collect();
+        Self { file: Some(file), vaults }
+    }
+
+    pub fn list(&self) -> &[PathBuf] {
+        &self.vaults
+    }
+
+    pub fn most_recent(&self) -> Option<&Path> {
+        self.vaults.first().map(PathBuf::as_path)
+    }
+
+    /// Moves `dir` to the top of the list and saves it.
+    pub fn touch(&mut self, dir: &Path) -> io::Result<()> {
+        self.vaults.retain(|vault| vault != dir);
+        self.vaults.insert(0, dir.to_path_buf());
+        self.vaults.truncate(MAX_RECENT);
+        self.save()
+    }
+
+    pub fn forget(&mut self, dir: &Path) -> io::Result<()> {
+        self.vaults.retain(|vault| vault != dir);
+        self.save()
+    }
+
+    fn save(&self) -> io::Result<()> {
+        let Some(file) = &self.file else {
+            return Ok(());
+        };
+        if let Some(parent) = file.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        let mut contents = String::new();
+        for vault in &self.vaults {
+            contents.push_str(&vault.to_string_lossy());
+            contents.push('\n');
+        }
+        write_atomic(file, contents.as_bytes())
+    }
+}
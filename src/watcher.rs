@@ -0,0 +1,51 @@
+//! Notices changes other programs make in the notes directory, so that it
+//! only has to be walked when something did change.
+
+use notify::{Event, RecommendedWatcher, RecursiveMode, Watcher};
+use std::fs;
+use std::path::{Component, Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+
+pub struct DiskWatcher {
+    // Watches for as long as it lives
+    _watcher: RecommendedWatcher,
+    changed: Arc<AtomicBool>,
+}
+
+impl DiskWatcher {
+    /// Watches `dir` and every folder below it. The hidden folders and
+    /// files the store keeps its own records in are left out.
+    pub fn new(dir: &Path) -> notify::Result<Self> {
+        let changed = Arc::new(AtomicBool::new(false));
+        let flag = Arc::clone(&changed);
+        // Events may name paths as given or with links resolved
+        let roots: Vec<PathBuf> = [Some(dir.to_path_buf()), fs::canonicalize(dir).ok()].into_iter().flatten().collect();
+        let mut watcher = notify::recommended_watcher(move |event: notify::Result<Event>| {
+            // An error may mean events were lost, so look anyway
+            let relevant = event.map_or(true, |event| {
+                !event.kind.is_access() && event.paths.iter().any(|path| !is_hidden(&roots, path))
+            });
+            if relevant {
+                flag.store(true, Ordering::Relaxed);
+            }
+        })?;
+        watcher.watch(dir, RecursiveMode::Recursive)?;
+        Ok(Self { _watcher: watcher, changed })
+    }
+
+    /// Whether anything changed since the last call.
+    pub fn take_changed(&self) -> bool {
+        self.changed.swap(false, Ordering::Relaxed)
+    }
+}
+
+// Whether `path` is, or is inside, a hidden entry of the notes directory
+fn is_hidden(roots: &[PathBuf], path: &Path) -> bool {
+    let Some(relative) = roots.iter().find_map(|root| path.strip_prefix(root).ok()) else {
+        return false;
+    };
+    relative
+        .components()
+        .any(|component| matches!(component, Component::Normal(name) if name.to_string_lossy().starts_with('.')))
+}